@@ -0,0 +1,40 @@
+#![feature(test)]
+#![feature(thread_id_value)]
+
+extern crate test;
+
+use test::{black_box, Bencher};
+
+timeloop::create_profiler!();
+
+#[bench]
+fn empty(b: &mut Bencher) {
+    b.iter(|| black_box(0));
+}
+
+#[bench]
+fn scoped_timer(b: &mut Bencher) {
+    b.iter(|| {
+        timeloop::scoped_timer!("scoped_timer");
+        black_box(0)
+    });
+}
+
+#[bench]
+fn bandwidth_timer(b: &mut Bencher) {
+    b.iter(|| {
+        timeloop::scoped_bandwidth_timer!("bandwidth_timer", black_box(64));
+        black_box(0)
+    });
+}
+
+#[bench]
+fn nested_timers(b: &mut Bencher) {
+    b.iter(|| {
+        timeloop::scoped_timer!("outer");
+        {
+            timeloop::scoped_timer!("inner");
+            black_box(0)
+        }
+    });
+}
@@ -28,6 +28,12 @@ fn main() {
         10
     });
 
+    // Example of the chain_timers! macro for back to back stages
+    timeloop::chain_timers! {
+        "stage1" => let stage1 = value * 2;
+        "stage2" => std::thread::sleep(Duration::from_millis(stage1));
+    }
+
     // Print the timer state
     timeloop::print!();
 
@@ -0,0 +1,15 @@
+use std::time::Duration;
+
+#[allow(clippy::cast_possible_truncation)]
+fn main() {
+    const SIZE: usize = 64 * 1024 * 1024;
+
+    // Compare filling a 64 MB buffer with each allocation strategy
+    let comparison = timeloop::compare_buffer_strategies(Duration::from_secs(2), SIZE, |buffer| {
+        for i in 0..SIZE / 4096 {
+            buffer.extend_from_slice(&[i as u8; 4096]);
+        }
+    });
+
+    comparison.print();
+}
@@ -0,0 +1,4 @@
+fn main() {
+    // Measure the cost of each timer kind on this machine
+    timeloop::overhead_report().print();
+}
@@ -51,6 +51,9 @@ fn top() {
 fn main() {
     timeloop::start_profiler!();
 
+    // Attribute the time to the full timer stacks for the folded stacks below
+    timeloop::record_stacks!();
+
     let start = std::time::Instant::now();
 
     timeloop::time_work!("Total", {
@@ -62,4 +65,11 @@ fn main() {
 
     // Print the timer state
     timeloop::print!();
+
+    // Write the timer stacks to be rendered by inferno or flamegraph.pl
+    timeloop::write_folded_stacks!(std::fs::File::create("/tmp/timeloop.folded").unwrap())
+        .expect("Failed to write folded stacks");
+
+    // Print which functions have been instrumented
+    timeloop::print_coverage!(&["main", "top", "first", "second"]);
 }
@@ -0,0 +1,37 @@
+#![feature(thread_id_value)]
+
+use std::time::Duration;
+
+timeloop::create_profiler!();
+
+fn work(i: u64) -> u64 {
+    timeloop::time_work!("Work", {
+        std::thread::sleep(Duration::from_millis(10 * i));
+        i
+    })
+}
+
+fn main() {
+    timeloop::start_profiler!();
+
+    // Record every timer hit to show the thread lifetimes in the trace
+    timeloop::record_events!();
+
+    // Each thread is timed from spawn to join without any manual timers
+    let threads: Vec<_> = (1..=4)
+        .map(|i| timeloop::spawn!("Worker", move || work(i)))
+        .collect();
+
+    let total: u64 = threads
+        .into_iter()
+        .map(|thread| thread.join().unwrap())
+        .sum();
+
+    println!("Total: {total}");
+
+    // Print the timer state
+    timeloop::print!();
+
+    // Write the recorded events as a chrome trace
+    timeloop::write_chrome_trace!("/tmp/timeloop_spawn_trace.json").expect("Failed to write trace");
+}
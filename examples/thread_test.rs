@@ -69,6 +69,12 @@ fn thread_func(i: usize) {
 fn main() {
     timeloop::start_profiler!();
 
+    // Record every timer hit to view in chrome://tracing
+    timeloop::record_events!();
+
+    // Keep a few concrete hits of every timer to show in the report
+    timeloop::record_exemplars!();
+
     let start = std::time::Instant::now();
 
     for k in 0..4 {
@@ -95,4 +101,13 @@ fn main() {
 
     // Print the timer state
     timeloop::print!();
+
+    // Write the recorded events as a chrome trace
+    timeloop::write_chrome_trace!("/tmp/timeloop_trace.json").expect("Failed to write trace");
+
+    // Write the recorded events for speedscope.app
+    timeloop::write_speedscope!("/tmp/timeloop_speedscope.json").expect("Failed to write profile");
+
+    // Write a report to share with others
+    timeloop::write_html_report!("/tmp/timeloop_report.html").expect("Failed to write report");
 }
@@ -0,0 +1,32 @@
+#![feature(thread_id_value)]
+
+use std::time::Duration;
+
+timeloop::create_profiler!();
+
+timeloop::impl_enum! {
+    #[derive(Debug, Copy, Clone)]
+    pub enum Timers {
+        Parse,
+        Execute,
+        Cleanup,
+    }
+}
+
+fn main() {
+    timeloop::start_profiler!();
+
+    // Every timer is reported, even `Cleanup` which is never hit
+    timeloop::register_timers!(Timers);
+
+    timeloop::time_work!(Timers::Parse, {
+        std::thread::sleep(Duration::from_millis(50));
+    });
+
+    {
+        timeloop::scoped_timer!(Timers::Execute);
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    timeloop::print!();
+}
@@ -0,0 +1,183 @@
+//! Implements attributing the heap allocations of each thread to its open timers
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::MutexGuard;
+
+use crate::{OpenTimer, Profiler, ThreadProfile};
+
+/// Header of the allocation count column of the report
+pub(crate) const ALLOCS_HEADER: &str = "allocs";
+
+/// Header of the allocated bytes column of the report
+pub(crate) const ALLOC_BYTES_HEADER: &str = "alloc bytes";
+
+/// Set once the [`TrackingAllocator`] made its first allocation
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The allocations made by the current thread
+    static THREAD_ALLOCATIONS: Cell<AllocationStats> = const {
+        Cell::new(AllocationStats { count: 0, bytes: 0 })
+    };
+}
+
+/// The number and total size of heap allocations
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct AllocationStats {
+    /// Number of allocations (including reallocations)
+    pub count: u64,
+
+    /// Total bytes requested by the allocations
+    pub bytes: u64,
+}
+
+impl AllocationStats {
+    /// Get the allocations made since `earlier`
+    #[must_use]
+    pub fn since(self, earlier: AllocationStats) -> AllocationStats {
+        AllocationStats {
+            count: self.count.wrapping_sub(earlier.count),
+            bytes: self.bytes.wrapping_sub(earlier.bytes),
+        }
+    }
+
+    /// Add the allocations of `other` to these
+    pub fn add(&mut self, other: AllocationStats) {
+        self.count = self.count.wrapping_add(other.count);
+        self.bytes = self.bytes.wrapping_add(other.bytes);
+    }
+}
+
+/// A global allocator counting the allocations of every thread, so that the profiler can
+/// attribute them to the timer that made them
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: timeloop::TrackingAllocator = timeloop::TrackingAllocator::new();
+/// ```
+///
+/// Each timer is attributed the allocations made while it is the innermost open timer,
+/// shown as the `allocs` and `alloc bytes` columns of the report. The first hits of a
+/// timer may also attribute the profiler's own bookkeeping allocations to its parent.
+#[derive(Debug, Default)]
+pub struct TrackingAllocator<A: GlobalAlloc = System> {
+    /// The allocator doing the allocations
+    inner: A,
+}
+
+impl TrackingAllocator<System> {
+    /// Track the allocations of the [`System`] allocator
+    #[must_use]
+    pub const fn new() -> Self {
+        TrackingAllocator { inner: System }
+    }
+}
+
+impl<A: GlobalAlloc> TrackingAllocator<A> {
+    /// Track the allocations of the `inner` allocator
+    pub const fn with_allocator(inner: A) -> Self {
+        TrackingAllocator { inner }
+    }
+
+    /// Count an allocation of `bytes` on the current thread
+    fn track(bytes: usize) {
+        if !INSTALLED.load(Ordering::Relaxed) {
+            INSTALLED.store(true, Ordering::Relaxed);
+        }
+
+        // The counters can't be accessed anymore while the thread is exiting
+        let _ = THREAD_ALLOCATIONS.try_with(|allocations| {
+            let mut stats = allocations.get();
+            stats.add(AllocationStats {
+                count: 1,
+                bytes: bytes as u64,
+            });
+            allocations.set(stats);
+        });
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::track(layout.size());
+        unsafe { self.inner.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::track(layout.size());
+        unsafe { self.inner.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.inner.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::track(new_size);
+        unsafe { self.inner.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// Get the allocations made by the current thread so far, if the [`TrackingAllocator`]
+/// is installed
+pub(crate) fn thread_allocations() -> Option<AllocationStats> {
+    if !INSTALLED.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    THREAD_ALLOCATIONS.try_with(Cell::get).ok()
+}
+
+impl<const TIMERS: usize> ThreadProfile<TIMERS> {
+    /// Attribute the allocations made during the hit of the exited `open` timer on this
+    /// thread to it, without the allocations of its child timers
+    pub(crate) fn record_allocations(&mut self, open: &OpenTimer) {
+        let Some(start_allocations) = open.start_allocations else {
+            return;
+        };
+        let Some(allocations) = thread_allocations() else {
+            return;
+        };
+
+        let allocations = allocations.since(start_allocations);
+        self.allocations
+            .entry(open.timer)
+            .or_default()
+            .add(allocations);
+
+        // The parent's own allocations will include these when it exits
+        if let Some(parent) = open.parent {
+            let parent = self.allocations.entry(parent).or_default();
+            *parent = AllocationStats {
+                count: parent.count.wrapping_sub(allocations.count),
+                bytes: parent.bytes.wrapping_sub(allocations.bytes),
+            };
+        }
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Get the allocations made by `timer` summed over every thread, if the
+    /// [`TrackingAllocator`] attributed any to it
+    #[must_use]
+    pub fn timer_allocations(&self, timer: &str) -> Option<AllocationStats> {
+        let threads: Vec<_> = self.thread_profiles().collect();
+        allocations_of(&threads, timer)
+    }
+}
+
+/// Get the allocations made by `timer` summed over the already locked `threads`, if
+/// any were attributed to it
+pub(crate) fn allocations_of<const TIMERS: usize>(
+    threads: &[MutexGuard<ThreadProfile<TIMERS>>],
+    timer: &str,
+) -> Option<AllocationStats> {
+    threads
+        .iter()
+        .filter_map(|thread| thread.allocations.get(timer).copied())
+        .reduce(|mut total, allocations| {
+            total.add(allocations);
+            total
+        })
+}
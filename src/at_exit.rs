@@ -0,0 +1,71 @@
+//! Implements printing or saving the report automatically when the process exits
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use crate::periodic::write_json_report;
+use crate::{Profiler, ProfilerCell, TextRenderer};
+
+/// The reports to write when the process exits, in the order they were registered
+static EXIT_REPORTS: Mutex<Vec<Box<dyn FnOnce() + Send>>> = Mutex::new(Vec::new());
+
+/// Set once [`run_exit_reports`] is registered with `atexit`
+static REGISTERED: AtomicBool = AtomicBool::new(false);
+
+extern "C" {
+    fn atexit(callback: extern "C" fn()) -> i32;
+}
+
+/// Write every registered report, called by `atexit`
+extern "C" fn run_exit_reports() {
+    let reports = std::mem::take(&mut *EXIT_REPORTS.lock().unwrap_or_else(PoisonError::into_inner));
+
+    for report in reports {
+        report();
+    }
+}
+
+/// Print the report of `profiler` to stderr when the process exits, or write it as JSON
+/// to `path` if given, such as to not need a `print!` on every exit path
+///
+/// The report is written by an `atexit` handler, so it is written when `main` returns or
+/// on `std::process::exit`, but not when the process is killed or aborts.
+///
+/// # Errors
+///
+/// * Failed to register the `atexit` handler
+pub fn report_at_exit<const THREADS: usize, const TIMERS: usize>(
+    path: Option<PathBuf>,
+    profiler: &'static ProfilerCell<Profiler<THREADS, TIMERS>>,
+) -> std::io::Result<()> {
+    if !REGISTERED.swap(true, Ordering::AcqRel) && unsafe { atexit(run_exit_reports) } != 0 {
+        REGISTERED.store(false, Ordering::Release);
+        return Err(std::io::Error::other(
+            "Failed to register the atexit handler",
+        ));
+    }
+
+    EXIT_REPORTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Box::new(move || {
+            // Only hold the lock while the report is gathered, not while it is written
+            let mut locked = profiler.write();
+            if let Some(path) = &path {
+                let report = locked.create_report();
+                drop(locked);
+                write_json_report(&report, path);
+            } else {
+                let renderer = TextRenderer {
+                    color: locked.color.enabled(),
+                };
+                let mut out = Vec::new();
+                let _ = locked.print_with_renderer_to(&renderer, &mut out);
+                drop(locked);
+                let _ = std::io::stderr().write_all(&out);
+            }
+        }));
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! Command line tools for the results of timeloop
+//!
+//! ```text
+//! timeloop-cli plot results.json > plot.svg
+//! ```
+use std::io::Write;
+use std::process::ExitCode;
+
+use timeloop::SvgPlot;
+
+/// The usage printed on invalid arguments
+const USAGE: &str = "Usage: timeloop-cli plot <results.json>
+
+Commands:
+  plot  Write an SVG line plot of the JSON results to stdout. The results are either a
+        plot, the rows of a size sweep or the messages of `cargo criterion
+        --message-format=json`. See `SvgPlot::from_json` for their layout.";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.as_slice() {
+        [command, path] if command == "plot" => plot(path),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("timeloop-cli: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Write the SVG plot of the JSON results in `path` to stdout
+fn plot(path: &str) -> std::io::Result<()> {
+    let plot = SvgPlot::load(path)
+        .map_err(|err| std::io::Error::new(err.kind(), format!("{path}: {err}")))?;
+    std::io::stdout().lock().write_all(plot.render().as_bytes())
+}
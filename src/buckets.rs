@@ -0,0 +1,158 @@
+//! Implements the power-of-two bucket histograms kept for each timer
+use std::io::Write;
+use std::time::Duration;
+
+use crate::{tsc_frequency, Profiler, ThreadProfile};
+
+/// Number of buckets, enough for every `u64` duration
+const LOG2_BUCKETS: usize = 64;
+
+/// Width (in characters) of the longest bar in the rendered histogram
+const BAR_WIDTH: usize = 40;
+
+/// A histogram of hit durations where bucket `i` counts the hits of `2^i` up to
+/// `2^(i+1)` cycles. Bucket 0 also counts the hits of 0 cycles.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Log2Histogram {
+    /// The number of hits in each bucket
+    pub buckets: [u64; LOG2_BUCKETS],
+}
+
+impl Default for Log2Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; LOG2_BUCKETS],
+        }
+    }
+}
+
+impl Log2Histogram {
+    /// Add a hit of `cycles` to the histogram
+    pub fn record(&mut self, cycles: u64) {
+        self.buckets[cycles.max(1).ilog2() as usize] += 1;
+    }
+
+    /// Add the hits of `other` to this histogram
+    pub fn merge(&mut self, other: &Log2Histogram) {
+        for (bucket, count) in self.buckets.iter_mut().zip(other.buckets) {
+            *bucket += count;
+        }
+    }
+
+    /// The total number of hits in the histogram
+    #[must_use]
+    pub fn total_count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Write the non-empty range of buckets as ASCII bars to `out`, labeling each bucket
+    /// with its range of durations
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn write_ascii(&self, os_timer_freq: f64, out: &mut dyn Write) -> std::io::Result<()> {
+        let Some(first) = self.buckets.iter().position(|count| *count > 0) else {
+            return Ok(());
+        };
+        let last = self
+            .buckets
+            .iter()
+            .rposition(|count| *count > 0)
+            .unwrap_or(first);
+        let max_count = self.buckets.iter().copied().max().unwrap_or(1);
+
+        let duration = |cycles: u128| {
+            let duration = Duration::from_secs_f64(cycles as f64 / os_timer_freq);
+            format!("{duration:.2?}")
+        };
+
+        for (index, count) in self.buckets.iter().enumerate().take(last + 1).skip(first) {
+            let low = if index == 0 { 0 } else { 1u128 << index };
+            let high = 1u128 << (index + 1);
+            let bar = (*count as f64 / max_count as f64 * BAR_WIDTH as f64).ceil() as usize;
+
+            writeln!(
+                out,
+                "    {:>10} - {:>10} | {:<BAR_WIDTH$} | {count}",
+                duration(low),
+                duration(high),
+                "#".repeat(bar)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Add a hit of `elapsed` cycles of `timer` on the locked `thread` to its power-of-two
+    /// histogram if those histograms are enabled
+    pub(crate) fn record_log2_histogram(
+        &self,
+        thread: &mut ThreadProfile<TIMERS>,
+        timer: &'static str,
+        elapsed: u64,
+    ) {
+        if !self.record_log2_histograms {
+            return;
+        }
+
+        thread
+            .log2_histograms
+            .entry(timer)
+            .or_default()
+            .record(elapsed);
+    }
+
+    /// Get the power-of-two histogram of `timer` merged across all threads
+    #[must_use]
+    pub fn log2_histogram(&self, timer: &'static str) -> Log2Histogram {
+        let mut merged = Log2Histogram::default();
+
+        for thread in self.thread_profiles() {
+            if let Some(histogram) = thread.log2_histograms.get(timer) {
+                merged.merge(histogram);
+            }
+        }
+
+        merged
+    }
+
+    /// Print the power-of-two histogram of every timer
+    pub fn print_log2_histograms(&self) {
+        let _ = self.write_log2_histograms(tsc_frequency(), &mut std::io::stderr().lock());
+    }
+
+    /// Write the power-of-two histogram of every timer as ASCII bars to `out`
+    pub(crate) fn write_log2_histograms(
+        &self,
+        os_timer_freq: f64,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        let mut timers: Vec<&'static str> = Vec::new();
+        for thread in self.thread_profiles() {
+            timers.extend(thread.log2_histograms.keys().copied());
+        }
+        timers.sort_unstable();
+        timers.dedup();
+
+        if timers.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(out, "Histograms:")?;
+        for timer in timers {
+            let histogram = self.log2_histogram(timer);
+            writeln!(out, "  {timer} ({} hits)", histogram.total_count())?;
+            histogram.write_ascii(os_timer_freq, out)?;
+        }
+
+        Ok(())
+    }
+}
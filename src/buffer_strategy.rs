@@ -0,0 +1,274 @@
+//! Implements the comparison of buffer allocation strategies with the `RepititionTester`
+use std::io::Write;
+use std::time::Duration;
+
+use crate::{RepititionTester, TestResults};
+
+/// `PROT_READ | PROT_WRITE` from `<sys/mman.h>`
+const PROT_READ_WRITE: i32 = 0x1 | 0x2;
+
+/// `MAP_PRIVATE | MAP_ANONYMOUS` from `<sys/mman.h>`
+const MAP_PRIVATE_ANONYMOUS: i32 = 0x02 | 0x20;
+
+/// `MAP_FAILED` from `<sys/mman.h>`
+const MAP_FAILED: *mut u8 = usize::MAX as *mut u8;
+
+extern "C" {
+    fn mmap(addr: *mut u8, len: usize, prot: i32, flags: i32, fd: i32, offset: i64) -> *mut u8;
+    fn munmap(addr: *mut u8, len: usize) -> i32;
+}
+
+/// How the buffer given to each test is allocated
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BufferStrategy {
+    /// A new empty `Vec` that grows as the test writes to it
+    Fresh,
+
+    /// A new `Vec` with the full capacity allocated up front
+    WithCapacity,
+
+    /// The same `Vec` reused (cleared) across every test
+    Reused,
+
+    /// A new anonymous memory mapping of the full capacity, so every page faults on
+    /// first write
+    Mmap,
+}
+
+impl BufferStrategy {
+    /// Every buffer strategy, in the order they are compared
+    pub const ALL: [BufferStrategy; 4] = [
+        BufferStrategy::Fresh,
+        BufferStrategy::WithCapacity,
+        BufferStrategy::Reused,
+        BufferStrategy::Mmap,
+    ];
+
+    /// The name of this strategy in the comparison table
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            BufferStrategy::Fresh => "fresh",
+            BufferStrategy::WithCapacity => "with_capacity",
+            BufferStrategy::Reused => "reused",
+            BufferStrategy::Mmap => "mmap",
+        }
+    }
+}
+
+/// The buffer given to each test, allocated by one of the [`BufferStrategy`]s
+#[derive(Debug)]
+pub enum Buffer {
+    /// A heap allocated buffer
+    Vec(Vec<u8>),
+
+    /// An anonymous memory mapping that can't grow past its capacity
+    Mmap {
+        /// The start of the mapping
+        ptr: *mut u8,
+
+        /// The number of bytes written to the mapping
+        len: usize,
+
+        /// The size of the mapping
+        capacity: usize,
+    },
+}
+
+impl Buffer {
+    /// Allocate a buffer for `capacity` bytes using `strategy`, taking the buffer out
+    /// of `reused` for [`BufferStrategy::Reused`]
+    ///
+    /// # Panics
+    ///
+    /// * Failed to create the memory mapping
+    fn allocate(strategy: BufferStrategy, capacity: usize, reused: &mut Option<Vec<u8>>) -> Self {
+        match strategy {
+            BufferStrategy::Fresh => Buffer::Vec(Vec::new()),
+            BufferStrategy::WithCapacity => Buffer::Vec(Vec::with_capacity(capacity)),
+            BufferStrategy::Reused => {
+                let mut buffer = reused
+                    .take()
+                    .unwrap_or_else(|| Vec::with_capacity(capacity));
+                buffer.clear();
+                Buffer::Vec(buffer)
+            }
+            BufferStrategy::Mmap => {
+                let ptr = unsafe {
+                    mmap(
+                        std::ptr::null_mut(),
+                        capacity.max(1),
+                        PROT_READ_WRITE,
+                        MAP_PRIVATE_ANONYMOUS,
+                        -1,
+                        0,
+                    )
+                };
+                assert!(ptr != MAP_FAILED, "Failed to map the buffer");
+
+                Buffer::Mmap {
+                    ptr,
+                    len: 0,
+                    capacity: capacity.max(1),
+                }
+            }
+        }
+    }
+
+    /// Append `bytes` to the buffer
+    ///
+    /// # Panics
+    ///
+    /// * A memory mapped buffer is full
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        match self {
+            Buffer::Vec(buffer) => buffer.extend_from_slice(bytes),
+            Buffer::Mmap { ptr, len, capacity } => {
+                assert!(
+                    *len + bytes.len() <= *capacity,
+                    "Memory mapped buffer is full"
+                );
+
+                unsafe {
+                    std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr.add(*len), bytes.len());
+                }
+                *len += bytes.len();
+            }
+        }
+    }
+
+    /// Append a single byte to the buffer
+    pub fn push(&mut self, byte: u8) {
+        match self {
+            Buffer::Vec(buffer) => buffer.push(byte),
+            Buffer::Mmap { .. } => self.extend_from_slice(&[byte]),
+        }
+    }
+
+    /// The bytes written to the buffer
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            Buffer::Vec(buffer) => buffer,
+            Buffer::Mmap { ptr, len, .. } => unsafe { std::slice::from_raw_parts(*ptr, *len) },
+        }
+    }
+
+    /// The number of bytes written to the buffer
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Check if nothing was written to the buffer
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        if let Buffer::Mmap { ptr, capacity, .. } = self {
+            unsafe {
+                munmap(*ptr, *capacity);
+            }
+        }
+    }
+}
+
+/// The results of every [`BufferStrategy`] compared by [`compare_buffer_strategies`]
+#[derive(Debug, Clone)]
+pub struct StrategyComparison {
+    /// The results of each strategy, in the order they were tested
+    pub results: Vec<(BufferStrategy, TestResults)>,
+}
+
+impl StrategyComparison {
+    /// Print the comparison table of the strategies
+    pub fn print(&self) {
+        let _ = self.print_to(&mut std::io::stdout().lock());
+    }
+
+    /// Write the comparison table of the strategies to `out`, with the throughput and
+    /// page faults of the average and the fastest test of each strategy
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    pub fn print_to(&self, out: &mut impl Write) -> std::io::Result<()> {
+        /// Format an optional throughput in GBs/sec
+        fn gbs_per_sec(bytes_per_second: Option<f64>) -> String {
+            bytes_per_second
+                .map(|bytes| format!("{:.3}", bytes / 1024. / 1024. / 1024.))
+                .unwrap_or_default()
+        }
+
+        writeln!(
+            out,
+            "{:<13} | {:>10} | {:>10} | {:>12} | {:>12} | {:>10} | {:>10}",
+            "STRATEGY", "MIN", "AVG", "MIN GBs/sec", "AVG GBs/sec", "MIN FAULTS", "AVG FAULTS"
+        )?;
+
+        for (strategy, results) in &self.results {
+            writeln!(
+                out,
+                "{:<13} | {:>10} | {:>10} | {:>12} | {:>12} | {:>10} | {:>10}",
+                strategy.name(),
+                format!("{:.2?}", results.min.time),
+                format!("{:.2?}", results.avg.time),
+                gbs_per_sec(results.min.bytes_per_second),
+                gbs_per_sec(results.avg.bytes_per_second),
+                results.min.page_faults,
+                results.avg.page_faults,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Run `test` against a buffer of every [`BufferStrategy`] for `duration` each and
+/// compare the results. The allocation of the buffer is part of each timed test.
+///
+/// `capacity` is the expected size (in bytes) of the buffer, used for the up front
+/// allocations and as the throughput of each test.
+pub fn compare_buffer_strategies(
+    duration: Duration,
+    capacity: usize,
+    mut test: impl FnMut(&mut Buffer),
+) -> StrategyComparison {
+    let mut results = Vec::new();
+
+    for strategy in BufferStrategy::ALL {
+        let mut tester = RepititionTester::new(duration);
+        let mut reused = None;
+
+        while tester.is_testing() {
+            tester.start();
+            let mut buffer = Buffer::allocate(strategy, capacity, &mut reused);
+            test(&mut buffer);
+            tester.stop();
+
+            // Keep the buffer for the next test, outside of the timed test
+            if let (BufferStrategy::Reused, Buffer::Vec(buffer)) = (strategy, &mut buffer) {
+                reused = Some(std::mem::take(buffer));
+            }
+        }
+
+        results.push((strategy, tester.results_with_throughput(capacity)));
+    }
+
+    StrategyComparison { results }
+}
@@ -0,0 +1,127 @@
+//! Implements the accounting of every caller to callee edge between the timers
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::{Profiler, ThreadProfile};
+
+/// Label of the caller of the timers hit outside of any other timer
+const NO_CALLER_LABEL: &str = "(no caller)";
+
+/// The hits of a timer from a single caller
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CallerEdge {
+    /// The timer that was open when the callee was hit, `None` for the outermost timers
+    pub caller: Option<&'static str>,
+
+    /// The number of times the callee was hit from this caller
+    pub hits: u64,
+
+    /// Time (in cycles) spent in the callee from this caller (with child timers)
+    pub cycles: u64,
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Add a hit of `elapsed` cycles of `timer` called from `caller` on the locked
+    /// `thread` if caller recording is enabled
+    pub(crate) fn record_caller(
+        &self,
+        thread: &mut ThreadProfile<TIMERS>,
+        caller: Option<&'static str>,
+        timer: &'static str,
+        elapsed: u64,
+    ) {
+        if !self.record_callers {
+            return;
+        }
+
+        let edge = thread
+            .caller_edges
+            .entry((timer, caller))
+            .or_insert(CallerEdge {
+                caller,
+                ..CallerEdge::default()
+            });
+
+        edge.hits += 1;
+        edge.cycles = edge.cycles.wrapping_add(elapsed);
+    }
+
+    /// Get every caller of `timer` across all threads, the most expensive first
+    #[must_use]
+    pub fn callers(&self, timer: &str) -> Vec<CallerEdge> {
+        let mut merged: BTreeMap<Option<&'static str>, CallerEdge> = BTreeMap::new();
+
+        for thread in self.thread_profiles() {
+            for ((callee, caller), edge) in &thread.caller_edges {
+                if *callee != timer {
+                    continue;
+                }
+
+                let entry = merged.entry(*caller).or_insert(CallerEdge {
+                    caller: *caller,
+                    ..CallerEdge::default()
+                });
+                entry.hits += edge.hits;
+                entry.cycles = entry.cycles.wrapping_add(edge.cycles);
+            }
+        }
+
+        let mut callers: Vec<CallerEdge> = merged.into_values().collect();
+        callers.sort_by_key(|edge| std::cmp::Reverse(edge.cycles));
+        callers
+    }
+
+    /// Write the breakdown of the callers of every timer to `out`
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn write_callers(
+        &self,
+        os_timer_freq: f64,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        let mut timers: Vec<&'static str> = self
+            .thread_profiles()
+            .flat_map(|thread| {
+                thread
+                    .caller_edges
+                    .keys()
+                    .map(|(callee, _)| *callee)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        timers.sort_unstable();
+        timers.dedup();
+
+        if timers.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(out, "Callers:")?;
+        for timer in timers {
+            let callers = self.callers(timer);
+            let total: u64 = callers.iter().map(|edge| edge.cycles).sum();
+
+            let name_width = callers
+                .iter()
+                .map(|edge| edge.caller.unwrap_or(NO_CALLER_LABEL).len())
+                .max()
+                .unwrap_or(0);
+
+            writeln!(out, "  {timer}")?;
+            for edge in callers {
+                let duration = Duration::from_secs_f64(edge.cycles as f64 / os_timer_freq);
+                let percent = edge.cycles as f64 / total.max(1) as f64 * 100.;
+
+                writeln!(
+                    out,
+                    "    {:<name_width$} | {:>10} hits | {:>10} | {percent:6.2}%",
+                    edge.caller.unwrap_or(NO_CALLER_LABEL),
+                    edge.hits,
+                    format!("{duration:.2?}"),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+//! Implements the timer categories and their subtotals in the report
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::{Profiler, Report, TimerReport};
+
+/// Label of the group of timers without a category
+const UNCATEGORIZED_LABEL: &str = "(uncategorized)";
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Put `timer` in `category` (such as "io", "compute" or "locking") for the
+    /// category subtotals in the report
+    pub fn set_category(&mut self, timer: &'static str, category: &'static str) {
+        if self.categories.get(timer) != Some(&category) {
+            self.categories.insert(timer, category);
+        }
+    }
+
+    /// Get the category of `timer`, if it was given one
+    #[must_use]
+    pub fn category(&self, timer: &str) -> Option<&'static str> {
+        self.categories.get(timer).copied()
+    }
+}
+
+/// Write the subtotal of every category in `report` followed by its timers, the most
+/// expensive first. Nothing is written if no timer has a category.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn write_categories(report: &Report, out: &mut dyn Write) -> std::io::Result<()> {
+    if report.timers.iter().all(|timer| timer.category.is_none()) {
+        return Ok(());
+    }
+
+    let mut groups: BTreeMap<&str, Vec<&TimerReport>> = BTreeMap::new();
+    for timer in &report.timers {
+        groups
+            .entry(timer.category.unwrap_or(UNCATEGORIZED_LABEL))
+            .or_default()
+            .push(timer);
+    }
+
+    let subtotal = |timers: &[&TimerReport]| -> u64 {
+        timers
+            .iter()
+            .fold(0u64, |acc, timer| acc.wrapping_add(timer.exclusive_time))
+    };
+
+    let mut groups: Vec<(&str, Vec<&TimerReport>)> = groups.into_iter().collect();
+    groups.sort_by_key(|(_, timers)| std::cmp::Reverse(subtotal(timers)));
+
+    let name_width = report
+        .timers
+        .iter()
+        .map(|timer| timer.name.len() + 2)
+        .chain(groups.iter().map(|(category, _)| category.len()))
+        .max()
+        .unwrap_or(0);
+
+    let line = |out: &mut dyn Write, name: &str, cycles: u64, hits: u64| {
+        let duration = Duration::from_secs_f64(cycles as f64 / report.os_timer_freq);
+        let percent = cycles as f64 / report.total_time.max(1) as f64 * 100.;
+
+        writeln!(
+            out,
+            "  {name:<name_width$} | {:>10} | {percent:6.2}% | {hits:>10} hits",
+            format!("{duration:.2?}")
+        )
+    };
+
+    writeln!(out, "Categories:")?;
+    for (category, timers) in &groups {
+        let hits = timers.iter().map(|timer| timer.hits).sum();
+        line(out, category, subtotal(timers), hits)?;
+
+        for timer in timers {
+            line(
+                out,
+                &format!("  {}", timer.name),
+                timer.exclusive_time,
+                timer.hits,
+            )?;
+        }
+    }
+
+    Ok(())
+}
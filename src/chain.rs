@@ -0,0 +1,173 @@
+//! Implements the latency chains: requests timed across a named chain of stages by a
+//! token that can be sent between threads
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::percentile::weighted_percentiles;
+use crate::{rdtsc, tsc_frequency, Percentiles, Profiler, SampleReservoir};
+
+/// Times a single request through the stages of a latency chain. The token can be sent
+/// to other threads as the request moves through the program.
+#[derive(Debug, Clone)]
+pub struct LatencyToken {
+    /// The chain this request belongs to
+    chain: &'static str,
+
+    /// The timestamp when the request started
+    start: u64,
+
+    /// The timestamp when the last stage ended
+    last: u64,
+
+    /// The time (in cycles) of every finished stage, in order
+    stages: Vec<(&'static str, u64)>,
+}
+
+impl LatencyToken {
+    /// Start timing a request of `chain` now
+    #[must_use]
+    pub fn new(chain: &'static str) -> Self {
+        let now = rdtsc();
+
+        Self {
+            chain,
+            start: now,
+            last: now,
+            stages: Vec::new(),
+        }
+    }
+
+    /// End `stage` of the request now. The stage covers the time since the previous
+    /// stage ended, or since the request started.
+    pub fn stage(&mut self, stage: &'static str) {
+        let now = rdtsc();
+        self.stages.push((stage, now.wrapping_sub(self.last)));
+        self.last = now;
+    }
+}
+
+/// The requests finished for a single latency chain on a single thread
+#[derive(Debug, Clone, Default)]
+pub struct ChainStats {
+    /// Number of finished requests
+    pub requests: u64,
+
+    /// Total time (in cycles) of every request from start to the last stage
+    pub total_cycles: u64,
+
+    /// Total time (in cycles) spent in each stage
+    pub stage_cycles: BTreeMap<&'static str, u64>,
+
+    /// A sample of the end to end time of the requests
+    pub latencies: SampleReservoir,
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Define the ordered `stages` of the latency chain `name`, used to order the stages
+    /// in the report
+    pub fn define_chain(&mut self, name: &'static str, stages: &'static [&'static str]) {
+        self.chain_stages.insert(name, stages);
+    }
+
+    /// Record the finished request timed by `token` on the given thread. The request
+    /// ends when its last stage ended.
+    pub fn finish_token(&self, thread_id: usize, token: LatencyToken) {
+        let total = token.last.wrapping_sub(token.start);
+
+        let mut thread = self.thread(thread_id);
+        let stats = thread.chains.entry(token.chain).or_default();
+        stats.requests += 1;
+        stats.total_cycles = stats.total_cycles.wrapping_add(total);
+        stats.latencies.record(total);
+
+        for (stage, cycles) in token.stages {
+            let entry = stats.stage_cycles.entry(stage).or_default();
+            *entry = entry.wrapping_add(cycles);
+        }
+    }
+
+    /// Print the end to end latency percentiles and the contribution of each stage of
+    /// every latency chain
+    pub fn print_chains(&self) {
+        let _ = self.write_chains(tsc_frequency(), &mut std::io::stderr().lock());
+    }
+
+    /// Write the latency chains to `out`
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn write_chains(
+        &self,
+        os_timer_freq: f64,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        // Copy the chains to not hold up the threads while writing them
+        let chains: Vec<BTreeMap<&'static str, ChainStats>> = self
+            .thread_profiles()
+            .filter(|thread| !thread.chains.is_empty())
+            .map(|thread| thread.chains.clone())
+            .collect();
+
+        let mut names: Vec<&'static str> = chains
+            .iter()
+            .flat_map(|chains| chains.keys().copied())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+
+        if names.is_empty() {
+            return Ok(());
+        }
+
+        let duration =
+            |cycles: f64| format!("{:.2?}", Duration::from_secs_f64(cycles / os_timer_freq));
+
+        writeln!(out, "Latency chains:")?;
+        for name in names {
+            let threads = || chains.iter().filter_map(|chains| chains.get(name));
+
+            let requests: u64 = threads().map(|stats| stats.requests).sum();
+            let total_cycles: u64 = threads().map(|stats| stats.total_cycles).sum();
+            let Percentiles { p50, p90, p99 } =
+                weighted_percentiles(threads().map(|stats| &stats.latencies)).unwrap_or_default();
+
+            writeln!(
+                out,
+                "  {name} ({requests} requests) | p50 {} | p90 {} | p99 {}",
+                duration(p50 as f64),
+                duration(p90 as f64),
+                duration(p99 as f64)
+            )?;
+
+            let mut stage_cycles: BTreeMap<&'static str, u64> = BTreeMap::new();
+            for stats in threads() {
+                for (stage, cycles) in &stats.stage_cycles {
+                    *stage_cycles.entry(stage).or_default() += cycles;
+                }
+            }
+
+            // The defined stages first, in order, followed by any undefined stages
+            let defined = self.chain_stages.get(name).copied().unwrap_or_default();
+            let mut stages: Vec<&'static str> = defined
+                .iter()
+                .copied()
+                .filter(|stage| stage_cycles.contains_key(stage))
+                .collect();
+            stages.extend(stage_cycles.keys().filter(|stage| !defined.contains(stage)));
+
+            let name_width = stages.iter().map(|stage| stage.len()).max().unwrap_or(0);
+            for stage in stages {
+                let cycles = stage_cycles[stage];
+                let percent = cycles as f64 / total_cycles.max(1) as f64 * 100.;
+                let mean = cycles as f64 / requests.max(1) as f64;
+
+                writeln!(
+                    out,
+                    "    {stage:<name_width$} | {percent:6.2}% | mean {:>10}",
+                    duration(mean)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+//! Implements the calibration of the timestamp counter used by every timer
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::rdtsc;
+
+/// Number of separate measurements of the timestamp counter frequency
+const CALIBRATION_ROUNDS: u32 = 5;
+
+/// Time spent on each measurement of the timestamp counter frequency
+const CALIBRATION_ROUND_TIME: Duration = Duration::from_millis(20);
+
+/// The clock read by the timers
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClockSource {
+    /// The `rdtsc` timestamp counter
+    #[default]
+    Rdtsc,
+
+    /// The coarse monotonic clock (in nanoseconds) of the `counters` mode
+    CoarseMonotonic,
+}
+
+impl ClockSource {
+    /// The name of this clock source used in the exports
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            ClockSource::Rdtsc => "rdtsc",
+            ClockSource::CoarseMonotonic => "coarse-monotonic",
+        }
+    }
+}
+
+impl std::fmt::Display for ClockSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The calibrated frequency of the clock used to convert cycles into time
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Calibration {
+    /// The frequency (in Hz) of the clock
+    pub frequency: f64,
+
+    /// The estimated error (in Hz) of `frequency`, half the spread of the measurements
+    pub error: f64,
+
+    /// The clock that was calibrated
+    pub source: ClockSource,
+}
+
+impl Calibration {
+    /// The estimated error relative to the frequency
+    #[must_use]
+    pub fn relative_error(&self) -> f64 {
+        self.error / self.frequency
+    }
+
+    /// Check if `other` measured a different frequency than this calibration, beyond
+    /// the error of both
+    #[must_use]
+    pub fn differs_from(&self, other: &Calibration) -> bool {
+        self.source != other.source
+            || (self.frequency - other.frequency).abs() > self.error + other.error
+    }
+}
+
+/// Get the calibration of the timestamp counter, measured once on first use
+pub fn calibration() -> Calibration {
+    static CALIBRATION: OnceLock<Calibration> = OnceLock::new();
+
+    *CALIBRATION.get_or_init(|| {
+        // The coarse clock already counts nanoseconds
+        if cfg!(feature = "counters") {
+            return Calibration {
+                frequency: 1_000_000_000.0,
+                error: 0.0,
+                source: ClockSource::CoarseMonotonic,
+            };
+        }
+
+        let mut min = f64::MAX;
+        let mut max = f64::MIN;
+        let mut sum = 0.0;
+
+        // Time a few short timeouts to estimate how stable the frequency is
+        for _ in 0..CALIBRATION_ROUNDS {
+            let start = Instant::now();
+            let clock_start = rdtsc();
+            while start.elapsed() < CALIBRATION_ROUND_TIME {}
+            let clock_end = rdtsc();
+
+            #[allow(clippy::cast_precision_loss)]
+            let frequency =
+                clock_end.wrapping_sub(clock_start) as f64 / start.elapsed().as_secs_f64();
+
+            min = min.min(frequency);
+            max = max.max(frequency);
+            sum += frequency;
+        }
+
+        Calibration {
+            frequency: sum / f64::from(CALIBRATION_ROUNDS),
+            error: (max - min) / 2.0,
+            source: ClockSource::Rdtsc,
+        }
+    })
+}
+
+/// Get the frequency (in Hz) of the timestamp counter, calibrated once on first use
+#[must_use]
+pub fn tsc_frequency() -> f64 {
+    calibration().frequency
+}
@@ -0,0 +1,101 @@
+//! Implements the comparison of two variants of the same code path
+use crate::{calculate_os_frequency, rdtsc, Profiler};
+
+/// A logical timer that is timed separately for each of its two variants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparison {
+    /// The name of the logical timer
+    pub name: &'static str,
+
+    /// The name of each variant
+    pub variants: [&'static str; 2],
+
+    /// The timer used for each variant
+    pub timers: [&'static str; 2],
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Register a comparison between the two variant timers of `name`
+    pub fn register_comparison(
+        &mut self,
+        name: &'static str,
+        variants: [&'static str; 2],
+        timers: [&'static str; 2],
+    ) {
+        let comparison = Comparison {
+            name,
+            variants,
+            timers,
+        };
+
+        if !self.comparisons.contains(&comparison) {
+            self.comparisons.push(comparison);
+        }
+    }
+
+    /// Print the per hit cost (including child timers) of both variants of every
+    /// registered comparison, relative to the first variant
+    #[allow(clippy::cast_precision_loss)]
+    pub fn print_comparisons(&mut self) {
+        self.stop_running_threads(rdtsc());
+
+        let (acc, _) = self.accumulate_timers();
+        let os_timer_freq = calculate_os_frequency();
+
+        let name_width = self
+            .comparisons
+            .iter()
+            .map(|comparison| comparison.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("COMPARISON".len());
+
+        let variant_width = self
+            .comparisons
+            .iter()
+            .flat_map(|comparison| comparison.variants)
+            .map(str::len)
+            .max()
+            .unwrap_or(0)
+            .max("VARIANT".len());
+
+        eprintln!(
+            "{:<name_width$} | {:<variant_width$} | {:>10} | {:>14} | {:>12} | {:>8}",
+            "COMPARISON", "VARIANT", "HITS", "CYCLES/HIT", "TIME/HIT", "RELATIVE"
+        );
+
+        for comparison in &self.comparisons {
+            let mut baseline = None;
+
+            for (variant, timer) in comparison.variants.iter().zip(comparison.timers) {
+                let timer = self
+                    .timer_name_to_index
+                    .get(timer)
+                    .map(|index| acc[*index as usize])
+                    .unwrap_or_default();
+
+                if timer.hits == 0 {
+                    eprintln!(
+                        "{:<name_width$} | {variant:<variant_width$} | {:>10} | {:>14} | {:>12} | {:>8}",
+                        comparison.name, 0, "", "", ""
+                    );
+                    continue;
+                }
+
+                let cycles_per_hit = timer.inclusive_time as f64 / timer.hits as f64;
+                let time_per_hit =
+                    std::time::Duration::from_secs_f64(cycles_per_hit / os_timer_freq);
+
+                let relative = *baseline.get_or_insert(cycles_per_hit);
+                let relative = format!("{:.2}x", cycles_per_hit / relative);
+
+                eprintln!(
+                    "{:<name_width$} | {variant:<variant_width$} | {:>10} | {cycles_per_hit:>14.2} | {:>12} | {relative:>8}",
+                    comparison.name,
+                    timer.hits,
+                    format!("{time_per_hit:.2?}"),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Implements counting the context switches of the thread during each timer
+use std::sync::MutexGuard;
+
+use crate::{OpenTimer, Profiler, ThreadProfile};
+
+/// `RUSAGE_THREAD` from `<sys/resource.h>`
+const RUSAGE_THREAD: i32 = 1;
+
+/// Header of the voluntary context switch column of the report
+pub(crate) const VOLUNTARY_HEADER: &str = "vol cs";
+
+/// Header of the involuntary context switch column of the report
+pub(crate) const INVOLUNTARY_HEADER: &str = "invol cs";
+
+/// `struct timeval` from `<sys/time.h>`
+#[repr(C)]
+#[derive(Default)]
+struct Timeval {
+    tv_sec: i64,
+    tv_usec: i64,
+}
+
+/// `struct rusage` from `<sys/resource.h>`
+#[repr(C)]
+#[derive(Default)]
+#[allow(clippy::struct_field_names)]
+struct Rusage {
+    ru_utime: Timeval,
+    ru_stime: Timeval,
+    ru_maxrss: i64,
+    ru_ixrss: i64,
+    ru_idrss: i64,
+    ru_isrss: i64,
+    ru_minflt: i64,
+    ru_majflt: i64,
+    ru_nswap: i64,
+    ru_inblock: i64,
+    ru_oublock: i64,
+    ru_msgsnd: i64,
+    ru_msgrcv: i64,
+    ru_nsignals: i64,
+    ru_nvcsw: i64,
+    ru_nivcsw: i64,
+}
+
+extern "C" {
+    fn getrusage(who: i32, usage: *mut Rusage) -> i32;
+}
+
+/// The number of times a thread was descheduled
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ContextSwitches {
+    /// Switches where the thread gave up the CPU itself, such as to wait on I/O or a lock
+    pub voluntary: u64,
+
+    /// Switches where the thread was preempted by the scheduler
+    pub involuntary: u64,
+}
+
+impl ContextSwitches {
+    /// Get the context switches of the current thread so far, or `None` if they can't be
+    /// read
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn current_thread() -> Option<ContextSwitches> {
+        let mut usage = Rusage::default();
+
+        let result = unsafe { getrusage(RUSAGE_THREAD, std::ptr::addr_of_mut!(usage)) };
+        if result != 0 {
+            return None;
+        }
+
+        Some(ContextSwitches {
+            voluntary: usage.ru_nvcsw as u64,
+            involuntary: usage.ru_nivcsw as u64,
+        })
+    }
+
+    /// Add the context switches of `other` to these
+    pub fn add(&mut self, other: ContextSwitches) {
+        self.voluntary = self.voluntary.wrapping_add(other.voluntary);
+        self.involuntary = self.involuntary.wrapping_add(other.involuntary);
+    }
+}
+
+impl<const TIMERS: usize> ThreadProfile<TIMERS> {
+    /// Add the context switches during the hit of the exited `open` timer on this
+    /// thread, if they were read when it was entered
+    pub(crate) fn record_context_switch_delta(&mut self, open: &OpenTimer) {
+        let Some(start) = open.start_context_switches else {
+            return;
+        };
+        let Some(now) = ContextSwitches::current_thread() else {
+            return;
+        };
+
+        self.context_switches
+            .entry(open.timer)
+            .or_default()
+            .add(ContextSwitches {
+                voluntary: now.voluntary.saturating_sub(start.voluntary),
+                involuntary: now.involuntary.saturating_sub(start.involuntary),
+            });
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Get the context switches during the hits of `timer` summed over every thread, if
+    /// context switches were recorded for it
+    #[must_use]
+    pub fn timer_context_switches(&self, timer: &str) -> Option<ContextSwitches> {
+        let threads: Vec<_> = self.thread_profiles().collect();
+        context_switches_of(&threads, timer)
+    }
+}
+
+/// Get the context switches during the hits of `timer` summed over the already locked
+/// `threads`, if context switches were recorded for it
+pub(crate) fn context_switches_of<const TIMERS: usize>(
+    threads: &[MutexGuard<ThreadProfile<TIMERS>>],
+    timer: &str,
+) -> Option<ContextSwitches> {
+    threads
+        .iter()
+        .filter_map(|thread| thread.context_switches.get(timer).copied())
+        .reduce(|mut total, switches| {
+            total.add(switches);
+            total
+        })
+}
@@ -0,0 +1,63 @@
+//! Implements the `counters` compile mode, where timers only count their hits and time
+//! themselves with a coarse clock for always-on production telemetry
+use crate::{OpenTimer, Profiler};
+
+/// `CLOCK_MONOTONIC_COARSE` from `<time.h>`
+const CLOCK_MONOTONIC_COARSE: i32 = 6;
+
+/// `struct timespec` from `<time.h>`
+#[repr(C)]
+struct Timespec {
+    tv_sec: i64,
+    tv_nsec: i64,
+}
+
+extern "C" {
+    fn clock_gettime(clock_id: i32, tp: *mut Timespec) -> i32;
+}
+
+/// Read the coarse monotonic clock (in nanoseconds). It only advances once per
+/// scheduler tick, a few milliseconds, but is much cheaper to read than a precise clock.
+#[allow(clippy::cast_sign_loss)]
+pub(crate) fn coarse_nanos() -> u64 {
+    let mut time = Timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+
+    unsafe {
+        clock_gettime(CLOCK_MONOTONIC_COARSE, std::ptr::addr_of_mut!(time));
+    }
+
+    time.tv_sec as u64 * 1_000_000_000 + time.tv_nsec as u64
+}
+
+/// Enter `timer` in the `counters` mode, without tracking its parent
+pub(crate) fn enter_counter(timer: &'static str, bytes_processed: u64) -> OpenTimer {
+    OpenTimer {
+        timer,
+        start_time: coarse_nanos(),
+        bytes_processed,
+        ..Default::default()
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Exit the `open` timer in the `counters` mode, counting the hit and its coarse
+    /// duration. Without parent tracking the exclusive time includes the child timers.
+    pub(crate) fn exit_counter(&self, thread_id: usize, open: &OpenTimer, stop_time: u64) {
+        let elapsed = stop_time.saturating_sub(open.start_time);
+        let mut thread = self.thread(thread_id);
+        let timer = &mut thread.timers[self.timer_index(open.timer)];
+
+        if timer.hits == 0 || elapsed < timer.min_cycles {
+            timer.min_cycles = elapsed;
+        }
+        timer.max_cycles = timer.max_cycles.max(elapsed);
+
+        timer.exclusive_time = timer.exclusive_time.wrapping_add(elapsed);
+        timer.inclusive_time = timer.inclusive_time.wrapping_add(elapsed);
+        timer.bytes_processed += open.bytes_processed;
+        timer.hits += 1;
+    }
+}
@@ -0,0 +1,189 @@
+//! Implements the capture of the CPU model and topology attached to reports
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::Profiler;
+
+/// A single CPU cache as seen by the first CPU
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CpuCache {
+    /// The level of the cache (1 for L1)
+    pub level: u64,
+
+    /// The kind of the cache: `Data`, `Instruction` or `Unified`
+    pub kind: String,
+
+    /// The size of the cache in bytes
+    pub size: u64,
+}
+
+/// The model and topology of the CPU that produced a report
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CpuInfo {
+    /// The brand string reported by `cpuid`
+    pub brand: String,
+
+    /// Number of logical CPUs (hardware threads)
+    pub logical_cpus: u64,
+
+    /// Number of physical cores across all packages
+    pub physical_cores: u64,
+
+    /// Number of CPU packages (sockets)
+    pub packages: u64,
+
+    /// The caches of the first CPU
+    pub caches: Vec<CpuCache>,
+}
+
+impl CpuInfo {
+    /// Number of hardware threads per physical core
+    #[must_use]
+    pub fn threads_per_core(&self) -> u64 {
+        self.logical_cpus / self.physical_cores.max(1)
+    }
+}
+
+impl std::fmt::Display for CpuInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({} cores, {} threads",
+            self.brand, self.physical_cores, self.logical_cpus
+        )?;
+
+        for cache in &self.caches {
+            let kind = match cache.kind.as_str() {
+                "Data" => "d",
+                "Instruction" => "i",
+                _ => "",
+            };
+
+            write!(f, ", L{}{kind} {}K", cache.level, cache.size / 1024)?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+/// Get the model and topology of this CPU, collected once on first use
+pub fn cpu_info() -> &'static CpuInfo {
+    static CPU_INFO: OnceLock<CpuInfo> = OnceLock::new();
+
+    CPU_INFO.get_or_init(|| {
+        let cpus = Path::new("/sys/devices/system/cpu");
+
+        // Count the unique cores and packages of every online CPU
+        let mut logical_cpus = 0;
+        let mut cores = BTreeSet::new();
+        let mut packages = BTreeSet::new();
+
+        for entry in std::fs::read_dir(cpus).into_iter().flatten().flatten() {
+            let name = entry.file_name();
+            let Some(id) = name.to_str().and_then(|name| name.strip_prefix("cpu")) else {
+                continue;
+            };
+
+            if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+                continue;
+            }
+
+            let topology = entry.path().join("topology");
+            let package = read_number(&topology.join("physical_package_id"));
+            let core = read_number(&topology.join("core_id"));
+
+            logical_cpus += 1;
+            packages.insert(package);
+            cores.insert((package, core));
+        }
+
+        // Fall back to the scheduler's view without sysfs
+        if logical_cpus == 0 {
+            logical_cpus = std::thread::available_parallelism().map_or(1, |cpus| cpus.get() as u64);
+            cores.insert((None, None));
+            packages.insert(None);
+        }
+
+        let mut caches = Vec::new();
+        for index in 0.. {
+            let cache = cpus.join(format!("cpu0/cache/index{index}"));
+            if !cache.exists() {
+                break;
+            }
+
+            let kind = std::fs::read_to_string(cache.join("type")).unwrap_or_default();
+
+            caches.push(CpuCache {
+                level: read_number(&cache.join("level")).unwrap_or(0),
+                kind: kind.trim().to_string(),
+                size: read_size(&cache.join("size")).unwrap_or(0),
+            });
+        }
+
+        CpuInfo {
+            brand: cpu_brand(),
+            logical_cpus,
+            physical_cores: cores.len() as u64,
+            packages: packages.len() as u64,
+            caches,
+        }
+    })
+}
+
+/// Read the processor brand string from the extended `cpuid` leaves
+fn cpu_brand() -> String {
+    use std::arch::x86_64::__cpuid;
+
+    let max_leaf = __cpuid(0x8000_0000).eax;
+    if max_leaf < 0x8000_0004 {
+        return String::from("Unknown");
+    }
+
+    let mut brand = Vec::with_capacity(48);
+    for leaf in 0x8000_0002..=0x8000_0004 {
+        let result = __cpuid(leaf);
+
+        for reg in [result.eax, result.ebx, result.ecx, result.edx] {
+            brand.extend_from_slice(&reg.to_le_bytes());
+        }
+    }
+
+    String::from_utf8_lossy(&brand)
+        .trim_matches(|c: char| c == '\0' || c.is_whitespace())
+        .to_string()
+}
+
+/// Read a single number from a sysfs file
+fn read_number(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Read a sysfs cache size such as `48K` in bytes
+fn read_size(path: &Path) -> Option<u64> {
+    let size = std::fs::read_to_string(path).ok()?;
+    let size = size.trim();
+
+    let (number, scale) = match size.as_bytes().last()? {
+        b'K' => (&size[..size.len() - 1], 1024),
+        b'M' => (&size[..size.len() - 1], 1024 * 1024),
+        _ => (size, 1),
+    };
+
+    Some(number.parse::<u64>().ok()? * scale)
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Collect the CPU model and topology now and attach it to every report and snapshot
+    pub fn capture_cpu_info(&mut self) {
+        cpu_info();
+        self.capture_cpu_info = true;
+    }
+
+    /// The CPU model and topology, if it is being captured
+    pub(crate) fn captured_cpu_info(&self) -> Option<CpuInfo> {
+        self.capture_cpu_info.then(|| cpu_info().clone())
+    }
+}
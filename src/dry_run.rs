@@ -0,0 +1,72 @@
+//! Implements the dry-run mode recording only the discovered call structure of the timers
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::{OpenTimer, Profiler, ThreadProfile};
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Enter `timer` on the locked `thread` without reading the clock, only counting the
+    /// hit and the full stack it was entered from
+    pub(crate) fn enter_timer_dry_run(
+        &self,
+        thread: &mut ThreadProfile<TIMERS>,
+        timer: &'static str,
+    ) -> OpenTimer {
+        let parent = thread.stack.last().copied();
+        thread.stack.push(timer);
+
+        let stack = thread.stack.clone();
+        *thread.call_counts.entry(stack).or_default() += 1;
+
+        thread.timers[self.timer_index(timer)].hits += 1;
+
+        OpenTimer {
+            timer,
+            parent,
+            ..Default::default()
+        }
+    }
+
+    /// Print the call structure discovered during a dry run
+    pub fn print_call_tree(&self) {
+        let _ = self.write_call_tree(&mut std::io::stderr().lock());
+    }
+
+    /// Write the call structure discovered during a dry run to `out` as an indented tree
+    /// of every timer under each of its parents along with the number of hits
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    pub fn write_call_tree(&self, out: &mut impl Write) -> std::io::Result<()> {
+        // Merge the stacks of all threads. Sorting the stacks places each parent directly
+        // before its children.
+        let mut merged: BTreeMap<Vec<&'static str>, u64> = BTreeMap::new();
+        for thread in self.thread_profiles() {
+            for (stack, hits) in &thread.call_counts {
+                *merged.entry(stack.clone()).or_default() += hits;
+            }
+        }
+
+        let name_width = merged
+            .keys()
+            .map(|stack| (stack.len() - 1) * 2 + stack.last().map_or(0, |name| name.len()))
+            .max()
+            .unwrap_or(0)
+            .max("TIMER".len());
+
+        writeln!(out, "Call structure (dry run, no timing)")?;
+        writeln!(out, "{:<name_width$} | HITS", "TIMER")?;
+        for (stack, hits) in merged {
+            let Some(name) = stack.last() else {
+                continue;
+            };
+
+            let indent = (stack.len() - 1) * 2;
+            let name = format!("{:indent$}{name}", "");
+            writeln!(out, "{name:<name_width$} | {hits}")?;
+        }
+
+        out.flush()
+    }
+}
@@ -0,0 +1,178 @@
+//! Implements the eviction of the least hit timers once the soft limit of timers is reached
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Profiler, ShortStreak, ThreadProfile};
+
+/// The name of the timer aggregating the evicted timers
+const OVERFLOW_LABEL: &str = "Other";
+
+/// Move the value of every key of `map` renamed by `rename` to its new key, adding it to
+/// the value already there with `add`
+fn rename_keys<K: Ord, V: Default>(
+    map: &mut BTreeMap<K, V>,
+    rename: impl Fn(&K) -> Option<K>,
+    add: impl Fn(&mut V, V),
+) {
+    if !map.keys().any(|key| rename(key).is_some()) {
+        return;
+    }
+
+    for (key, value) in std::mem::take(map) {
+        let key = rename(&key).unwrap_or(key);
+        add(map.entry(key).or_default(), value);
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Make room for the new `timer_name` by folding the least hit timer into the
+    /// overflow timer, returning the index for `timer_name`
+    ///
+    /// Timers that are currently open on any thread are never evicted. If every timer
+    /// is open, `timer_name` itself is aggregated into the overflow timer.
+    ///
+    /// The profiler is borrowed exclusively, which for the static profiler means under
+    /// the write lock of [`crate::ProfilerCell`], as the reports take it: no thread is
+    /// entering or exiting a timer while the victim is chosen and folded, and no report
+    /// sees it half folded.
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn evict_timer(&mut self, timer_name: &'static str) -> usize {
+        // Warn only once, on the first timer that doesn't fit
+        if self.evicted_timers == 0 {
+            eprintln!(
+                "Warning: more than {} timers were used, aggregating the least hit timers into \"{OVERFLOW_LABEL}\". Increase the TIMERS of the Profiler to keep them.",
+                Self::OVERFLOW_INDEX
+            );
+        }
+
+        // Expose the overflow timer to everything iterating over the used timers
+        self.timer_names[Self::OVERFLOW_INDEX] = OVERFLOW_LABEL;
+        self.next_index = TIMERS as u32;
+        self.evicted_timers += 1;
+
+        let mut open: BTreeSet<&'static str> = BTreeSet::new();
+        let mut hits = [0u64; TIMERS];
+        for thread in self.thread_profiles_mut() {
+            open.extend(thread.stack.iter().copied());
+            for (hits, timer) in hits.iter_mut().zip(&thread.timers) {
+                *hits += timer.hits;
+            }
+        }
+
+        let victim = (0..Self::OVERFLOW_INDEX)
+            .filter(|index| !open.contains(self.timer_names[*index]))
+            .min_by_key(|index| hits[*index]);
+
+        let Some(victim) = victim else {
+            self.timer_name_to_index
+                .insert(timer_name, Self::OVERFLOW_INDEX as u32);
+            return Self::OVERFLOW_INDEX;
+        };
+
+        let victim_name = self.timer_names[victim];
+
+        // Fold the victim into the overflow timer on every thread and reset the rest of
+        // its state for the new timer
+        for thread in self.thread_profiles_mut() {
+            let evicted = std::mem::take(&mut thread.timers[victim]);
+            thread.timers[Self::OVERFLOW_INDEX].merge(&evicted);
+
+            thread.short_streaks[victim] = ShortStreak::EMPTY;
+            thread.recursion_depths[victim] = 0;
+            thread.ignore_decisions.remove(victim_name);
+            thread.ignored_hits.remove(victim_name);
+            thread.fold_into_overflow(victim_name);
+        }
+
+        #[cfg(feature = "otel")]
+        {
+            self.otel_exported[victim] = crate::Timer::default();
+        }
+
+        // Future hits of the victim go straight to the overflow timer
+        self.timer_name_to_index
+            .insert(victim_name, Self::OVERFLOW_INDEX as u32);
+
+        self.timer_name_to_index.insert(timer_name, victim as u32);
+        self.timer_names[victim] = timer_name;
+
+        victim
+    }
+
+    /// Get the label of the timer at `index` to show in reports
+    pub(crate) fn timer_label(&self, index: usize) -> String {
+        if index == Self::OVERFLOW_INDEX && self.evicted_timers > 0 {
+            return format!("{OVERFLOW_LABEL} ({} timers)", self.evicted_timers);
+        }
+
+        self.timer_names[index].to_string()
+    }
+}
+
+impl<const TIMERS: usize> ThreadProfile<TIMERS> {
+    /// Fold the data of the evicted timer `victim` kept by name into the overflow timer,
+    /// as its name now maps to the overflow timer. The sampled data, such as exemplars
+    /// and percentiles, is dropped instead, as it is not merged by [`Profiler::merge`]
+    /// either.
+    fn fold_into_overflow(&mut self, victim: &'static str) {
+        let rename = |timer: &'static str| {
+            if timer == victim {
+                OVERFLOW_LABEL
+            } else {
+                timer
+            }
+        };
+        let renamed = |timer: &&'static str| (*timer == victim).then_some(OVERFLOW_LABEL);
+        let add = |total: &mut u64, value: u64| *total = total.wrapping_add(value);
+
+        rename_keys(&mut self.page_faults, renamed, add);
+        rename_keys(&mut self.sampled_parent_cycles, renamed, add);
+        rename_keys(&mut self.allocations, renamed, |total, value| {
+            total.add(value);
+        });
+        rename_keys(&mut self.context_switches, renamed, |total, value| {
+            total.add(value);
+        });
+        rename_keys(&mut self.log2_histograms, renamed, |total, value| {
+            total.merge(&value);
+        });
+        rename_keys(
+            &mut self.user_counters,
+            |(timer, counter)| (*timer == victim).then_some((OVERFLOW_LABEL, *counter)),
+            add,
+        );
+
+        for points in self.series.values_mut() {
+            rename_keys(points, renamed, |total, point| {
+                total.hits += point.hits;
+                total.cycles = total.cycles.wrapping_add(point.cycles);
+            });
+        }
+
+        let renamed_stack = |stack: &Vec<&'static str>| {
+            stack
+                .contains(&victim)
+                .then(|| stack.iter().copied().map(rename).collect())
+        };
+        rename_keys(&mut self.stack_times, renamed_stack, add);
+        rename_keys(&mut self.call_counts, renamed_stack, add);
+
+        rename_keys(
+            &mut self.caller_edges,
+            |(callee, caller)| {
+                (*callee == victim || *caller == Some(victim))
+                    .then(|| (rename(callee), caller.map(rename)))
+            },
+            |total, edge| {
+                total.caller = edge.caller.map(rename);
+                total.hits += edge.hits;
+                total.cycles = total.cycles.wrapping_add(edge.cycles);
+            },
+        );
+
+        self.exemplars.remove(victim);
+        self.latency_samples.remove(victim);
+        self.sketches.remove(victim);
+        #[cfg(feature = "hist")]
+        self.histograms.remove(victim);
+    }
+}
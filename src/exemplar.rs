@@ -0,0 +1,192 @@
+//! Implements the reservoir of exemplar hits kept for each timer
+use std::io::Write;
+use std::time::Duration;
+
+use crate::{Profiler, ThreadProfile};
+
+/// Number of exemplars kept for each timer
+const EXEMPLARS_PER_TIMER: usize = 8;
+
+/// A single sampled hit of a timer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exemplar {
+    /// The timestamp (in cycles) when the timer started
+    pub start: u64,
+
+    /// The time (in cycles) spent in the timer, including child timers
+    pub duration: u64,
+
+    /// The thread that hit the timer
+    pub thread_id: usize,
+
+    /// The open timers when this timer was hit, outermost first
+    pub parents: Vec<&'static str>,
+}
+
+/// A uniform random sample of the hits of a timer on a single thread
+#[derive(Debug, Clone, Default)]
+pub struct Reservoir {
+    /// The number of hits seen by this reservoir
+    seen: u64,
+
+    /// The sampled hits
+    exemplars: Vec<Exemplar>,
+
+    /// State of the xorshift random number generator
+    rng: u64,
+}
+
+impl Reservoir {
+    /// Get the next random number of this reservoir
+    fn next_random(&mut self) -> u64 {
+        xorshift(&mut self.rng)
+    }
+}
+
+/// Advance the xorshift random number generator `state`, returning the next number
+pub(crate) fn xorshift(state: &mut u64) -> u64 {
+    if *state == 0 {
+        *state = 0x2545_f491_4f6c_dd1d;
+    }
+
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Sample a hit of `timer` on the given thread into its reservoir if exemplar
+    /// recording is enabled
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn record_exemplar(
+        &self,
+        thread_id: usize,
+        thread: &mut ThreadProfile<TIMERS>,
+        timer: &'static str,
+        start: u64,
+        stop: u64,
+    ) {
+        if !self.record_exemplars {
+            return;
+        }
+
+        let reservoir = thread.exemplars.entry(timer).or_default();
+        reservoir.seen += 1;
+
+        // Keep each hit with probability `EXEMPLARS_PER_TIMER / seen` (Algorithm R)
+        let slot = if reservoir.exemplars.len() < EXEMPLARS_PER_TIMER {
+            None
+        } else {
+            let index = (reservoir.next_random() % reservoir.seen) as usize;
+            if index >= EXEMPLARS_PER_TIMER {
+                return;
+            }
+            Some(index)
+        };
+
+        // `timer` was already popped, so the stack only holds its parents
+        let parents = thread.stack.clone();
+
+        let exemplar = Exemplar {
+            start,
+            duration: stop - start,
+            thread_id,
+            parents,
+        };
+
+        let reservoir = thread.exemplars.entry(timer).or_default();
+        match slot {
+            Some(index) => reservoir.exemplars[index] = exemplar,
+            None => reservoir.exemplars.push(exemplar),
+        }
+    }
+
+    /// Get a uniform sample of the hits of `timer` across all threads
+    ///
+    /// Each thread's exemplars are weighted by the number of hits they represent, so
+    /// threads that hit the timer more often are more likely to be in the sample.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn exemplars(&self, timer: &'static str) -> Vec<Exemplar> {
+        let mut rng = Reservoir::default();
+
+        // Weighted sampling without replacement: keep the largest `u^(1/weight)` keys
+        let mut keyed = Vec::new();
+        for thread in self.thread_profiles() {
+            let Some(reservoir) = thread.exemplars.get(timer) else {
+                continue;
+            };
+
+            let weight = reservoir.seen as f64 / reservoir.exemplars.len() as f64;
+            for exemplar in &reservoir.exemplars {
+                let random = (rng.next_random() >> 11) as f64 / (1u64 << 53) as f64;
+                keyed.push((random.powf(1.0 / weight), exemplar.clone()));
+            }
+        }
+
+        keyed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        let mut result: Vec<Exemplar> = keyed
+            .into_iter()
+            .take(EXEMPLARS_PER_TIMER)
+            .map(|(_, exemplar)| exemplar)
+            .collect();
+
+        result.sort_by_key(|exemplar| exemplar.start);
+        result
+    }
+
+    /// Write the exemplars of every sampled timer to `out`
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn write_exemplars(
+        &self,
+        os_timer_freq: f64,
+        out: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        let mut timers: Vec<&'static str> = Vec::new();
+        let mut first_start = None;
+        for thread in self.thread_profiles() {
+            timers.extend(thread.exemplars.keys().copied());
+
+            // Show the start of each exemplar relative to the first one
+            let starts = thread
+                .exemplars
+                .values()
+                .flat_map(|reservoir| &reservoir.exemplars)
+                .map(|exemplar| exemplar.start);
+            first_start = first_start.into_iter().chain(starts).min();
+        }
+        timers.sort_unstable();
+        timers.dedup();
+
+        if timers.is_empty() {
+            return Ok(());
+        }
+
+        let first_start = first_start.unwrap_or(0);
+
+        writeln!(out, "Exemplars:")?;
+        for timer in timers {
+            writeln!(out, "  {timer}")?;
+
+            for exemplar in self.exemplars(timer) {
+                let at = Duration::from_secs_f64(
+                    exemplar.start.saturating_sub(first_start) as f64 / os_timer_freq,
+                );
+                let duration = Duration::from_secs_f64(exemplar.duration as f64 / os_timer_freq);
+
+                writeln!(
+                    out,
+                    "    at {:>10} | {:>10} | thread {:>3} | {}",
+                    format!("{at:.2?}"),
+                    format!("{duration:.2?}"),
+                    exemplar.thread_id,
+                    exemplar.parents.join(" -> ")
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+//! Implements restricting the report to the timers matching a glob or regex, or to the
+//! most expensive timers
+use std::io::Write;
+
+use crate::render::{ReportRenderer, TextRenderer};
+use crate::{Profiler, TimerReport};
+
+/// The environment variable holding the filter applied to every report
+pub const FILTER_ENV_VAR: &str = "TIMELOOP_FILTER";
+
+/// The prefix of a filter pattern that is a regex rather than a glob
+const REGEX_PREFIX: &str = "re:";
+
+/// A filter of the timers shown in the report
+///
+/// A pattern is a glob where `*` matches any run of characters and `?` any single
+/// character. Patterns prefixed with `re:` are regexes, which need the `regex` feature.
+#[derive(Debug, Clone)]
+pub enum TimerFilter {
+    /// A glob matching the whole timer name
+    Glob(String),
+
+    /// A regex matching anywhere in the timer name
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl TimerFilter {
+    /// Parse the filter `pattern`
+    ///
+    /// # Errors
+    ///
+    /// * The pattern is an invalid regex
+    /// * The pattern is a regex but the `regex` feature is disabled
+    pub fn new(pattern: &str) -> std::io::Result<Self> {
+        let Some(regex) = pattern.strip_prefix(REGEX_PREFIX) else {
+            return Ok(TimerFilter::Glob(pattern.to_string()));
+        };
+
+        #[cfg(feature = "regex")]
+        {
+            regex::Regex::new(regex)
+                .map(TimerFilter::Regex)
+                .map_err(|err| invalid_input(err.to_string()))
+        }
+
+        #[cfg(not(feature = "regex"))]
+        {
+            Err(invalid_input(format!(
+                "Regex filter {regex:?} needs the `regex` feature of timeloop"
+            )))
+        }
+    }
+
+    /// Get the filter set by the `TIMELOOP_FILTER` environment variable, if it is set
+    /// to a valid pattern
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let pattern = std::env::var(FILTER_ENV_VAR).ok()?;
+
+        match TimerFilter::new(&pattern) {
+            Ok(filter) => Some(filter),
+            Err(err) => {
+                eprintln!("Ignoring {FILTER_ENV_VAR}: {err}");
+                None
+            }
+        }
+    }
+
+    /// Check if the timer `name` passes this filter
+    #[must_use]
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            TimerFilter::Glob(glob) => glob_match(glob.as_bytes(), name.as_bytes()),
+            #[cfg(feature = "regex")]
+            TimerFilter::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Check if the whole of `name` matches `glob`
+fn glob_match(glob: &[u8], name: &[u8]) -> bool {
+    let (mut g, mut n) = (0, 0);
+
+    // The position of the last `*` and the name position it currently matches up to
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match glob.get(g) {
+            Some(b'*') => {
+                backtrack = Some((g, n));
+                g += 1;
+            }
+            Some(b'?') => {
+                g += 1;
+                n += 1;
+            }
+            Some(c) if *c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => {
+                // Let the last `*` match one more character
+                let Some((star, matched)) = backtrack else {
+                    return false;
+                };
+
+                backtrack = Some((star, matched + 1));
+                g = star + 1;
+                n = matched + 1;
+            }
+        }
+    }
+
+    glob[g..].iter().all(|c| *c == b'*')
+}
+
+/// Create an [`std::io::ErrorKind::InvalidInput`] error with the given `message`
+fn invalid_input(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Print the report of only the timers whose names match `pattern`, see
+    /// [`TimerFilter`]
+    ///
+    /// # Errors
+    ///
+    /// * `pattern` is not a valid filter
+    pub fn print_filtered(&mut self, pattern: &str) -> std::io::Result<()> {
+        let filter = TimerFilter::new(pattern)?;
+        self.print_filtered_to(&filter, &mut std::io::stderr().lock())
+    }
+
+    /// Write the report of only the timers passing `filter` to `out`
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    pub fn print_filtered_to(
+        &mut self,
+        filter: &TimerFilter,
+        out: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let mut report = self.create_report();
+        report.timers.retain(|timer| filter.matches(&timer.name));
+
+        let renderer = TextRenderer {
+            color: self.color.enabled(),
+        };
+        renderer.render(&report, out)
+    }
+
+    /// Print the report of only the `n` most expensive timers (by exclusive time), with
+    /// the rest folded into a single row
+    pub fn print_top(&mut self, n: usize) {
+        let _ = self.print_top_to(n, &mut std::io::stderr().lock());
+    }
+
+    /// Write the report of only the `n` most expensive timers to `out`
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn print_top_to(&mut self, n: usize, out: &mut impl Write) -> std::io::Result<()> {
+        let mut report = self.create_report();
+
+        // The timers are already sorted by most exclusive time first
+        if report.timers.len() > n {
+            let rest = report.timers.split_off(n);
+
+            let mut other = TimerReport {
+                name: format!("Other ({} timers)", rest.len()),
+                min_cycles: rest.iter().map(|timer| timer.min_cycles).min().unwrap_or(0),
+                max_cycles: rest.iter().map(|timer| timer.max_cycles).max().unwrap_or(0),
+                ..TimerReport::default()
+            };
+
+            for timer in &rest {
+                other.exclusive_time = other.exclusive_time.wrapping_add(timer.exclusive_time);
+                other.hits += timer.hits;
+                other.bytes_processed += timer.bytes_processed;
+                other.seconds += timer.seconds;
+            }
+
+            other.percent = other.exclusive_time as f64 / report.total_time.max(1) as f64 * 100.;
+            report.timers.push(other);
+        }
+
+        let renderer = TextRenderer {
+            color: self.color.enabled(),
+        };
+        renderer.render(&report, out)
+    }
+}
@@ -0,0 +1,215 @@
+//! Implements the self-contained HTML flamechart of the recorded timer events
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use crate::trace::json_escape;
+use crate::{calibration, timer_hash, Event, Profiler};
+
+/// Styles and scripts drawing one zoomable lane per thread from the embedded `DATA`
+const FLAMECHART_HEAD: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>timeloop flamechart</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+canvas { display: block; width: 100%; border: 1px solid #ddd; margin-bottom: 1em; }
+h3 { margin: 0.5em 0 0.2em; font-size: 1em; }
+#tooltip { position: fixed; pointer-events: none; background: #222; color: #fff; padding: 0.3em 0.6em; font-family: monospace; font-size: 12px; display: none; }
+</style>
+</head>
+<body>
+<h1>timeloop flamechart</h1>
+<p>Scroll to zoom, drag to pan, double click to reset.</p>
+<div id="lanes"></div>
+<div id="tooltip"></div>
+"#;
+
+/// Script drawing the lanes, shared by every flamechart
+const FLAMECHART_SCRIPT: &str = r"<script>
+const ROW = 18;
+let view = { start: DATA.start, end: DATA.end };
+const lanes = DATA.threads.map(thread => {
+  const title = document.createElement('h3');
+  title.textContent = thread.name;
+  const canvas = document.createElement('canvas');
+  canvas.height = (thread.depth + 1) * ROW;
+  document.getElementById('lanes').append(title, canvas);
+  return { thread, canvas };
+});
+function color(hash) {
+  return 'hsl(' + (hash % 360) + ',60%,' + (55 + hash % 20) + '%)';
+}
+function draw() {
+  for (const { thread, canvas } of lanes) {
+    canvas.width = canvas.clientWidth;
+    const ctx = canvas.getContext('2d');
+    const scale = canvas.width / (view.end - view.start);
+    ctx.clearRect(0, 0, canvas.width, canvas.height);
+    ctx.font = '11px monospace';
+    for (const [start, dur, depth, name] of thread.spans) {
+      const x = (start - view.start) * scale, w = dur * scale;
+      if (x + w < 0 || x > canvas.width || w < 0.5) continue;
+      ctx.fillStyle = color(DATA.hashes[name]);
+      ctx.fillRect(x, depth * ROW, Math.max(w - 1, 0.5), ROW - 1);
+      if (w > 30) {
+        ctx.fillStyle = '#000';
+        ctx.save();
+        ctx.beginPath();
+        ctx.rect(x, depth * ROW, w - 1, ROW);
+        ctx.clip();
+        ctx.fillText(DATA.names[name], Math.max(x, 0) + 3, depth * ROW + 13);
+        ctx.restore();
+      }
+    }
+  }
+}
+function spanAt(thread, canvas, event) {
+  const rect = canvas.getBoundingClientRect();
+  const time = view.start + (event.clientX - rect.left) / canvas.width * (view.end - view.start);
+  const depth = Math.floor((event.clientY - rect.top) / ROW);
+  return thread.spans.find(([start, dur, d]) => d === depth && start <= time && time <= start + dur);
+}
+let drag = null;
+const tooltip = document.getElementById('tooltip');
+for (const { thread, canvas } of lanes) {
+  canvas.addEventListener('wheel', event => {
+    event.preventDefault();
+    const rect = canvas.getBoundingClientRect();
+    const at = view.start + (event.clientX - rect.left) / canvas.width * (view.end - view.start);
+    const factor = event.deltaY < 0 ? 0.8 : 1.25;
+    view = { start: at - (at - view.start) * factor, end: at + (view.end - at) * factor };
+    draw();
+  });
+  canvas.addEventListener('mousedown', event => { drag = { x: event.clientX, view, width: canvas.width }; });
+  canvas.addEventListener('dblclick', () => { view = { start: DATA.start, end: DATA.end }; draw(); });
+  canvas.addEventListener('mousemove', event => {
+    const span = spanAt(thread, canvas, event);
+    tooltip.style.display = span ? 'block' : 'none';
+    if (span) {
+      tooltip.textContent = DATA.names[span[3]] + ' ' + span[1].toFixed(3) + ' us' + (span[4] > 1 ? ' (' + span[4] + ' hits)' : '');
+      tooltip.style.left = event.clientX + 12 + 'px';
+      tooltip.style.top = event.clientY + 12 + 'px';
+    }
+  });
+  canvas.addEventListener('mouseleave', () => { tooltip.style.display = 'none'; });
+}
+window.addEventListener('mousemove', event => {
+  if (!drag) return;
+  const shift = (event.clientX - drag.x) / drag.width * (drag.view.end - drag.view.start);
+  view = { start: drag.view.start - shift, end: drag.view.end - shift };
+  draw();
+});
+window.addEventListener('mouseup', () => { drag = null; });
+window.addEventListener('resize', draw);
+draw();
+</script>
+</body>
+</html>
+";
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Write the recorded events as a single-file HTML flamechart to `path`, with one
+    /// zoomable lane per thread and the spans colored by timer
+    ///
+    /// # Errors
+    ///
+    /// * Failed to create or write to `path`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn write_flamechart(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut out = std::io::BufWriter::new(file);
+
+        let cycles_per_us = calibration().frequency / 1_000_000.0;
+
+        // Start the chart at the first recorded event
+        let threads = self.thread_events();
+        let events = || threads.iter().flat_map(|(_, _, events)| events);
+        let first_timestamp = events().map(|event| event.start).min().unwrap_or(0);
+        let last_timestamp = events().map(|event| event.stop).max().unwrap_or(0);
+
+        let to_us = |timestamp: u64| timestamp.wrapping_sub(first_timestamp) as f64 / cycles_per_us;
+
+        // Each timer name is written once and referenced by its index
+        let mut names: BTreeMap<&'static str, usize> = BTreeMap::new();
+        for event in events() {
+            let next_name = names.len();
+            names.entry(event.timer).or_insert(next_name);
+        }
+
+        let mut ordered_names = vec![""; names.len()];
+        for (name, index) in &names {
+            ordered_names[*index] = name;
+        }
+
+        out.write_all(FLAMECHART_HEAD.as_bytes())?;
+        write!(
+            out,
+            "<script>\nconst DATA = {{\"start\":0,\"end\":{:.3},\"names\":[",
+            to_us(last_timestamp).max(0.001)
+        )?;
+
+        for (i, name) in ordered_names.iter().enumerate() {
+            let comma = if i > 0 { "," } else { "" };
+            write!(
+                out,
+                "{comma}\"{}\"",
+                json_escape(name).replace('<', "\\u003c")
+            )?;
+        }
+
+        write!(out, "],\"hashes\":[")?;
+        for (i, name) in ordered_names.iter().enumerate() {
+            let comma = if i > 0 { "," } else { "" };
+            write!(out, "{comma}{}", timer_hash(name) % 1_000_000)?;
+        }
+
+        write!(out, "],\"threads\":[")?;
+
+        let mut first = true;
+        for (thread_id, label, events) in &threads {
+            if !first {
+                write!(out, ",")?;
+            }
+            first = false;
+
+            // Events are recorded when a timer stops. Order them by start (outermost
+            // first) to rebuild the depth of each span.
+            let mut spans: Vec<&Event> = events.iter().filter(|event| !event.instant).collect();
+            spans.sort_by_key(|event| (event.start, std::cmp::Reverse(event.stop)));
+
+            let mut open: Vec<&Event> = Vec::new();
+            let mut max_depth = 0;
+
+            write!(
+                out,
+                "\n{{\"id\":{thread_id},\"name\":\"{}\",\"spans\":[",
+                json_escape(label).replace('<', "\\u003c")
+            )?;
+            for (i, span) in spans.into_iter().enumerate() {
+                while open.pop_if(|last| last.stop <= span.start).is_some() {}
+
+                let depth = open.len();
+                max_depth = max_depth.max(depth);
+                open.push(span);
+
+                let comma = if i > 0 { "," } else { "" };
+                write!(
+                    out,
+                    "{comma}[{:.3},{:.3},{depth},{},{}]",
+                    to_us(span.start),
+                    span.stop.wrapping_sub(span.start) as f64 / cycles_per_us,
+                    names[span.timer],
+                    span.hits
+                )?;
+            }
+
+            write!(out, "],\"depth\":{max_depth}}}")?;
+        }
+
+        writeln!(out, "]}};\n</script>")?;
+        out.write_all(FLAMECHART_SCRIPT.as_bytes())?;
+        out.flush()
+    }
+}
@@ -0,0 +1,66 @@
+//! Implements tracking of the full timer stacks and the folded stack export
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::{Profiler, ThreadProfile};
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Pop the innermost timer of the locked `thread`. If the stacks are recorded,
+    /// attribute `elapsed` cycles to its stack and remove them from the parent stack.
+    pub(crate) fn pop_stack(&self, thread: &mut ThreadProfile<TIMERS>, elapsed: u64) {
+        let stack = &mut thread.stack;
+
+        if !self.record_stacks {
+            stack.pop();
+            return;
+        }
+
+        let stack_times = &mut thread.stack_times;
+
+        if let Some(time) = stack_times.get_mut(stack.as_slice()) {
+            *time = time.wrapping_add(elapsed);
+        } else {
+            stack_times.insert(stack.clone(), elapsed);
+        }
+
+        stack.pop();
+
+        // The parent stack only keeps the time spent outside of this timer
+        if !stack.is_empty() {
+            if let Some(time) = stack_times.get_mut(stack.as_slice()) {
+                *time = time.wrapping_sub(elapsed);
+            } else {
+                stack_times.insert(stack.clone(), 0_u64.wrapping_sub(elapsed));
+            }
+        }
+    }
+
+    /// Write the timer stacks across all threads in the folded stack format
+    /// (`parent;child cycles`) used by inferno and `flamegraph.pl`
+    ///
+    /// Only the hits while [`Profiler::record_stacks`] is set are written.
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    pub fn write_folded_stacks(&self, mut out: impl Write) -> std::io::Result<()> {
+        let mut folded: BTreeMap<Vec<&'static str>, u64> = BTreeMap::new();
+
+        for thread in self.thread_profiles() {
+            for (stack, time) in &thread.stack_times {
+                let entry = folded.entry(stack.clone()).or_default();
+                *entry = entry.wrapping_add(*time);
+            }
+        }
+
+        for (stack, time) in folded {
+            if time == 0 {
+                continue;
+            }
+
+            writeln!(out, "{} {time}", stack.join(";"))?;
+        }
+
+        out.flush()
+    }
+}
@@ -0,0 +1,23 @@
+//! Implements resetting the profiler in a child process after `fork()`
+use crate::{Profiler, ThreadTimerStatus};
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Reset the profiler state inherited from the parent process, keeping only the
+    /// timers that are currently open on `thread_id`, the only thread left after a fork
+    ///
+    /// The child then only reports the time spent after the fork. Timers that were
+    /// open during the fork still count their time from before the fork when they exit.
+    pub fn reset_after_fork(&mut self, thread_id: usize) {
+        // The other threads do not exist in the child
+        for (id, thread) in self.thread_profiles_mut().enumerate() {
+            if id != thread_id {
+                thread.status = ThreadTimerStatus::Stopped;
+                thread.stack.clear();
+                thread.recursion_depths = [0; TIMERS];
+            }
+        }
+
+        // Restart the clock of the forking thread at the fork
+        self.reset();
+    }
+}
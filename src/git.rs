@@ -0,0 +1,101 @@
+//! Implements the capture of the git commit of the profiled program attached to snapshots
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use crate::Profiler;
+
+/// The environment variable naming the git commit of the profiled program, used instead
+/// of asking `git` in the current directory
+pub const GIT_COMMIT_ENV_VAR: &str = "TIMELOOP_GIT_COMMIT";
+
+/// The git commit of the program that produced a snapshot
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GitInfo {
+    /// The hash of the checked out commit
+    pub commit: String,
+
+    /// Whether the working tree had uncommitted changes
+    pub dirty: bool,
+}
+
+impl std::fmt::Display for GitInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", &self.commit[..self.commit.len().min(12)])?;
+
+        if self.dirty {
+            write!(f, "-dirty")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Run `git` with `args` in the current directory, returning its trimmed output if it
+/// succeeded
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Get the git commit of the profiled program, collected once on first use
+///
+/// The commit is taken from the `TIMELOOP_GIT_COMMIT` environment variable if it is set,
+/// otherwise from the repository of the current directory.
+pub fn git_info() -> Option<&'static GitInfo> {
+    static GIT_INFO: OnceLock<Option<GitInfo>> = OnceLock::new();
+
+    GIT_INFO
+        .get_or_init(|| {
+            if let Ok(commit) = std::env::var(GIT_COMMIT_ENV_VAR) {
+                return Some(GitInfo {
+                    commit: commit.trim().to_string(),
+                    dirty: false,
+                });
+            }
+
+            let commit = git(&["rev-parse", "HEAD"])?;
+            let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+                .is_some_and(|status| !status.is_empty());
+
+            Some(GitInfo { commit, dirty })
+        })
+        .as_ref()
+}
+
+/// Get the one line summaries of the commits after `before` up to `after`, newest first,
+/// if both are known to the repository of the current directory
+pub(crate) fn commits_between(before: &GitInfo, after: &GitInfo) -> Option<Vec<String>> {
+    let range = format!("{}..{}", before.commit, after.commit);
+    let log = git(&["log", "--format=%h %s", &range])?;
+
+    Some(log.lines().map(str::to_string).collect())
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Collect the git commit now and attach it to every snapshot, for comparing dumps
+    /// across commits
+    pub fn capture_git_info(&mut self) {
+        git_info();
+        self.capture_git_info = true;
+    }
+
+    /// The git commit, if it is being captured and is known
+    pub(crate) fn captured_git_info(&self) -> Option<GitInfo> {
+        if self.capture_git_info {
+            git_info().cloned()
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Implements a profiler instance owned by a library instead of the global profiler
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::thread_slot::{owner_is_alive, thread_owner};
+use crate::{is_enabled, OpenTimer, Profiler, Report, ThreadTimerStatus, MAX_TIMERS};
+
+/// A profiler that is not the global profiler of `create_profiler!`, such as for a
+/// library crate to ship its own instrumentation without requiring the binary to create
+/// a profiler and without colliding with the binary's profiler
+///
+/// ```ignore
+/// static PROFILER: timeloop::ProfilerHandle = timeloop::ProfilerHandle::new();
+///
+/// fn parse() {
+///     let _timer = PROFILER.timer("parse");
+/// }
+///
+/// PROFILER.print();
+/// ```
+///
+/// The profiler is behind a mutex, which is locked on every timer entry and exit, and
+/// threads are started on their first timer. Each thread is given its own slot of the
+/// profiler on its first timer, which is given to another thread once the thread
+/// exited, so `THREADS` bounds the number of threads using the profiler at once, no
+/// matter how many threads use other profilers.
+#[derive(Debug)]
+pub struct ProfilerHandle<const THREADS: usize = 64, const TIMERS: usize = MAX_TIMERS> {
+    /// The profiler of this handle
+    profiler: Mutex<Profiler<THREADS, TIMERS>>,
+
+    /// The owner token of the thread bound to each slot of the profiler, or 0 while the
+    /// slot was never bound
+    slots: [AtomicU64; THREADS],
+}
+
+impl<const THREADS: usize, const TIMERS: usize> ProfilerHandle<THREADS, TIMERS> {
+    /// Create an empty profiler, such as for a `static`
+    #[must_use]
+    pub const fn new() -> Self {
+        ProfilerHandle {
+            profiler: Mutex::new(Profiler::new()),
+            slots: [const { AtomicU64::new(0) }; THREADS],
+        }
+    }
+
+    /// Get the slot of the current thread in the profiler, binding a slot to the current
+    /// thread the first time it is seen
+    ///
+    /// The slots are looked up from the thread slot onwards and never go back to free, so
+    /// the first slot that is either free or already bound to the thread is its slot.
+    /// Once every slot is bound, the slot of a thread that exited is bound again. The
+    /// exiting threads, which already gave up their thread slot, share a single slot.
+    ///
+    /// # Panics
+    ///
+    /// * More than `THREADS` threads use the profiler at once
+    fn thread_id(&self) -> usize {
+        let key = match thread_owner() {
+            0 => u64::MAX,
+            owner => owner,
+        };
+        let start = crate::_thread_slot();
+
+        for offset in 0..THREADS {
+            let thread_id = (start + offset) % THREADS;
+            match self.slots[thread_id].compare_exchange(
+                0,
+                key,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return thread_id,
+                Err(bound) if bound == key => return thread_id,
+                Err(_) => {}
+            }
+        }
+
+        // Every slot is bound, so take over the slot of a thread that exited
+        for offset in 0..THREADS {
+            let thread_id = (start + offset) % THREADS;
+            let bound = self.slots[thread_id].load(Ordering::Acquire);
+            if !owner_is_alive(bound)
+                && self.slots[thread_id]
+                    .compare_exchange(bound, key, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+            {
+                return thread_id;
+            }
+        }
+
+        panic!("Too many threads. Increase the THREADS of the ProfilerHandle");
+    }
+
+    /// Lock the profiler, even if a thread panicked while holding it
+    fn lock(&self) -> MutexGuard<'_, Profiler<THREADS, TIMERS>> {
+        self.profiler.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Enter `timer` on the current thread, exiting it when the returned guard is dropped
+    pub fn timer(&self, timer: &'static str) -> HandleTimer<'_, THREADS, TIMERS> {
+        self.timer_with_bandwidth(timer, 0)
+    }
+
+    /// Enter `timer` on the current thread processing `bytes_processed` bytes, exiting
+    /// it when the returned guard is dropped
+    pub fn timer_with_bandwidth(
+        &self,
+        timer: &'static str,
+        bytes_processed: u64,
+    ) -> HandleTimer<'_, THREADS, TIMERS> {
+        let mut guard = HandleTimer {
+            handle: self,
+            thread_id: 0,
+            open: OpenTimer::default(),
+            finished: true,
+            canceled: false,
+        };
+
+        if !cfg!(feature = "enable") || !is_enabled() {
+            return guard;
+        }
+
+        let thread_id = self.thread_id();
+        let mut profiler = self.lock();
+        if profiler.is_paused() {
+            return guard;
+        }
+
+        // Threads are profiled from their first timer
+        if profiler.thread(thread_id).status == ThreadTimerStatus::Stopped {
+            profiler.start(thread_id);
+        }
+
+        guard.thread_id = thread_id;
+        guard.open = profiler.enter_timer(thread_id, timer, bytes_processed);
+        guard.finished = false;
+        guard
+    }
+
+    /// Run `func` with exclusive access to the profiler, such as to change its settings
+    /// or take a snapshot
+    pub fn with<R>(&self, func: impl FnOnce(&mut Profiler<THREADS, TIMERS>) -> R) -> R {
+        func(&mut self.lock())
+    }
+
+    /// Gather the structured [`Report`] of every thread that used this profiler
+    pub fn report(&self) -> Report {
+        self.lock().create_report()
+    }
+
+    /// Print the report of this profiler
+    pub fn print(&self) {
+        self.lock().print();
+    }
+
+    /// Clear the timers of this profiler, see [`Profiler::reset`]
+    pub fn reset(&self) {
+        self.lock().reset();
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Default for ProfilerHandle<THREADS, TIMERS> {
+    fn default() -> Self {
+        ProfilerHandle::new()
+    }
+}
+
+/// The guard of a timer entered by [`ProfilerHandle::timer`], exiting the timer when
+/// dropped
+#[derive(Debug)]
+pub struct HandleTimer<'a, const THREADS: usize, const TIMERS: usize> {
+    /// The profiler the timer was entered in
+    handle: &'a ProfilerHandle<THREADS, TIMERS>,
+
+    /// The thread slot the timer was entered on
+    thread_id: usize,
+
+    /// The state of the entered timer
+    open: OpenTimer,
+
+    /// Set when the timer was never entered
+    finished: bool,
+
+    /// Set when the measurement of the timer is discarded on exit
+    canceled: bool,
+}
+
+impl<const THREADS: usize, const TIMERS: usize> HandleTimer<'_, THREADS, TIMERS> {
+    /// Exclude the time until [`HandleTimer::resume`] from this timer
+    pub fn pause(&mut self) {
+        self.open.pause();
+    }
+
+    /// Resume timing this timer after [`HandleTimer::pause`]
+    pub fn resume(&mut self) {
+        self.open.resume();
+    }
+
+    /// Discard the measurement of this timer when it is exited, such as on an error or
+    /// early exit path that would skew the timer
+    pub fn cancel(&mut self) {
+        self.canceled = true;
+    }
+
+    /// Set the number of bytes processed by this timer, for when it is only known inside
+    /// the scope (such as once a read returns)
+    pub fn set_bytes(&mut self, bytes: u64) {
+        self.open.bytes_processed = bytes;
+    }
+
+    /// Add `bytes` to the number of bytes processed by this timer
+    pub fn add_bytes(&mut self, bytes: u64) {
+        self.open.bytes_processed += bytes;
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Drop for HandleTimer<'_, THREADS, TIMERS> {
+    fn drop(&mut self) {
+        // Immediately stop the timer, before waiting on the lock
+        let stop_time = crate::_timestamp();
+
+        if self.finished {
+            return;
+        }
+
+        let profiler = self.handle.lock();
+        if self.canceled {
+            profiler.cancel_timer(self.thread_id, &self.open);
+        } else {
+            profiler.exit_timer(self.thread_id, &self.open, stop_time);
+        }
+    }
+}
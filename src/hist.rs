@@ -0,0 +1,244 @@
+//! Implements the per timer latency histograms and their `HdrHistogram` export
+use std::io::Write;
+use std::path::Path;
+
+use crate::{calibration, Profiler, ThreadProfile};
+
+/// Number of bits of precision kept for every value (under 1% error)
+const SUB_BUCKET_BITS: u32 = 7;
+
+/// Number of linear sub-buckets in the first bucket
+const SUB_BUCKET_COUNT: usize = 1 << SUB_BUCKET_BITS;
+
+/// Number of linear sub-buckets in every following bucket
+const SUB_BUCKET_HALF_COUNT: usize = SUB_BUCKET_COUNT / 2;
+
+/// Number of percentile steps reported for every halving of the remaining percentile
+const PERCENTILE_TICKS_PER_HALF_DISTANCE: f64 = 5.0;
+
+/// A log-linear histogram of the time (in cycles) of every hit of a timer
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    /// The count of every recorded sub-bucket
+    counts: Vec<u64>,
+
+    /// Total number of recorded values
+    total_count: u64,
+}
+
+impl Histogram {
+    /// Record a single `value`
+    pub fn record(&mut self, value: u64) {
+        let index = Self::index(value);
+
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+        }
+
+        self.counts[index] += 1;
+        self.total_count += 1;
+    }
+
+    /// Add all of the values of `other` to this histogram
+    pub fn merge(&mut self, other: &Histogram) {
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+
+        self.total_count += other.total_count;
+    }
+
+    /// The number of recorded values
+    #[must_use]
+    pub fn total_count(&self) -> u64 {
+        self.total_count
+    }
+
+    /// Get the sub-bucket index holding `value`
+    fn index(value: u64) -> usize {
+        if value < SUB_BUCKET_COUNT as u64 {
+            #[allow(clippy::cast_possible_truncation)]
+            return value as usize;
+        }
+
+        let shift = value.ilog2() - (SUB_BUCKET_BITS - 1);
+
+        #[allow(clippy::cast_possible_truncation)]
+        let sub_bucket = (value >> shift) as usize;
+
+        SUB_BUCKET_COUNT + (shift as usize - 1) * SUB_BUCKET_HALF_COUNT + sub_bucket
+            - SUB_BUCKET_HALF_COUNT
+    }
+
+    /// Get the lowest and highest value held by the sub-bucket at `index`
+    fn range(index: usize) -> (u64, u64) {
+        if index < SUB_BUCKET_COUNT {
+            return (index as u64, index as u64);
+        }
+
+        let shift = (index - SUB_BUCKET_COUNT) / SUB_BUCKET_HALF_COUNT + 1;
+        let sub_bucket = (index - SUB_BUCKET_COUNT) % SUB_BUCKET_HALF_COUNT + SUB_BUCKET_HALF_COUNT;
+
+        let lowest = (sub_bucket as u64) << shift;
+        (lowest, lowest + (1 << shift) - 1)
+    }
+
+    /// Get the highest value equivalent to the value at `percentile` (0 to 100)
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn value_at_percentile(&self, percentile: f64) -> (u64, u64) {
+        let wanted = ((percentile / 100.0 * self.total_count as f64).ceil() as u64).max(1);
+
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+
+            if seen >= wanted {
+                return (Self::range(index).1, seen);
+            }
+        }
+
+        (0, seen)
+    }
+
+    /// Write this histogram in the `HdrHistogram` percentile distribution format, scaling
+    /// every value down by `value_scale`
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn write_percentiles(&self, out: &mut impl Write, value_scale: f64) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "{:>12} {:>14} {:>10} {:>14}\n",
+            "Value", "Percentile", "TotalCount", "1/(1-Percentile)"
+        )?;
+
+        if self.total_count == 0 {
+            return Ok(());
+        }
+
+        let mut percentile = 0.0;
+        loop {
+            let (value, count) = self.value_at_percentile(percentile);
+            let value = value as f64 / value_scale;
+
+            if count >= self.total_count {
+                writeln!(out, "{value:12.3} {:14.12} {count:10}", 1.0)?;
+                break;
+            }
+
+            writeln!(
+                out,
+                "{value:12.3} {:14.12} {count:10} {:14.2}",
+                percentile / 100.0,
+                1.0 / (1.0 - percentile / 100.0)
+            )?;
+
+            // Take smaller steps the closer the percentile gets to 100
+            let half_distance = (100.0 / (100.0 - percentile)).log2().floor() + 1.0;
+            let ticks = PERCENTILE_TICKS_PER_HALF_DISTANCE * half_distance.exp2();
+            percentile += 100.0 / ticks;
+        }
+
+        // Summary statistics using the middle of every sub-bucket
+        let mut sum = 0.0;
+        let mut max = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            if *count > 0 {
+                let (lowest, highest) = Self::range(index);
+                sum += f64::midpoint(lowest as f64, highest as f64) * *count as f64;
+                max = highest;
+            }
+        }
+
+        let mean = sum / self.total_count as f64;
+
+        let mut variance = 0.0;
+        for (index, count) in self.counts.iter().enumerate() {
+            if *count > 0 {
+                let (lowest, highest) = Self::range(index);
+                let delta = f64::midpoint(lowest as f64, highest as f64) - mean;
+                variance += delta * delta * *count as f64;
+            }
+        }
+
+        let std_deviation = (variance / self.total_count as f64).sqrt();
+        let buckets =
+            self.counts.len().saturating_sub(SUB_BUCKET_COUNT) / SUB_BUCKET_HALF_COUNT + 1;
+
+        writeln!(
+            out,
+            "#[Mean    = {:12.3}, StdDeviation   = {:12.3}]",
+            mean / value_scale,
+            std_deviation / value_scale
+        )?;
+        writeln!(
+            out,
+            "#[Max     = {:12.3}, Total count    = {:12}]",
+            max as f64 / value_scale,
+            self.total_count
+        )?;
+        writeln!(
+            out,
+            "#[Buckets = {buckets:12}, SubBuckets     = {SUB_BUCKET_COUNT:12}]"
+        )
+    }
+}
+
+impl<const TIMERS: usize> ThreadProfile<TIMERS> {
+    /// Record the `elapsed` time of a hit of `timer` in its histogram
+    pub(crate) fn record_histogram(&mut self, timer: &'static str, elapsed: u64) {
+        self.histograms.entry(timer).or_default().record(elapsed);
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Get the histogram of the time (in cycles) of every hit of `timer` merged across
+    /// all threads
+    #[must_use]
+    pub fn histogram(&self, timer: &'static str) -> Histogram {
+        let mut result = Histogram::default();
+
+        for thread in self.thread_profiles() {
+            if let Some(histogram) = thread.histograms.get(timer) {
+                result.merge(histogram);
+            }
+        }
+
+        result
+    }
+
+    /// Write the latency distribution of `timer` (with child timers) to `path` in the
+    /// `HdrHistogram` percentile format (`.hgrm`), with values in microseconds
+    ///
+    /// # Errors
+    ///
+    /// * Failed to create or write to `path`
+    pub fn write_hgrm(&self, timer: &'static str, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let calibration = calibration();
+        let cycles_per_us = calibration.frequency / 1_000_000.0;
+
+        let file = std::fs::File::create(path)?;
+        let mut out = std::io::BufWriter::new(file);
+
+        self.histogram(timer)
+            .write_percentiles(&mut out, cycles_per_us)?;
+        writeln!(
+            out,
+            "#[Clock   = {:>12}, Frequency      = {:12.0}, FrequencyError = {:.0}]",
+            calibration.source.name(),
+            calibration.frequency,
+            calibration.error
+        )?;
+        out.flush()
+    }
+}
@@ -0,0 +1,194 @@
+//! Implements the self-contained HTML report
+use std::io::Write;
+use std::path::Path;
+
+use crate::report::TimerReport;
+use crate::{calibration, rdtsc, Profiler, REMAINING_TIME_LABEL};
+
+/// Styles and scripts for sorting the tables and switching between the thread tabs
+const HTML_HEAD: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>timeloop report</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+.tabs button { padding: 0.4em 1em; border: 1px solid #ccc; background: #f4f4f4; cursor: pointer; }
+.tabs button.active { background: #ddd; font-weight: bold; }
+.tab { display: none; }
+.tab.active { display: block; }
+table { border-collapse: collapse; margin-top: 1em; }
+th, td { padding: 0.3em 0.8em; border-bottom: 1px solid #eee; text-align: right; }
+th { cursor: pointer; background: #fafafa; }
+th:first-child, td:first-child { text-align: left; font-family: monospace; }
+td.bar { width: 300px; text-align: left; }
+td.bar div { background: #4a90d9; height: 1em; }
+</style>
+<script>
+function showTab(id) {
+  document.querySelectorAll('.tab').forEach(t => t.classList.toggle('active', t.id === id));
+  document.querySelectorAll('.tabs button').forEach(b => b.classList.toggle('active', b.dataset.tab === id));
+}
+function sortTable(th) {
+  const table = th.closest('table');
+  const body = table.tBodies[0];
+  const col = th.cellIndex;
+  const asc = th.dataset.asc !== 'true';
+  th.dataset.asc = asc;
+  const rows = Array.from(body.rows);
+  rows.sort((a, b) => {
+    const x = a.cells[col].dataset.value, y = b.cells[col].dataset.value;
+    const cmp = isNaN(x) || isNaN(y) ? x.localeCompare(y) : Number(x) - Number(y);
+    return asc ? cmp : -cmp;
+  });
+  rows.forEach(r => body.appendChild(r));
+}
+</script>
+</head>
+<body>
+"#;
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Write a single-file HTML report to `path` with sortable timer tables for all
+    /// threads combined and for each thread, including bar charts of the exclusive time
+    ///
+    /// # Errors
+    ///
+    /// * Failed to create or write to `path`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn write_html_report(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.stop_running_threads(rdtsc());
+
+        let calibration = calibration();
+        let os_timer_freq = calibration.frequency;
+        let (acc, total_time_cycles, details) = self.accumulate_timer_details();
+
+        let file = std::fs::File::create(path)?;
+        let mut out = std::io::BufWriter::new(file);
+
+        out.write_all(HTML_HEAD.as_bytes())?;
+
+        let total_time =
+            std::time::Duration::from_secs_f64(total_time_cycles as f64 / os_timer_freq);
+        writeln!(out, "<h1>timeloop report</h1>")?;
+        writeln!(
+            out,
+            "<p>Total time: {total_time:.2?} ({total_time_cycles} cycles). Calculated OS frequency: {os_timer_freq:.0} ({}, ±{:.0})</p>",
+            calibration.source, calibration.error
+        )?;
+
+        if let Some(cpu) = self.captured_cpu_info() {
+            writeln!(out, "<p>CPU: {}</p>", html_escape(&cpu.to_string()))?;
+        }
+
+        // Gather the tabs: all threads combined followed by each used thread
+        let mut tabs = Vec::new();
+        let (results, other) = self.timer_results(&acc, &details, total_time_cycles, os_timer_freq);
+        tabs.push((
+            "all".to_string(),
+            "All threads".to_string(),
+            results,
+            other,
+            total_time_cycles,
+        ));
+
+        for thread_id in 0..THREADS {
+            let thread = self.thread_mut(thread_id);
+            if !thread.is_used() {
+                continue;
+            }
+
+            let (timers, thread_time, label) =
+                (thread.timers, thread.time, thread.label(thread_id));
+            let (results, other) =
+                self.timer_results(&timers, &details, thread_time, os_timer_freq);
+
+            tabs.push((
+                format!("thread{thread_id}"),
+                html_escape(&label),
+                results,
+                other,
+                thread_time,
+            ));
+        }
+
+        writeln!(out, "<div class=\"tabs\">")?;
+        for (id, title, ..) in &tabs {
+            writeln!(
+                out,
+                "<button data-tab=\"{id}\" onclick=\"showTab('{id}')\">{title}</button>"
+            )?;
+        }
+        writeln!(out, "</div>")?;
+
+        for (id, _, results, other, total) in &tabs {
+            writeln!(out, "<div class=\"tab\" id=\"{id}\">")?;
+            write_html_table(&mut out, results, *other, *total)?;
+            writeln!(out, "</div>")?;
+        }
+
+        writeln!(out, "<script>showTab('all');</script>\n</body>\n</html>")?;
+        out.flush()
+    }
+}
+
+/// Write a sortable table of the given timer results
+#[allow(clippy::cast_precision_loss)]
+fn write_html_table(
+    out: &mut impl Write,
+    results: &[TimerReport],
+    other: u64,
+    total_time_cycles: u64,
+) -> std::io::Result<()> {
+    writeln!(out, "<table>\n<thead><tr>")?;
+    for column in [
+        "Timer",
+        "Hits",
+        "Cycles",
+        "Percent",
+        "With children",
+        "Throughput (GBs/sec)",
+        "Exclusive time",
+    ] {
+        writeln!(out, "<th onclick=\"sortTable(this)\">{column}</th>")?;
+    }
+    writeln!(out, "</tr></thead>\n<tbody>")?;
+
+    let remainder = TimerReport {
+        name: REMAINING_TIME_LABEL.to_string(),
+        exclusive_time: other,
+        percent: other as f64 / total_time_cycles as f64 * 100.,
+        ..Default::default()
+    };
+
+    for result in results.iter().chain(std::iter::once(&remainder)) {
+        let name = html_escape(&result.name);
+        let inclusive = result.inclusive_percent.unwrap_or(result.percent);
+        let throughput = result.throughput.unwrap_or(0.0);
+        let throughput_str = result
+            .throughput
+            .map(|gbs_per_sec| format!("{gbs_per_sec:.3}"))
+            .unwrap_or_default();
+
+        writeln!(
+            out,
+            "<tr data-hash=\"{hash:016x}\"><td data-value=\"{name}\">{name}</td><td data-value=\"{hits}\">{hits}</td><td data-value=\"{cycles}\">{cycles}</td><td data-value=\"{percent}\">{percent:.2}%</td><td data-value=\"{inclusive}\">{inclusive:.2}%</td><td data-value=\"{throughput}\">{throughput_str}</td><td class=\"bar\" data-value=\"{percent}\"><div style=\"width: {percent:.2}%\"></div></td></tr>",
+            hash = result.hash,
+            hits = result.hits,
+            cycles = result.exclusive_time,
+            percent = result.percent,
+        )?;
+    }
+
+    writeln!(out, "</tbody>\n</table>")
+}
+
+/// Escape the given string to be used in HTML
+fn html_escape(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,43 @@
+//! Implements the interner giving runtime timer names the `'static` lifetime
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+
+/// Every name interned so far
+static INTERNED: RwLock<BTreeSet<&'static str>> = RwLock::new(BTreeSet::new());
+
+/// Get a `'static` copy of `name` usable as a timer name, such as a per-file or
+/// per-endpoint name built at runtime
+///
+/// Each distinct name is allocated (and leaked) only the first time it is interned,
+/// later calls only look it up.
+pub fn intern(name: &str) -> &'static str {
+    if let Some(interned) = INTERNED
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .get(name)
+    {
+        return interned;
+    }
+
+    let mut interned = INTERNED
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    // Another thread could have interned the name between the two locks
+    if let Some(name) = interned.get(name) {
+        return name;
+    }
+
+    let name: &'static str = Box::leak(name.into());
+    interned.insert(name);
+    name
+}
+
+/// Get the number of distinct names interned so far
+#[must_use]
+pub fn interned_names() -> usize {
+    INTERNED
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .len()
+}
@@ -0,0 +1,274 @@
+//! Implements a minimal JSON parser for loading results written by other tools
+use std::collections::BTreeMap;
+
+use crate::snapshot::invalid_data;
+
+/// A parsed JSON value
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(BTreeMap<String, JsonValue>),
+}
+
+impl JsonValue {
+    /// Parse the single JSON document in `input`
+    pub(crate) fn parse(input: &str) -> std::io::Result<Self> {
+        let mut parser = Parser {
+            input: input.as_bytes(),
+            pos: 0,
+        };
+
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.input.len() {
+            return Err(invalid_data("Trailing characters after the JSON document"));
+        }
+
+        Ok(value)
+    }
+
+    /// Get the field `key` of this object
+    pub(crate) fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(fields) => fields.get(key),
+            _ => None,
+        }
+    }
+
+    /// Get this value as a number
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get this value as a string
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the elements of this array
+    pub(crate) fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// The state of parsing a JSON document
+struct Parser<'a> {
+    /// The whole document
+    input: &'a [u8],
+
+    /// The offset of the next byte to parse
+    pos: usize,
+}
+
+impl Parser<'_> {
+    /// Skip the whitespace before the next token
+    fn skip_whitespace(&mut self) {
+        while self
+            .input
+            .get(self.pos)
+            .is_some_and(|byte| matches!(byte, b' ' | b'\t' | b'\n' | b'\r'))
+        {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `expected` after any whitespace
+    fn expect(&mut self, expected: u8) -> std::io::Result<()> {
+        self.skip_whitespace();
+        if self.input.get(self.pos) != Some(&expected) {
+            return Err(invalid_data("Unexpected character in the JSON document"));
+        }
+
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Consume the literal `word`, such as `true`
+    fn keyword(&mut self, word: &str) -> std::io::Result<()> {
+        if !self.input[self.pos..].starts_with(word.as_bytes()) {
+            return Err(invalid_data("Unknown literal in the JSON document"));
+        }
+
+        self.pos += word.len();
+        Ok(())
+    }
+
+    /// Parse the next value
+    fn value(&mut self) -> std::io::Result<JsonValue> {
+        self.skip_whitespace();
+
+        match self.input.get(self.pos) {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(JsonValue::String(self.string()?)),
+            Some(b't') => self.keyword("true").map(|()| JsonValue::Bool(true)),
+            Some(b'f') => self.keyword("false").map(|()| JsonValue::Bool(false)),
+            Some(b'n') => self.keyword("null").map(|()| JsonValue::Null),
+            Some(_) => self.number(),
+            None => Err(invalid_data("Unexpected end of the JSON document")),
+        }
+    }
+
+    /// Parse the object starting at the next `{`
+    fn object(&mut self) -> std::io::Result<JsonValue> {
+        let mut fields = BTreeMap::new();
+        self.expect(b'{')?;
+
+        self.skip_whitespace();
+        if self.input.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(b':')?;
+            fields.insert(key, self.value()?);
+
+            self.skip_whitespace();
+            match self.input.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(fields));
+                }
+                _ => return Err(invalid_data("Unterminated JSON object")),
+            }
+        }
+    }
+
+    /// Parse the array starting at the next `[`
+    fn array(&mut self) -> std::io::Result<JsonValue> {
+        let mut values = Vec::new();
+        self.expect(b'[')?;
+
+        self.skip_whitespace();
+        if self.input.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+
+            self.skip_whitespace();
+            match self.input.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(values));
+                }
+                _ => return Err(invalid_data("Unterminated JSON array")),
+            }
+        }
+    }
+
+    /// Parse the string starting at the next `"`
+    fn string(&mut self) -> std::io::Result<String> {
+        self.expect(b'"')?;
+
+        let mut bytes = Vec::new();
+        loop {
+            let Some(&byte) = self.input.get(self.pos) else {
+                return Err(invalid_data("Unterminated JSON string"));
+            };
+            self.pos += 1;
+
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&escape) = self.input.get(self.pos) else {
+                        return Err(invalid_data("Unterminated JSON string"));
+                    };
+                    self.pos += 1;
+
+                    let unescaped = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(invalid_data("Unknown escape in a JSON string")),
+                    };
+
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(unescaped.encode_utf8(&mut buf).as_bytes());
+                }
+                byte => bytes.push(byte),
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| invalid_data("JSON string is not UTF-8"))
+    }
+
+    /// Parse the hex digits of a `\u` escape, joining the two halves of a surrogate pair
+    fn unicode_escape(&mut self) -> std::io::Result<char> {
+        let first = self.hex4()?;
+
+        let code = if (0xd800..0xdc00).contains(&first) {
+            if !self.input[self.pos..].starts_with(b"\\u") {
+                return Err(invalid_data("Unpaired surrogate in a JSON string"));
+            }
+            self.pos += 2;
+
+            let second = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&second) {
+                return Err(invalid_data("Unpaired surrogate in a JSON string"));
+            }
+            0x10000 + ((first - 0xd800) << 10) + (second - 0xdc00)
+        } else {
+            first
+        };
+
+        char::from_u32(code).ok_or_else(|| invalid_data("Invalid escape in a JSON string"))
+    }
+
+    /// Parse the next four hex digits
+    fn hex4(&mut self) -> std::io::Result<u32> {
+        let digits = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| invalid_data("Invalid escape in a JSON string"))?;
+
+        self.pos += 4;
+        Ok(digits)
+    }
+
+    /// Parse the number at the current position
+    fn number(&mut self) -> std::io::Result<JsonValue> {
+        let start = self.pos;
+        while self
+            .input
+            .get(self.pos)
+            .is_some_and(|byte| matches!(byte, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E'))
+        {
+            self.pos += 1;
+        }
+
+        std::str::from_utf8(&self.input[start..self.pos])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .map(JsonValue::Number)
+            .ok_or_else(|| invalid_data("Invalid number in the JSON document"))
+    }
+}
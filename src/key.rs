@@ -0,0 +1,94 @@
+//! Implements timers keyed by the variants of an enum
+use crate::Profiler;
+
+/// A complete set of timers known at compile time, implemented by `impl_enum!`
+///
+/// Every variant is a timer named after the variant. Using an unknown variant is a
+/// compile error, unlike a misspelled string timer.
+pub trait TimerKey {
+    /// The name of each timer, indexed by the discriminant of its variant
+    const NAMES: &'static [&'static str];
+}
+
+/// Get the stable hash identifying the timer `name` in exports
+///
+/// The hash is the 64-bit FNV-1a of the full timer name, so it is the same across runs,
+/// binaries and platforms, even when a report truncates the displayed name.
+#[must_use]
+pub const fn timer_hash(name: &str) -> u64 {
+    let bytes = name.as_bytes();
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+
+    hash
+}
+
+/// The error returned when registering more timers than a [`Profiler`] can hold
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimerCapacityError {
+    /// The number of timers that were registered
+    pub requested: usize,
+
+    /// The number of timers the profiler can hold, excluding the overflow timer
+    pub capacity: usize,
+}
+
+impl std::fmt::Display for TimerCapacityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Too many timers: {} registered but the profiler holds {}. Increase the TIMERS of the Profiler",
+            self.requested, self.capacity
+        )
+    }
+}
+
+impl std::error::Error for TimerCapacityError {}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// The number of distinct timers this profiler holds before the least hit timers
+    /// are aggregated into the overflow timer
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        Self::OVERFLOW_INDEX
+    }
+
+    /// Register every timer of `K` in order, giving them fixed indices, and report them
+    /// even when they weren't hit
+    ///
+    /// # Panics
+    ///
+    /// * `K` has more timers than the profiler can hold
+    pub fn register_keys<K: TimerKey>(&mut self) {
+        if let Err(err) = self.try_register_keys::<K>() {
+            panic!("{err}");
+        }
+    }
+
+    /// Register every timer of `K` in order like [`Profiler::register_keys`]
+    ///
+    /// # Errors
+    ///
+    /// * `K` has more timers than the profiler can hold. Nothing is registered.
+    pub fn try_register_keys<K: TimerKey>(&mut self) -> Result<(), TimerCapacityError> {
+        if K::NAMES.len() > Self::OVERFLOW_INDEX {
+            return Err(TimerCapacityError {
+                requested: K::NAMES.len(),
+                capacity: Self::OVERFLOW_INDEX,
+            });
+        }
+
+        for name in K::NAMES {
+            self.get_timer_index(name);
+            self.exhaustive_timers.insert(name);
+        }
+
+        Ok(())
+    }
+}
@@ -4,24 +4,201 @@
 #![feature(generic_const_exprs)]
 #![feature(let_chains)]
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::fmt::Debug;
 use std::io::Read;
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError, TryLockError};
 
 mod macros;
 
+pub mod prelude;
+
+mod clock;
+pub use clock::{calibration, tsc_frequency, Calibration, ClockSource};
+
+mod cpu;
+pub use cpu::{cpu_info, CpuCache, CpuInfo};
+
+mod git;
+pub use git::{git_info, GitInfo, GIT_COMMIT_ENV_VAR};
+
+mod triage;
+
+mod trace;
+pub use trace::Event;
+
+mod exemplar;
+pub use exemplar::{Exemplar, Reservoir};
+
+mod percentile;
+pub use percentile::{Percentiles, SampleReservoir};
+
+mod chain;
+pub use chain::{ChainStats, LatencyToken};
+
+mod category;
+
+mod user_counter;
+
+mod metrics;
+pub use metrics::Gauge;
+
+mod page_faults;
+
+mod alloc;
+pub use alloc::{AllocationStats, TrackingAllocator};
+
+mod context_switch;
+pub use context_switch::ContextSwitches;
+
+mod callers;
+pub use callers::CallerEdge;
+
+mod counters;
+
+mod evict;
+
+mod flamegraph;
+
+mod dry_run;
+
+mod tree;
+
+mod per_thread;
+
+mod thread_name;
+
+mod static_profiler;
+pub use static_profiler::ProfilerCell;
+
+mod thread_slot;
+pub use thread_slot::_thread_slot;
+
+mod span;
+pub use span::SpanId;
+
+#[cfg(feature = "hist")]
+mod hist;
+#[cfg(feature = "hist")]
+pub use hist::Histogram;
+
+mod buckets;
+pub use buckets::Log2Histogram;
+
+mod intern;
+pub use intern::{intern, interned_names};
+
+mod key;
+pub use key::{timer_hash, TimerCapacityError, TimerKey};
+
+mod fork;
+
+mod merge;
+
+mod recursion;
+
+mod sampling;
+
+mod window;
+
+mod series;
+pub use series::{SeriesClock, SeriesPoint};
+
+mod short_timer;
+pub use short_timer::{
+    IgnoreDecision, IgnoredHits, ShortStreak, ShortTimerPolicy, SHORT_TIMERS_ENV_VAR,
+};
+
+mod handle;
+pub use handle::{HandleTimer, ProfilerHandle};
+
+mod pause;
+
+mod toggle;
+pub use pause::DisabledTimerGuard;
+pub use toggle::{is_enabled, set_enabled};
+
+mod speedscope;
+
+mod flamechart;
+
+mod filter;
+pub use filter::{TimerFilter, FILTER_ENV_VAR};
+
+mod report;
+use report::TimerDetails;
+pub use report::{Report, ReportFormat, TimerReport};
+
+mod render;
+pub use render::{ColorMode, JsonRenderer, MarkdownRenderer, ReportRenderer, TextRenderer};
+
+mod unit;
+pub use unit::TimeUnit;
+
+mod html;
+
+mod compare;
+pub use compare::Comparison;
+
+mod sketch;
+pub use sketch::DDSketch;
+
+mod snapshot;
+pub use snapshot::{ProfilerSnapshot, TimerDelta};
+
+mod json;
+
+mod svg;
+pub use svg::{Series, SvgPlot};
+
+mod persist;
+pub use persist::spawn_persist;
+
+mod periodic;
+pub use periodic::spawn_print_every;
+
+mod signal_dump;
+pub use signal_dump::install_signal_dump;
+
+mod panic_dump;
+pub use panic_dump::install_panic_dump;
+
+mod at_exit;
+pub use at_exit::report_at_exit;
+
+mod overhead;
+pub use overhead::{overhead_report, OverheadReport};
+
+mod prometheus;
+#[cfg(feature = "prometheus-http")]
+pub use prometheus::serve_prometheus;
+
+#[cfg(feature = "statsd")]
+mod statsd;
+#[cfg(feature = "statsd")]
+pub use statsd::{spawn_statsd_emitter, StatsdConfig};
+
+#[cfg(feature = "otel")]
+mod otel;
+
 mod repitition_tester;
 pub use repitition_tester::{RepititionTester, TestResults};
 
+mod buffer_strategy;
+pub use buffer_strategy::{compare_buffer_strategies, Buffer, BufferStrategy, StrategyComparison};
+
 pub use timeloop_proc_macro::*;
 
 // Check to ensure the profiler is explictly enabled or disabled
 #[cfg(not(any(feature = "enable", feature = "disable")))]
 compile_error!("Turn on the `enable` or `disable` feature");
 
-/// A timed block
-#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+/// A timed block, aligned to a cache line so that the timers of each thread don't share
+/// cache lines with other threads
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+#[repr(align(64))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Timer {
     /// The amount of time spent in this timing block (without child blocks)
     pub exclusive_time: u64,
@@ -34,6 +211,18 @@ pub struct Timer {
 
     /// The number of bytes processed in this timing block
     pub bytes_processed: u64,
+
+    /// The shortest single hit (in cycles, including child blocks), 0 if never hit
+    pub min_cycles: u64,
+
+    /// The longest single hit (in cycles, including child blocks)
+    pub max_cycles: u64,
+
+    /// The running mean of the hits (in cycles, including child blocks)
+    pub mean_cycles: f64,
+
+    /// The running sum of squared differences of the hits from `mean_cycles`
+    pub m2_cycles: f64,
 }
 
 impl Timer {
@@ -43,7 +232,96 @@ impl Timer {
             inclusive_time: 0,
             hits: 0,
             bytes_processed: 0,
+            min_cycles: 0,
+            max_cycles: 0,
+            mean_cycles: 0.0,
+            m2_cycles: 0.0,
+        }
+    }
+
+    /// Record a single hit of `elapsed` cycles without any child timers, such as a span
+    fn record_hit(&mut self, elapsed: u64) {
+        self.exclusive_time = self.exclusive_time.wrapping_add(elapsed);
+        self.inclusive_time = self.inclusive_time.wrapping_add(elapsed);
+
+        if self.hits == 0 || elapsed < self.min_cycles {
+            self.min_cycles = elapsed;
+        }
+        self.max_cycles = self.max_cycles.max(elapsed);
+        self.record_moments(elapsed);
+        self.hits += 1;
+    }
+
+    /// Add a single hit of `elapsed` cycles to the running mean and variance using
+    /// Welford's algorithm. Must be called before `hits` is incremented.
+    #[allow(clippy::cast_precision_loss)]
+    fn record_moments(&mut self, elapsed: u64) {
+        let elapsed = elapsed as f64;
+        let delta = elapsed - self.mean_cycles;
+        self.mean_cycles += delta / (self.hits + 1) as f64;
+        self.m2_cycles += delta * (elapsed - self.mean_cycles);
+    }
+
+    /// Get the running mean and squared differences across this timer and `other`
+    #[allow(clippy::cast_precision_loss)]
+    fn merged_moments(&self, other: &Timer) -> (f64, f64) {
+        match (self.hits, other.hits) {
+            (_, 0) => (self.mean_cycles, self.m2_cycles),
+            (0, _) => (other.mean_cycles, other.m2_cycles),
+            (hits, other_hits) => {
+                let (hits, other_hits) = (hits as f64, other_hits as f64);
+                let total = hits + other_hits;
+                let delta = other.mean_cycles - self.mean_cycles;
+
+                (
+                    self.mean_cycles + delta * other_hits / total,
+                    self.m2_cycles + other.m2_cycles + delta * delta * hits * other_hits / total,
+                )
+            }
+        }
+    }
+
+    /// The sample standard deviation of the hits (in cycles, including child blocks)
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn std_dev_cycles(&self) -> f64 {
+        if self.hits < 2 {
+            return 0.0;
+        }
+
+        (self.m2_cycles / (self.hits - 1) as f64).sqrt()
+    }
+
+    /// The standard deviation of the hits relative to their mean
+    #[must_use]
+    pub fn coefficient_of_variation(&self) -> f64 {
+        if self.mean_cycles == 0.0 {
+            return 0.0;
         }
+
+        self.std_dev_cycles() / self.mean_cycles
+    }
+
+    /// Get the shortest and longest hit across this timer and `other`
+    fn merged_extremes(&self, other: &Timer) -> (u64, u64) {
+        match (self.hits, other.hits) {
+            (_, 0) => (self.min_cycles, self.max_cycles),
+            (0, _) => (other.min_cycles, other.max_cycles),
+            _ => (
+                self.min_cycles.min(other.min_cycles),
+                self.max_cycles.max(other.max_cycles),
+            ),
+        }
+    }
+
+    /// Fold the hits of `other` into this timer
+    pub(crate) fn merge(&mut self, other: &Timer) {
+        (self.min_cycles, self.max_cycles) = self.merged_extremes(other);
+        (self.mean_cycles, self.m2_cycles) = self.merged_moments(other);
+        self.exclusive_time = self.exclusive_time.wrapping_add(other.exclusive_time);
+        self.inclusive_time = self.inclusive_time.wrapping_add(other.inclusive_time);
+        self.hits += other.hits;
+        self.bytes_processed += other.bytes_processed;
     }
 }
 
@@ -51,48 +329,388 @@ impl std::ops::Add for Timer {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self {
+        let (min_cycles, max_cycles) = self.merged_extremes(&rhs);
+        let (mean_cycles, m2_cycles) = self.merged_moments(&rhs);
+
         Self {
             exclusive_time: self.exclusive_time + rhs.exclusive_time,
             inclusive_time: self.inclusive_time + rhs.inclusive_time,
             hits: self.hits + rhs.hits,
             bytes_processed: self.bytes_processed + rhs.bytes_processed,
+            min_cycles,
+            max_cycles,
+            mean_cycles,
+            m2_cycles,
         }
     }
 }
 
-/// A timed block
-#[derive(Default, Clone)]
-struct TimerResult {
-    pub name: String,
-    pub exclusive_time: u64,
-    pub inclusive_time_str: String,
-    pub hits: u64,
-    pub percent: f64,
-    pub throughput_str: String,
+/// The change from `rhs` to this timer. The shortest and longest hits and the variance
+/// can't be separated, so they are kept from this timer.
+impl std::ops::Sub for Timer {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            exclusive_time: self.exclusive_time.wrapping_sub(rhs.exclusive_time),
+            inclusive_time: self.inclusive_time.wrapping_sub(rhs.inclusive_time),
+            hits: self.hits.wrapping_sub(rhs.hits),
+            bytes_processed: self.bytes_processed.wrapping_sub(rhs.bytes_processed),
+            min_cycles: self.min_cycles,
+            max_cycles: self.max_cycles,
+            mean_cycles: self.mean_cycles,
+            m2_cycles: self.m2_cycles,
+        }
+    }
 }
 
-const MAX_TIMERS: usize = 128;
+/// The default number of timers a [`Profiler`] can hold
+pub const MAX_TIMERS: usize = 128;
+
+/// The default number of threads the profiler of `create_profiler!` can hold
+///
+/// The slots of exited threads are reused, so this bounds the number of threads using
+/// the profiler at once.
+pub const MAX_THREADS: usize = 4096;
 
 /// The provided `Timer` struct that takes an abstract enum with the available subtimers
 /// to keep track of
-#[derive(Debug, Clone)]
-pub struct Profiler<const THREADS: usize> {
-    /// The global elapsed
-    pub thread_times: [u64; THREADS],
-
-    /// The status of the the thread indexed timer
-    pub thread_status: [ThreadTimerStatus; THREADS],
-
+///
+/// `TIMERS` is the number of distinct timers the profiler can hold, including the
+/// overflow timer aggregating the least hit timers once it is full.
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Profiler<const THREADS: usize, const TIMERS: usize = MAX_TIMERS> {
+    #[doc(hidden)]
+    /// The state of each thread slot, see [`ThreadProfile`]
+    pub threads: [Mutex<ThreadProfile<TIMERS>>; THREADS],
+
+    #[doc(hidden)]
     /// Timer name mapped to its index
     pub timer_name_to_index: BTreeMap<&'static str, u32>,
 
+    #[doc(hidden)]
     /// The index to allocate for the next timer
     pub next_index: u32,
 
-    pub timer_names: [&'static str; MAX_TIMERS],
+    /// Whether `print()` colorizes the report
+    pub color: ColorMode,
+
+    /// The unit the times of the printed report are displayed in
+    pub time_unit: TimeUnit,
+
+    /// Attach the CPU model and topology to reports and snapshots
+    pub capture_cpu_info: bool,
+
+    /// Attach the git commit of the program to snapshots
+    pub capture_git_info: bool,
+
+    /// The cost (in cycles) of a single timer hit subtracted from the exclusive time of
+    /// every hit and shown as a separate row in the report. Set by
+    /// [`Profiler::calibrate_overhead`]. 0 subtracts nothing.
+    pub overhead_cycles: f64,
+
+    /// Hide the timers taking less than this percent of the total time from the report,
+    /// summarizing them in a single line instead. 0 shows every timer.
+    pub min_percent: f64,
+
+    #[doc(hidden)]
+    /// The timestamp when the profiler was paused, if it is paused
+    pub paused_at: Option<u64>,
+
+    #[doc(hidden)]
+    /// Total time (in cycles) the profiler was paused
+    pub paused_cycles: u64,
+
+    /// Timers reported even when they weren't hit
+    pub exhaustive_timers: BTreeSet<&'static str>,
+
+    #[doc(hidden)]
+    /// Number of timers aggregated into the overflow timer
+    pub evicted_timers: u32,
+
+    #[doc(hidden)]
+    pub timer_names: [&'static str; TIMERS],
+
+    /// When to stop recording the timers that are consistently too short to measure
+    pub short_timer_policy: ShortTimerPolicy,
+
+    /// The timers that are always recorded, even when the [`ShortTimerPolicy`] would
+    /// ignore them
+    pub pinned_timers: BTreeSet<&'static str>,
+
+    /// Record a start/stop event for every timer hit
+    pub record_events: bool,
+
+    #[doc(hidden)]
+    /// Adjacent events of the same timer shorter than this (in cycles) are coalesced
+    pub min_event_cycles: u64,
+
+    #[doc(hidden)]
+    /// The length (in cycles) of the window of [`Profiler::window_report`], 0 if unset
+    pub window_cycles: u64,
+
+    #[doc(hidden)]
+    /// The snapshots spanning the window, oldest first, with the timestamp they were
+    /// taken at
+    pub window_snapshots: Mutex<VecDeque<(u64, ProfilerSnapshot)>>,
+
+    #[doc(hidden)]
+    /// The timestamp when the next window snapshot is due, checked on every timer exit
+    pub window_next_tick: AtomicU64,
+
+    #[doc(hidden)]
+    /// Maps the timestamps to the windows of the series of [`Profiler::record_series`]
+    pub series_clock: SeriesClock,
+
+    /// Keep a random sample of exemplar hits for each timer
+    pub record_exemplars: bool,
+
+    /// Keep a bounded random sample of the hit durations of each timer for percentiles
+    pub record_percentiles: bool,
+
+    /// Record the hits and time of every caller to callee edge between the timers
+    pub record_callers: bool,
+
+    #[doc(hidden)]
+    /// The ordered stages of each defined latency chain
+    pub chain_stages: BTreeMap<&'static str, &'static [&'static str]>,
+
+    #[doc(hidden)]
+    /// The category of each timer given one
+    pub categories: BTreeMap<&'static str, &'static str>,
+
+    #[doc(hidden)]
+    /// The rate of each sampled timer, which only records one of every rate hits
+    pub sample_rates: BTreeMap<&'static str, u64>,
+
+    #[doc(hidden)]
+    /// The spans started by [`Profiler::begin`] and not yet ended, across all threads
+    pub open_spans: Mutex<BTreeMap<SpanId, OpenTimer>>,
+
+    #[doc(hidden)]
+    /// The last [`SpanId`] given out
+    pub next_span_id: AtomicU64,
+
+    /// Attribute the time of every timer hit to its full timer stack for
+    /// [`Profiler::print_tree`] and [`Profiler::write_folded_stacks`]. This looks up and
+    /// copies the stack on every timer exit.
+    pub record_stacks: bool,
+
+    /// Only count hits and the call structure of the timers without timing them
+    pub dry_run: bool,
+
+    /// The registered comparisons between two variants of a timer
+    pub comparisons: Vec<Comparison>,
+
+    /// Count the page faults of the process during every timer hit, as
+    /// [`RepititionTester`] does for a whole test. Faults are read from `/proc/self/stat`
+    /// on entry and exit, so this adds a syscall to every timer hit.
+    pub record_page_faults: bool,
+
+    /// Count the voluntary and involuntary context switches of the thread during every
+    /// timer hit, to tell the timers that are descheduled apart from those burning CPU.
+    /// This adds a `getrusage` syscall to every timer entry and exit.
+    pub record_context_switches: bool,
+
+    /// Keep a power-of-two bucket histogram of the hits of each timer
+    pub record_log2_histograms: bool,
+
+    /// The timers whose distribution is tracked in a [`DDSketch`]
+    pub sketched_timers: BTreeSet<&'static str>,
+
+    #[doc(hidden)]
+    /// The state of the timers at the last OpenTelemetry export
+    #[cfg(feature = "otel")]
+    pub otel_exported: [Timer; TIMERS],
+}
+
+/// The state of a single thread slot of a [`Profiler`]
+///
+/// Only the thread using the slot records into it, locking it for every timer hit. The
+/// reports lock each slot in turn to read it.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct ThreadProfile<const TIMERS: usize> {
+    /// The elapsed time (in cycles) of the thread, less its start time while running
+    pub time: u64,
+
+    /// The status of the thread timer
+    pub status: ThreadTimerStatus,
+
+    /// The name of the thread that used this slot, captured on first use. Empty for
+    /// unnamed threads.
+    pub name: Option<&'static str>,
+
+    /// The token of the thread that last used this slot, 0 if it was never used
+    pub owner: u64,
+
+    /// The timers of the thread
+    pub timers: [Timer; TIMERS],
+
+    /// The number of consecutive short hits of each timer
+    pub short_streaks: [ShortStreak; TIMERS],
+
+    /// Total time (in cycles) of the ignored short hits
+    pub ignored_cycles: u64,
+
+    /// Why and when each ignored timer was ignored
+    pub ignore_decisions: BTreeMap<&'static str, IgnoreDecision>,
+
+    /// The hits of each ignored timer, merged back if it is re-included
+    pub ignored_hits: BTreeMap<&'static str, IgnoredHits>,
+
+    /// How many hits of each timer are currently open
+    pub recursion_depths: [u32; TIMERS],
+
+    /// The recorded events
+    pub events: Vec<Event>,
+
+    /// The hits and time of every timer in each window of the series
+    pub series: BTreeMap<u64, BTreeMap<&'static str, SeriesPoint>>,
+
+    /// The reservoirs of exemplar hits of each timer
+    pub exemplars: BTreeMap<&'static str, Reservoir>,
+
+    /// The reservoirs of hit durations of each timer
+    pub latency_samples: BTreeMap<&'static str, SampleReservoir>,
+
+    /// The edges keyed by callee and caller
+    pub caller_edges: BTreeMap<(&'static str, Option<&'static str>), CallerEdge>,
+
+    /// The finished requests of each latency chain
+    pub chains: BTreeMap<&'static str, ChainStats>,
+
+    /// The estimated time (in cycles) of the unrecorded hits of sampled timers included
+    /// in each parent timer
+    pub sampled_parent_cycles: BTreeMap<&'static str, u64>,
+
+    /// The user counters added by [`Profiler::count`] keyed by timer and counter
+    pub user_counters: BTreeMap<(&'static str, &'static str), u64>,
+
+    /// The counters added by [`Profiler::add_counter`]
+    pub metric_counters: BTreeMap<&'static str, u64>,
+
+    /// The gauges set by [`Profiler::set_gauge`]
+    pub gauges: BTreeMap<&'static str, Gauge>,
+
+    /// The currently open timers, outermost first
+    pub stack: Vec<&'static str>,
+
+    /// The number of recorded hits that removed their time from a parent, telling
+    /// whether a timer had children while it was open
+    pub nested_hits: u64,
 
-    /// Current timers available
-    pub timers: [[Timer; MAX_TIMERS]; THREADS],
+    /// The exclusive time (in cycles) spent in each full timer stack
+    pub stack_times: BTreeMap<Vec<&'static str>, u64>,
+
+    /// The number of hits of each full timer stack during a dry run
+    pub call_counts: BTreeMap<Vec<&'static str>, u64>,
+
+    /// The latency histograms of every timer
+    #[cfg(feature = "hist")]
+    pub histograms: BTreeMap<&'static str, Histogram>,
+
+    /// The page faults during the hits of every timer
+    pub page_faults: BTreeMap<&'static str, u64>,
+
+    /// The context switches during the hits of every timer
+    pub context_switches: BTreeMap<&'static str, ContextSwitches>,
+
+    /// The allocations attributed to every timer by the [`TrackingAllocator`]
+    pub allocations: BTreeMap<&'static str, AllocationStats>,
+
+    /// The power-of-two histograms of every timer
+    pub log2_histograms: BTreeMap<&'static str, Log2Histogram>,
+
+    /// The quantile sketches of the sketched timers
+    pub sketches: BTreeMap<&'static str, DDSketch>,
+}
+
+impl<const TIMERS: usize> ThreadProfile<TIMERS> {
+    /// An unused thread slot
+    const fn new() -> Self {
+        Self {
+            time: 0,
+            status: ThreadTimerStatus::Stopped,
+            name: None,
+            owner: 0,
+            timers: [Timer::const_default(); TIMERS],
+            short_streaks: [ShortStreak::EMPTY; TIMERS],
+            ignored_cycles: 0,
+            ignore_decisions: BTreeMap::new(),
+            ignored_hits: BTreeMap::new(),
+            recursion_depths: [0; TIMERS],
+            events: Vec::new(),
+            series: BTreeMap::new(),
+            exemplars: BTreeMap::new(),
+            latency_samples: BTreeMap::new(),
+            caller_edges: BTreeMap::new(),
+            chains: BTreeMap::new(),
+            sampled_parent_cycles: BTreeMap::new(),
+            user_counters: BTreeMap::new(),
+            metric_counters: BTreeMap::new(),
+            gauges: BTreeMap::new(),
+            stack: Vec::new(),
+            nested_hits: 0,
+            stack_times: BTreeMap::new(),
+            call_counts: BTreeMap::new(),
+            #[cfg(feature = "hist")]
+            histograms: BTreeMap::new(),
+            page_faults: BTreeMap::new(),
+            context_switches: BTreeMap::new(),
+            allocations: BTreeMap::new(),
+            log2_histograms: BTreeMap::new(),
+            sketches: BTreeMap::new(),
+        }
+    }
+
+    /// Check if any timer of this thread was hit
+    fn is_used(&self) -> bool {
+        self.timers.iter().any(|timer| timer.hits > 0)
+    }
+
+    /// Get the time (in cycles) of this thread, up to now if it is still running
+    fn elapsed(&self) -> u64 {
+        match self.status {
+            ThreadTimerStatus::Running => self.time.wrapping_add(rdtsc()),
+            ThreadTimerStatus::Stopped | ThreadTimerStatus::Paused => self.time,
+        }
+    }
+
+    /// Zero the timers of this thread, restarting its clock at `now` if it is running,
+    /// see [`Profiler::reset`]
+    fn reset(&mut self, now: u64) {
+        self.time = match self.status {
+            ThreadTimerStatus::Running => 0u64.wrapping_sub(now),
+            ThreadTimerStatus::Stopped | ThreadTimerStatus::Paused => 0,
+        };
+
+        self.timers = [Timer::const_default(); TIMERS];
+        self.short_streaks = [ShortStreak::EMPTY; TIMERS];
+        self.ignored_cycles = 0;
+        self.ignore_decisions.clear();
+        self.ignored_hits.clear();
+        self.events.clear();
+        self.exemplars.clear();
+        self.latency_samples.clear();
+        self.caller_edges.clear();
+        self.chains.clear();
+        self.sampled_parent_cycles.clear();
+        self.user_counters.clear();
+        self.metric_counters.clear();
+        self.gauges.clear();
+        self.stack_times.clear();
+        self.call_counts.clear();
+        self.sketches.clear();
+        self.log2_histograms.clear();
+        self.series.clear();
+        self.page_faults.clear();
+        self.allocations.clear();
+        self.context_switches.clear();
+        #[cfg(feature = "hist")]
+        self.histograms.clear();
+    }
 }
 
 /// Get the page faults from the current process
@@ -131,43 +749,200 @@ pub fn get_page_faults() -> u64 {
 }
 
 fn rdtsc() -> u64 {
+    if cfg!(feature = "counters") {
+        return counters::coarse_nanos();
+    }
+
     unsafe { core::arch::x86_64::_rdtsc() }
 }
 
+/// Read the clock used by the timers, the timestamp counter unless the `counters`
+/// feature is enabled. Used by the timers expanded from `create_profiler!`.
+#[doc(hidden)]
+#[must_use]
+pub fn _timestamp() -> u64 {
+    rdtsc()
+}
+
+/// A timer that has been entered but not yet exited
+#[doc(hidden)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct OpenTimer {
+    /// The name of this timer
+    pub timer: &'static str,
+
+    /// The parent of this timer
+    pub parent: Option<&'static str>,
+
+    /// The starting time for this timer
+    pub start_time: u64,
+
+    /// Number of bytes processed during this timer
+    pub bytes_processed: u64,
+
+    /// The total paused time of the profiler when this timer was entered
+    pub paused_cycles: u64,
+
+    /// Total time (in cycles) this timer itself was paused by [`OpenTimer::pause`]
+    pub own_paused_cycles: u64,
+
+    /// The timestamp when this timer itself was paused, if it is paused
+    pub own_paused_at: Option<u64>,
+
+    /// The page faults of the process when this timer was entered, if page faults are
+    /// recorded
+    pub start_page_faults: Option<u64>,
+
+    /// The allocations of the thread when this timer was entered, if the
+    /// [`TrackingAllocator`] is installed
+    pub start_allocations: Option<AllocationStats>,
+
+    /// The context switches of the thread when this timer was entered, if context
+    /// switches are recorded
+    pub start_context_switches: Option<ContextSwitches>,
+
+    /// The number of nested hits recorded on the thread when this timer was entered, see
+    /// [`ThreadProfile::nested_hits`]
+    pub nested_hits: u64,
+}
+
 /// The current thread timer status
+#[doc(hidden)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ThreadTimerStatus {
     Stopped,
     Running,
+
+    /// Running, but suspended by [`Profiler::pause`]
+    Paused,
 }
 
 const REMAINING_TIME_LABEL: &str = "Remainder";
 
-impl<const THREADS: usize> Default for Profiler<THREADS> {
+impl<const THREADS: usize, const TIMERS: usize> Default for Profiler<THREADS, TIMERS> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<const THREADS: usize> Profiler<THREADS> {
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// The index of the timer aggregating the evicted timers once the soft limit is
+    /// reached
+    pub(crate) const OVERFLOW_INDEX: usize = {
+        assert!(TIMERS >= 2, "A profiler must hold at least two timers");
+        TIMERS - 1
+    };
+
     /// Create a new timer struct
     #[must_use]
     pub const fn new() -> Self {
         Self {
-            thread_times: [0; THREADS],
-            thread_status: [ThreadTimerStatus::Stopped; THREADS],
+            threads: [const { Mutex::new(ThreadProfile::new()) }; THREADS],
             timer_name_to_index: BTreeMap::new(),
             next_index: 0,
-            timers: [[Timer::const_default(); MAX_TIMERS]; THREADS],
-            timer_names: [""; MAX_TIMERS],
+            color: ColorMode::Auto,
+            time_unit: TimeUnit::Cycles,
+            capture_cpu_info: false,
+            capture_git_info: false,
+            overhead_cycles: 0.,
+            min_percent: 0.,
+            paused_at: None,
+            paused_cycles: 0,
+            exhaustive_timers: BTreeSet::new(),
+            evicted_timers: 0,
+            short_timer_policy: ShortTimerPolicy::DEFAULT,
+            pinned_timers: BTreeSet::new(),
+            timer_names: [""; TIMERS],
+            record_events: false,
+            min_event_cycles: 0,
+            window_cycles: 0,
+            window_snapshots: Mutex::new(VecDeque::new()),
+            window_next_tick: AtomicU64::new(u64::MAX),
+            series_clock: SeriesClock::UNSET,
+            record_exemplars: false,
+            record_percentiles: false,
+            record_callers: false,
+            chain_stages: BTreeMap::new(),
+            categories: BTreeMap::new(),
+            sample_rates: BTreeMap::new(),
+            open_spans: Mutex::new(BTreeMap::new()),
+            next_span_id: AtomicU64::new(0),
+            record_stacks: false,
+            dry_run: false,
+            comparisons: Vec::new(),
+            record_page_faults: false,
+            record_context_switches: false,
+            record_log2_histograms: false,
+            sketched_timers: BTreeSet::new(),
+            #[cfg(feature = "otel")]
+            otel_exported: [Timer::const_default(); TIMERS],
         }
     }
 
+    /// Lock the state of the given thread slot, even if a thread panicked while holding
+    /// it
+    ///
+    /// # Panics
+    ///
+    /// If the given `thread_id` is too large
+    pub(crate) fn thread(&self, thread_id: usize) -> MutexGuard<'_, ThreadProfile<TIMERS>> {
+        let Some(thread) = self.threads.get(thread_id) else {
+            panic!("Unknown thread id: {thread_id}");
+        };
+
+        thread.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get the state of the given thread slot, which needs no lock while the profiler is
+    /// borrowed exclusively
+    ///
+    /// # Panics
+    ///
+    /// If the given `thread_id` is too large
+    pub(crate) fn thread_mut(&mut self, thread_id: usize) -> &mut ThreadProfile<TIMERS> {
+        let Some(thread) = self.threads.get_mut(thread_id) else {
+            panic!("Unknown thread id: {thread_id}");
+        };
+
+        thread.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Check if the slot of any thread is locked, such as by a thread that panicked while
+    /// recording into it
+    pub(crate) fn any_thread_locked(&self) -> bool {
+        self.threads
+            .iter()
+            .any(|thread| matches!(thread.try_lock(), Err(TryLockError::WouldBlock)))
+    }
+
+    /// Lock the state of every thread slot in turn, in the order of the slots
+    pub(crate) fn thread_profiles(
+        &self,
+    ) -> impl Iterator<Item = MutexGuard<'_, ThreadProfile<TIMERS>>> {
+        self.threads
+            .iter()
+            .map(|thread| thread.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Get the state of every thread slot while the profiler is borrowed exclusively
+    pub(crate) fn thread_profiles_mut(
+        &mut self,
+    ) -> impl Iterator<Item = &mut ThreadProfile<TIMERS>> {
+        self.threads
+            .iter_mut()
+            .map(|thread| thread.get_mut().unwrap_or_else(PoisonError::into_inner))
+    }
+
     pub fn get_timer_index(&mut self, timer_name: &'static str) -> usize {
         if let Some(index) = self.timer_name_to_index.get(timer_name) {
             return *index as usize;
         }
 
+        // Make room for the timer if the soft limit of timers is reached
+        if self.next_index as usize >= Self::OVERFLOW_INDEX {
+            return self.evict_timer(timer_name);
+        }
+
         // Not yet seen timer. Add it to the profiler
         let curr_index = self.next_index;
         self.timer_name_to_index.insert(timer_name, curr_index);
@@ -176,6 +951,21 @@ impl<const THREADS: usize> Profiler<THREADS> {
         curr_index as usize
     }
 
+    /// Check if `timer_name` was given an index by [`Profiler::get_timer_index`]. Names
+    /// are never forgotten, evicted timers are mapped to the overflow timer.
+    #[must_use]
+    pub fn is_registered(&self, timer_name: &str) -> bool {
+        self.timer_name_to_index.contains_key(timer_name)
+    }
+
+    /// Get the index of the registered `timer_name` without registering it, the
+    /// overflow timer if it was never registered
+    pub(crate) fn timer_index(&self, timer_name: &str) -> usize {
+        self.timer_name_to_index
+            .get(timer_name)
+            .map_or(Self::OVERFLOW_INDEX, |index| *index as usize)
+    }
+
     /// Get the timer for a given thread
     ///
     /// # Panics
@@ -183,12 +973,7 @@ impl<const THREADS: usize> Profiler<THREADS> {
     /// If the given `thread_id` is too large
     pub fn get_timer(&mut self, thread_id: usize, timer: &'static str) -> &Timer {
         let index = self.get_timer_index(timer);
-
-        if let Some(timers) = self.timers.get(thread_id) {
-            &timers[index]
-        } else {
-            panic!("Unknown thread id: {thread_id}");
-        }
+        &self.thread_mut(thread_id).timers[index]
     }
 
     /// Get the &mut timer for a given thread
@@ -198,219 +983,416 @@ impl<const THREADS: usize> Profiler<THREADS> {
     /// If the given `thread_id` is too large
     pub fn get_timer_mut(&mut self, thread_id: usize, timer: &'static str) -> &mut Timer {
         let index = self.get_timer_index(timer);
+        &mut self.thread_mut(thread_id).timers[index]
+    }
 
-        if let Some(timers) = self.timers.get_mut(thread_id) {
-            &mut timers[index]
-        } else {
-            panic!("Unknown thread id: {thread_id}");
-        }
+    /// Enter `timer` on the given thread, starting its clock
+    pub fn enter_timer(
+        &mut self,
+        thread_id: usize,
+        timer: &'static str,
+        bytes_processed: u64,
+    ) -> OpenTimer {
+        self.get_timer_index(timer);
+        self.enter_registered_timer(thread_id, timer, bytes_processed)
     }
 
-    /// Start the timer for the given thread
-    pub fn start(&mut self, thread_id: usize) {
-        if self.thread_status[thread_id] != ThreadTimerStatus::Stopped {
-            println!("Attempted to start an already started timer on thread {thread_id}");
+    /// Enter the registered `timer` on the given thread, starting its clock. Only locks
+    /// the slot of the thread, so every thread can enter timers at once.
+    pub(crate) fn enter_registered_timer(
+        &self,
+        thread_id: usize,
+        timer: &'static str,
+        bytes_processed: u64,
+    ) -> OpenTimer {
+        if cfg!(feature = "counters") {
+            return counters::enter_counter(timer, bytes_processed);
+        }
+
+        let mut thread = self.thread(thread_id);
+        thread.capture_name();
+
+        if self.dry_run {
+            return self.enter_timer_dry_run(&mut thread, timer);
         }
 
-        self.thread_times[thread_id] = self.thread_times[thread_id].wrapping_sub(rdtsc());
-        self.thread_status[thread_id] = ThreadTimerStatus::Running;
+        // The parent is the innermost timer that is still open
+        let parent = thread.stack.last().copied();
+
+        // Add this timer to the full stack of open timers
+        thread.stack.push(timer);
+
+        self.enter_recursion(&mut thread, timer);
+        let nested_hits = thread.nested_hits;
+        drop(thread);
+
+        // Read the page faults, allocations and context switches before the clock starts
+        // to not time them
+        let start_page_faults = self.record_page_faults.then(get_page_faults);
+        let start_allocations = alloc::thread_allocations();
+        let start_context_switches = if self.record_context_switches {
+            ContextSwitches::current_thread()
+        } else {
+            None
+        };
+
+        OpenTimer {
+            timer,
+            parent,
+            start_time: rdtsc(),
+            bytes_processed,
+            paused_cycles: self.paused_cycles,
+            own_paused_cycles: 0,
+            own_paused_at: None,
+            start_page_faults,
+            start_allocations,
+            start_context_switches,
+            nested_hits,
+        }
     }
 
-    /// Stop the timer for the given thread
-    pub fn stop(&mut self, thread_id: usize) {
-        if self.thread_status[thread_id] != ThreadTimerStatus::Running {
-            println!("Attempted to stop an already stopped timer {thread_id}");
+    /// Enter `timer` on the given thread, using the already read `start_time` as the
+    /// start of its clock. Used to chain adjacent timers off of a single timestamp.
+    pub fn enter_timer_at(
+        &mut self,
+        thread_id: usize,
+        timer: &'static str,
+        bytes_processed: u64,
+        start_time: u64,
+    ) -> OpenTimer {
+        let mut open = self.enter_timer(thread_id, timer, bytes_processed);
+        open.start_time = start_time;
+        open
+    }
+
+    /// Exit the given `open` timer on the given thread without recording its hit or time.
+    /// The time spent in it is left to its parent.
+    pub fn cancel_timer(&self, thread_id: usize, open: &OpenTimer) {
+        if cfg!(feature = "counters") {
+            return;
         }
 
-        self.thread_times[thread_id] = self.thread_times[thread_id].wrapping_add(rdtsc());
-        self.thread_status[thread_id] = ThreadTimerStatus::Stopped;
+        self.cancel_hit(&mut self.thread(thread_id), open);
     }
 
-    /// Print a basic percentage-based status of the timers state
-    #[allow(clippy::too_many_lines, clippy::cast_precision_loss)]
-    pub fn print(&mut self) {
-        // Immediately stop the profiler's timer at the beginning of this function
-        let stop_time = rdtsc();
+    /// Exit the given `open` timer on the locked `thread` without recording its hit
+    fn cancel_hit(&self, thread: &mut ThreadProfile<TIMERS>, open: &OpenTimer) {
+        debug_assert_eq!(thread.stack.last(), Some(&open.timer));
+        thread.stack.pop();
+        self.exit_recursion(thread, open.timer);
+    }
 
-        for thread_id in 0..THREADS {
-            // Check if this timer is running and stop it if it is
-            if self.thread_status[thread_id] == ThreadTimerStatus::Running {
-                eprintln!("Thread {thread_id} was still running during print. Stopping it.");
-                self.thread_times[thread_id] = self.thread_times[thread_id].wrapping_add(stop_time);
-                self.thread_status[thread_id] = ThreadTimerStatus::Running;
-            }
+    /// Exit the given `open` timer on the given thread, stopping its clock at `stop_time`
+    ///
+    /// Only locks the slot of the thread, so every thread can exit timers at once.
+    pub fn exit_timer(&self, thread_id: usize, open: &OpenTimer, stop_time: u64) {
+        if cfg!(feature = "counters") {
+            self.exit_counter(thread_id, open, stop_time);
+            return;
         }
 
-        // Initialize the accumulated timers across all threads
-        let mut acc = [Timer::default(); MAX_TIMERS];
+        let mut thread = self.thread(thread_id);
 
-        // Fold all of the current timers into the first one
-        let mut total_time_cycles = 0;
-        for thread_id in 0..THREADS {
-            // Ignore thread if it wasn't used
-            if self.timers[thread_id].iter().map(|x| x.hits).sum::<u64>() == 0 {
-                continue;
-            }
+        if self.dry_run {
+            thread.stack.pop();
+            return;
+        }
 
-            // Get the current thread time
-            let thread_time = self.thread_times[thread_id];
+        // Calculate the elapsed time for this timer, without the time spent paused. A
+        // start given by `enter_timer_at` may be after the stop.
+        let elapsed = stop_time
+            .saturating_sub(open.start_time)
+            .saturating_sub(self.paused_during(open, stop_time))
+            .saturating_sub(open.paused_until(stop_time));
+
+        // Leave the time of the ignored short hits to the parent
+        if self.ignore_short_hit(thread_id, &mut thread, open, elapsed) {
+            self.cancel_hit(&mut thread, open);
+            return;
+        }
 
-            // Add this thread's time to the total time
-            total_time_cycles += thread_time;
+        // Attribute the allocations before the bookkeeping below allocates
+        thread.record_allocations(open);
 
-            for (timer_index, timer) in acc.iter_mut().enumerate() {
-                let Timer {
-                    inclusive_time,
-                    exclusive_time,
-                    hits,
-                    bytes_processed,
-                } = self.timers[thread_id][timer_index];
+        let outermost = self.exit_recursion(&mut thread, open.timer);
 
-                // Add the current timer to the accumulated timer
-                timer.inclusive_time += inclusive_time;
-                timer.exclusive_time += exclusive_time;
-                timer.hits += hits;
-                timer.bytes_processed += bytes_processed;
-            }
+        // If there is a parent timer, remove this elapsed time from the parent
+        if let Some(parent) = open.parent {
+            let parent_timer = &mut thread.timers[self.timer_index(parent)];
+            parent_timer.exclusive_time = parent_timer.exclusive_time.wrapping_sub(elapsed);
+            thread.nested_hits += 1;
         }
 
-        let os_timer_freq = calculate_os_frequency();
-        eprintln!("Calculated OS frequency: {os_timer_freq}");
+        self.record_sampled_hit(&mut thread, open, elapsed);
 
-        let mut variant_length = REMAINING_TIME_LABEL.len();
-        let mut hits_col_width = 1;
+        let curr_timer = &mut thread.timers[self.timer_index(open.timer)];
 
-        // Calculate the longest timer name
-        let max_timer_name = self.timer_names.iter().map(|x| x.len()).max().unwrap_or(0);
+        // Update this timer's elapsed time
+        curr_timer.exclusive_time = curr_timer.exclusive_time.wrapping_add(elapsed);
 
-        // Update the variant length to be the maximum length (capped at 60 chars)
-        variant_length = variant_length.max(max_timer_name).min(60);
+        // Only the outermost hit of a recursive timer counts towards its inclusive time,
+        // as it already includes the time of the nested hits
+        if outermost {
+            curr_timer.inclusive_time = curr_timer.inclusive_time.wrapping_add(elapsed);
+        }
 
-        let total_time_secs = total_time_cycles as f64 / os_timer_freq;
+        // Add this the number of bytes processed by this timer
+        curr_timer.bytes_processed += open.bytes_processed;
 
-        eprintln!(
-            "Total time: {:8.2?} ({total_time_cycles} cycles)",
-            std::time::Duration::from_secs_f64(total_time_secs)
+        // Track the shortest and longest hit
+        if curr_timer.hits == 0 || elapsed < curr_timer.min_cycles {
+            curr_timer.min_cycles = elapsed;
+        }
+        curr_timer.max_cycles = curr_timer.max_cycles.max(elapsed);
+
+        // Track the running mean and variance of the hits
+        curr_timer.record_moments(elapsed);
+
+        // Increment the hit count
+        curr_timer.hits += 1;
+
+        // Add the elapsed time to the distribution of this timer
+        self.record_sketch(&mut thread, open.timer, elapsed);
+        #[cfg(feature = "hist")]
+        thread.record_histogram(open.timer, elapsed);
+        self.record_log2_histogram(&mut thread, open.timer, elapsed);
+        self.record_percentile_sample(&mut thread, open.timer, elapsed);
+
+        // Attribute the hit to the edge from its caller
+        self.record_caller(&mut thread, open.parent, open.timer, elapsed);
+
+        // Attribute the elapsed time to the full timer stack
+        self.pop_stack(&mut thread, elapsed);
+
+        // Sample this hit as an exemplar of the timer
+        self.record_exemplar(
+            thread_id,
+            &mut thread,
+            open.timer,
+            open.start_time,
+            stop_time,
         );
 
-        let mut other = total_time_cycles;
+        // Record the event for the trace exports
+        self.record_event(&mut thread, open.timer, open.start_time, stop_time);
+        self.record_series_hit(&mut thread, open.timer, stop_time, elapsed);
+
+        thread.record_page_fault_delta(open);
+        thread.record_context_switch_delta(open);
+
+        // The window snapshot locks the slot of every thread, including this one
+        drop(thread);
+
+        // Roll the window forward once its next slice is due
+        if stop_time >= self.window_next_tick.load(Ordering::Relaxed) {
+            self.tick_window();
+        }
+    }
+
+    /// Start the timer for the given thread
+    pub fn start(&self, thread_id: usize) {
+        let mut thread = self.thread(thread_id);
+        thread.capture_name();
+
+        if thread.status != ThreadTimerStatus::Stopped {
+            println!("Attempted to start an already started timer on thread {thread_id}");
+        }
 
-        // Calculate the maximum width of the hits column
-        let mut hit_width = "HITS".len();
-        for Timer { hits, .. } in &acc {
-            hit_width = hit_width.max(format!("{hits}").len());
+        // Threads started while paused only start their clock on resume
+        if self.is_paused() {
+            thread.status = ThreadTimerStatus::Paused;
+            return;
         }
 
-        let mut not_hit = Vec::new();
-        let mut results = Vec::new();
+        thread.time = thread.time.wrapping_sub(rdtsc());
+        thread.status = ThreadTimerStatus::Running;
+    }
 
-        for (i, timer) in acc.iter().enumerate() {
-            let Timer {
-                inclusive_time,
-                exclusive_time,
-                hits,
-                bytes_processed,
-            } = *timer;
+    /// Stop the timer for the given thread
+    pub fn stop(&self, thread_id: usize) {
+        let mut thread = self.thread(thread_id);
 
-            // If this timer wasn't hit, add it to the not hit list
-            if hits == 0 {
-                not_hit.push(i);
-                continue;
+        match thread.status {
+            ThreadTimerStatus::Running => {
+                thread.time = thread.time.wrapping_add(rdtsc());
+            }
+            ThreadTimerStatus::Paused => {
+                // The clock was already stopped by the pause
+            }
+            ThreadTimerStatus::Stopped => {
+                println!("Attempted to stop an already stopped timer {thread_id}");
             }
+        }
 
-            other = other.wrapping_sub(exclusive_time);
-            let percent = exclusive_time as f64 / total_time_cycles as f64 * 100.;
+        thread.status = ThreadTimerStatus::Stopped;
+    }
 
-            // Include the total time if it was included
-            let mut inclusive_time_str = String::new();
+    /// Zero every timer and thread time so the next report only covers the time after
+    /// this call, such as to profile distinct phases of a long-running program
+    ///
+    /// Timer names and settings are kept. Running threads restart their clock now.
+    /// Timers that are open during the reset keep their place in the stack and still
+    /// count their time from before the reset when they exit.
+    pub fn reset(&mut self) {
+        let now = rdtsc();
+
+        for thread in self.thread_profiles_mut() {
+            thread.reset(now);
+        }
 
-            if inclusive_time > 0 {
-                let total_time_percent = inclusive_time as f64 / total_time_cycles as f64 * 100.;
+        // The snapshots are of the timers before the reset
+        self.window_snapshots
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        *self.window_next_tick.get_mut() = 0;
 
-                if (total_time_percent - percent).abs() >= 0.1 {
-                    inclusive_time_str = format!("({total_time_percent:5.2}% with child timers)");
-                }
+        #[cfg(feature = "otel")]
+        {
+            self.otel_exported = [Timer::const_default(); TIMERS];
+        }
+    }
+
+    /// Stop any thread timers that are still running at `stop_time`
+    pub(crate) fn stop_running_threads(&mut self, stop_time: u64) {
+        for (thread_id, thread) in self.thread_profiles_mut().enumerate() {
+            // Check if this timer is running and stop it if it is
+            if thread.status == ThreadTimerStatus::Running {
+                eprintln!("Thread {thread_id} was still running during print. Stopping it.");
+                thread.time = thread.time.wrapping_add(stop_time);
+                thread.status = ThreadTimerStatus::Stopped;
             }
+        }
+    }
+
+    /// Accumulate the timers across all used threads, returning the accumulated timers
+    /// and the total time (in cycles) of those threads
+    pub(crate) fn accumulate_timers(&self) -> ([Timer; TIMERS], u64) {
+        // Lock every thread before reading any of them, so that concurrent readers see
+        // the same cut of the timers and never a thread midway through its hits
+        let threads: Vec<_> = self.thread_profiles().collect();
+        self.accumulate_locked_timers(&threads)
+    }
 
-            let mut throughput_str = String::new();
-            if bytes_processed > 0 {
-                // const MEGABYTE: f64 = 1024.0 * 1024.0;
-                const GIGABYTE: f64 = 1024.0 * 1024.0 * 1024.0;
+    /// Accumulate the timers as [`Profiler::accumulate_timers`] does, along with the
+    /// details of every reported timer read from the same cut of the threads
+    pub(crate) fn accumulate_timer_details(&self) -> ([Timer; TIMERS], u64, Vec<TimerDetails>) {
+        let threads: Vec<_> = self.thread_profiles().collect();
+        let (acc, total_time_cycles) = self.accumulate_locked_timers(&threads);
+
+        let details = acc
+            .iter()
+            .enumerate()
+            .map(|(index, timer)| {
+                if self.is_reported(index, timer) {
+                    self.timer_details(&threads, index)
+                } else {
+                    TimerDetails::default()
+                }
+            })
+            .collect();
 
-                let time = inclusive_time as f64 / os_timer_freq;
+        (acc, total_time_cycles, details)
+    }
 
-                let bytes_per_sec = bytes_processed as f64 / time;
-                let gbs_per_sec = bytes_per_sec / GIGABYTE;
-                throughput_str = format!("{gbs_per_sec:5.3} GBs/sec");
-            }
+    /// Accumulate the timers across the already locked `threads`
+    fn accumulate_locked_timers(
+        &self,
+        threads: &[MutexGuard<ThreadProfile<TIMERS>>],
+    ) -> ([Timer; TIMERS], u64) {
+        // Initialize the accumulated timers across all threads
+        let mut acc = [Timer::default(); TIMERS];
 
-            hits_col_width = hits_col_width.max(format!("{hits}").len());
+        // Fold all of the current timers into the first one
+        let mut total_time_cycles = 0;
+        for thread in threads {
+            // Ignore thread if it wasn't used
+            if !thread.is_used() {
+                continue;
+            }
 
-            let name = self.timer_names[i];
-            let name = name[..name.len().min(variant_length)].to_string();
+            // Add this thread's time, up to now if it is still running, to the total time
+            total_time_cycles += thread.elapsed();
 
-            results.push(TimerResult {
-                name,
-                exclusive_time,
-                inclusive_time_str,
-                hits,
-                percent,
-                throughput_str,
-            });
+            for (timer, thread_timer) in acc.iter_mut().zip(&thread.timers) {
+                // Add the current timer to the accumulated timer
+                timer.merge(thread_timer);
+            }
         }
 
-        results.sort_by_key(|timer| timer.exclusive_time);
+        self.scale_sampled_timers(&mut acc, threads);
 
-        eprintln!(
-            "{:<width$} | {:^hits_width$}",
-            "TIMER",
-            "HITS",
-            width = variant_length,
-            hits_width = hits_col_width
-        );
+        (acc, total_time_cycles)
+    }
 
-        for TimerResult {
-            name,
-            hits,
-            exclusive_time,
-            percent,
-            inclusive_time_str,
-            throughput_str,
-        } in results.iter().rev()
-        {
-            // Print the stats for this timer
-            eprintln!(
-                "{name:<variant_length$} | {hits:<hit_width$} | {exclusive_time:14.2?} cycles {percent:6.2}% | {inclusive_time_str} {throughput_str}",
-            );
+    /// Print which of the given `functions` are instrumented and how much of the total
+    /// time falls outside of every timer
+    ///
+    /// Functions are matched against the timer names generated by `#[profile]`
+    /// (`Fn__name`) and `#[profile_impl]` (`Type::name`) as well as raw timer names.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn print_coverage(&mut self, functions: &[&str]) {
+        self.stop_running_threads(rdtsc());
+
+        let (acc, total_time_cycles) = self.accumulate_timers();
+        let timer_names = &self.timer_names[..self.next_index as usize];
+
+        let name_width = functions
+            .iter()
+            .map(|func| func.len())
+            .max()
+            .unwrap_or(0)
+            .max("FUNCTION".len());
+
+        let mut instrumented = 0;
+
+        eprintln!("{:<name_width$} | {:<12} | HITS", "FUNCTION", "STATUS");
+        for func in functions {
+            let index = timer_names
+                .iter()
+                .position(|timer| timer_matches_function(timer, func));
+
+            if let Some(index) = index {
+                instrumented += 1;
+                let hits = acc[index].hits;
+                eprintln!("{func:<name_width$} | {:<12} | {hits}", "instrumented");
+            } else {
+                eprintln!("{func:<name_width$} | {:<12} |", "missing");
+            }
         }
 
-        // Print the remaining
+        // Everything not attributed to a timer is in the remainder
+        let covered = acc
+            .iter()
+            .fold(0_u64, |acc, timer| acc.wrapping_add(timer.exclusive_time));
+        let remainder = total_time_cycles.saturating_sub(covered);
+
         eprintln!(
-            "{:<width$} | {:<hit_width$} | {other:14.2?} cycles {:6.2}%",
-            REMAINING_TIME_LABEL,
-            "",
-            other as f64 / total_time_cycles as f64 * 100.,
-            width = variant_length,
-            hit_width = hit_width
+            "Instrumented {instrumented}/{} functions. {:5.2}% of the total time ({remainder} cycles) is in the {REMAINING_TIME_LABEL}",
+            functions.len(),
+            remainder as f64 / total_time_cycles as f64 * 100.
         );
+    }
+}
 
-        /*
-        println!("The following timers were not hit");
-        for timer in not_hit {
-            println!("{:?}", T::try_from(timer).unwrap());
-        }
-        */
+/// Check if the given timer name was generated for the given function name
+fn timer_matches_function(timer: &str, function: &str) -> bool {
+    if timer == function {
+        return true;
+    }
+
+    // `#[profile]` timers are named `Fn__name`
+    if timer.strip_prefix("Fn__") == Some(function) {
+        return true;
     }
+
+    // `#[profile_impl]` timers are named `Type::name`
+    timer
+        .rsplit_once("::")
+        .is_some_and(|(_, method)| method == function)
 }
 
-/// Calculate the OS frequency by timing a small timeout using `rdtsc`
-#[allow(clippy::cast_precision_loss)]
+/// Get the OS frequency of `rdtsc`, calibrated once on first use
 fn calculate_os_frequency() -> f64 {
-    let timeout = Duration::from_millis(100);
-    let start = Instant::now();
-    let clock_start = rdtsc();
-    while start.elapsed() < timeout {}
-    let clock_end = rdtsc();
-
-    (clock_end - clock_start) as f64 / timeout.as_secs_f64()
+    tsc_frequency()
 }
@@ -1,5 +1,8 @@
 /// Macro for creating various functions needed for the profiler
 /// over the enum of profile points, such as Into<usize> and `TryFrom`<usize>
+///
+/// The enum also implements [`TimerKey`](crate::TimerKey), so its variants can be used
+/// as timers and registered with `register_timers!`
 #[macro_export]
 macro_rules! impl_enum {
     (   // Base case of an enum that we want only one item of
@@ -20,6 +23,16 @@ macro_rules! impl_enum {
             )*
         }
 
+        impl From<$name> for &'static str {
+            fn from(value: $name) -> &'static str {
+                <$name as $crate::TimerKey>::NAMES[value as usize]
+            }
+        }
+
+        impl $crate::TimerKey for $name {
+            const NAMES: &'static [&'static str] = &[$(stringify!($var_name)),*];
+        }
+
         impl Into<usize> for $name {
             fn into(self) -> usize {
                 self as usize
@@ -54,118 +67,454 @@ macro_rules! impl_enum {
 #[allow(clippy::crate_in_macro_def)]
 macro_rules! create_profiler {
     () => {
-        pub const NUM_THREADS: usize = 4096;
+        $crate::create_profiler!(@options
+            [$crate::MAX_THREADS] [$crate::MAX_TIMERS] [$crate::ShortTimerPolicy::DEFAULT] []
+        );
+    };
+    (report_at_exit $($options:tt)*) => {
+        $crate::create_profiler!(@options
+            [$crate::MAX_THREADS] [$crate::MAX_TIMERS] [$crate::ShortTimerPolicy::DEFAULT] []
+            report_at_exit $($options)*
+        );
+    };
+    ($option:ident = $($options:tt)*) => {
+        $crate::create_profiler!(@options
+            [$crate::MAX_THREADS] [$crate::MAX_TIMERS] [$crate::ShortTimerPolicy::DEFAULT] []
+            $option = $($options)*
+        );
+    };
+    ($name:ident $(, $($options:tt)*)?) => {
+        /// A separate named profiler, used by passing `in` its path to the macros
+        #[allow(non_snake_case)]
+        pub mod $name {
+            $crate::create_profiler!($($($options)*)?);
+        }
+    };
+    // Accumulate the comma separated options into the threads, timers, short timer
+    // policy and the statements run by `_on_start`
+    (@options [$_threads:expr] [$timers:expr] [$policy:expr] [$($on_start:tt)*]
+        threads = $threads:expr $(, $($options:tt)*)?
+    ) => {
+        $crate::create_profiler!(@options
+            [$threads] [$timers] [$policy] [$($on_start)*] $($($options)*)?
+        );
+    };
+    (@options [$threads:expr] [$_timers:expr] [$policy:expr] [$($on_start:tt)*]
+        timers = $timers:expr $(, $($options:tt)*)?
+    ) => {
+        $crate::create_profiler!(@options
+            [$threads] [$timers] [$policy] [$($on_start)*] $($($options)*)?
+        );
+    };
+    (@options [$threads:expr] [$timers:expr] [$_policy:expr] [$($on_start:tt)*]
+        short_timers = $policy:expr $(, $($options:tt)*)?
+    ) => {
+        $crate::create_profiler!(@options
+            [$threads] [$timers] [$policy] [$($on_start)*] $($($options)*)?
+        );
+    };
+    (@options [$threads:expr] [$timers:expr] [$policy:expr] [$($on_start:tt)*]
+        statsd = $config:expr $(, $($options:tt)*)?
+    ) => {
+        $crate::create_profiler!(@options [$threads] [$timers] [$policy] [
+            $($on_start)*
 
-        // Create the static profiler
-        static mut TIMELOOP_PROFILER: timeloop::Profiler<NUM_THREADS> =
-            timeloop::Profiler::<NUM_THREADS>::new();
+            $crate::spawn_statsd_emitter($config, &TIMELOOP_PROFILER)
+            .expect("Failed to start the statsd emitter");
+        ] $($($options)*)?);
+    };
+    (@options [$threads:expr] [$timers:expr] [$policy:expr] [$($on_start:tt)*]
+        report_at_exit = $path:expr $(, $($options:tt)*)?
+    ) => {
+        $crate::create_profiler!(@options [$threads] [$timers] [$policy] [
+            $($on_start)*
+            $crate::create_profiler!(@report_at_exit Some($path.into()));
+        ] $($($options)*)?);
+    };
+    (@options [$threads:expr] [$timers:expr] [$policy:expr] [$($on_start:tt)*]
+        report_at_exit $(, $($options:tt)*)?
+    ) => {
+        $crate::create_profiler!(@options [$threads] [$timers] [$policy] [
+            $($on_start)*
+            $crate::create_profiler!(@report_at_exit None);
+        ] $($($options)*)?);
+    };
+    (@options [$threads:expr] [$timers:expr] [$policy:expr] [$($on_start:tt)*]) => {
+        $crate::create_profiler!(@profiler $threads, $timers, $policy);
+
+        /// Called by `start_profiler!` when the profiler is started
+        #[doc(hidden)]
+        pub fn _on_start() {
+            $($on_start)*
+        }
+    };
+    (@report_at_exit $path:expr) => {{
+        static REGISTERED: std::sync::Once = std::sync::Once::new();
 
-        // The current node being profiled, used to save who called which timer
-        static mut PROFILER_PARENT: [Option<&'static str>; NUM_THREADS] = [None; NUM_THREADS];
+        REGISTERED.call_once(|| {
+            $crate::report_at_exit($path, &TIMELOOP_PROFILER)
+                .expect("Failed to register the report at exit");
+        });
+    }};
+    (@profiler $threads:expr, $timers:expr, $policy:expr) => {
+        #[doc(hidden)]
+        pub const NUM_THREADS: usize = $threads;
 
-        pub struct _ScopedTimer {
-            /// This name of this current timer
-            timer: &'static str,
+        #[doc(hidden)]
+        pub const NUM_TIMERS: usize = $timers;
+
+        // Create the static profiler
+        #[doc(hidden)]
+        pub static TIMELOOP_PROFILER: $crate::ProfilerCell<
+            $crate::Profiler<NUM_THREADS, NUM_TIMERS>,
+        > = $crate::ProfilerCell::new(
+            $crate::Profiler::<NUM_THREADS, NUM_TIMERS>::new().with_short_timer_policy($policy),
+        );
 
-            /// The starting time for this timer
-            start_time: u64,
+        /// Set while the profiler is paused, checked before entering every timer
+        #[doc(hidden)]
+        pub static TIMELOOP_PAUSED: std::sync::atomic::AtomicBool =
+            std::sync::atomic::AtomicBool::new(false);
 
-            /// The parent of this timer
-            parent: Option<&'static str>,
+        #[doc(hidden)]
+        pub struct _ScopedTimer {
+            /// The state of the entered timer
+            open: $crate::OpenTimer,
 
-            /// The former inclusive time for this type of timer
-            old_inclusive_time: u64,
+            /// Set when the timer was already exited by [`_ScopedTimer::chain`] or was
+            /// never entered
+            finished: bool,
 
-            /// Number of bytes during this timer
-            bytes_processed: u64,
+            /// Set by [`_ScopedTimer::cancel`] to discard the measurement when exited
+            canceled: bool,
         }
 
-        /// Get the ID for the current thread that is guarenteed to be non-zero
+        /// The guard of a timer entered by `timer_guard!`, exiting the timer when dropped
+        pub type TimerGuard = _ScopedTimer;
+
+        /// Get the slot of the current thread in the profiler
+        #[doc(hidden)]
         pub fn thread_id() -> usize {
-            let thread_id = std::thread::current().id().as_u64().get() as usize;
+            let thread_id = $crate::_thread_slot();
 
             assert!(
                 thread_id < NUM_THREADS,
-                "Too many threads. Increase timeloop::NUM_THREADS"
+                "Too many threads. Increase the threads of create_profiler!"
             );
 
-            extern "C" {
-                fn pthread_self() -> u64;
-            }
-
             thread_id
         }
 
         impl _ScopedTimer {
-            fn new(timer: &'static str) -> Self {
+            pub fn new(timer: impl Into<&'static str>) -> Self {
+                _ScopedTimer::_new(timer.into(), 0)
+            }
+
+            pub fn new_with_bandwidth(
+                timer: impl Into<&'static str>,
+                bytes_processed: u64,
+            ) -> Self {
+                _ScopedTimer::_new(timer.into(), bytes_processed)
+            }
+
+            pub fn new_in_category(
+                timer: impl Into<&'static str>,
+                category: &'static str,
+            ) -> Self {
+                let timer = timer.into();
+                TIMELOOP_PROFILER.set_category(timer, category);
+
+                _ScopedTimer::_new(timer, 0)
+            }
+
+            /// Enter `timer` on only one of every `rate` calls, counted by `calls` for
+            /// each thread
+            pub fn new_sampled(
+                timer: impl Into<&'static str>,
+                rate: u64,
+                calls: &'static std::thread::LocalKey<std::cell::Cell<u64>>,
+            ) -> Self {
+                let rate = rate.max(1);
+                let call = calls.with(|calls| calls.replace(calls.get().wrapping_add(1)));
+                if call % rate != 0 {
+                    return _ScopedTimer::skipped();
+                }
+
+                let timer = timer.into();
+                TIMELOOP_PROFILER.set_sample_rate(timer, rate);
+
                 _ScopedTimer::_new(timer, 0)
             }
 
-            fn new_with_bandwidth(timer: &'static str, bytes_processed: u64) -> Self {
-                _ScopedTimer::_new(timer, bytes_processed)
+            /// Enter `timer`, pinning it so that it is never ignored for being too short
+            pub fn new_pinned(timer: impl Into<&'static str>) -> Self {
+                let timer = timer.into();
+                TIMELOOP_PROFILER.pin_timer(timer);
+
+                _ScopedTimer::_new(timer, 0)
             }
 
             fn _new(timer: &'static str, bytes_processed: u64) -> Self {
+                if !$crate::is_enabled()
+                    || TIMELOOP_PAUSED.load(std::sync::atomic::Ordering::Relaxed)
+                {
+                    return _ScopedTimer::skipped();
+                }
+
+                let thread_id = thread_id();
+
+                let open = TIMELOOP_PROFILER.enter_timer(thread_id, timer, bytes_processed);
+
+                _ScopedTimer {
+                    open,
+                    finished: false,
+                    canceled: false,
+                }
+            }
+
+            /// Exclude the time until [`_ScopedTimer::resume`] from this timer, such as
+            /// while waiting inside the timed scope
+            pub fn pause(&mut self) {
+                self.open.pause();
+            }
+
+            /// Resume timing this timer after [`_ScopedTimer::pause`]
+            pub fn resume(&mut self) {
+                self.open.resume();
+            }
+
+            /// Discard the measurement of this timer when it is exited, such as on an
+            /// error or early exit path that would skew the timer
+            pub fn cancel(&mut self) {
+                self.canceled = true;
+            }
+
+            /// Set the number of bytes processed by this timer, for when it is only
+            /// known inside the scope (such as once a read returns)
+            pub fn set_bytes(&mut self, bytes: u64) {
+                self.open.bytes_processed = bytes;
+            }
+
+            /// Add `bytes` to the number of bytes processed by this timer
+            pub fn add_bytes(&mut self, bytes: u64) {
+                self.open.bytes_processed += bytes;
+            }
+
+            /// A timer that was never entered, such as while the profiler is paused
+            fn skipped() -> Self {
+                _ScopedTimer {
+                    open: $crate::OpenTimer::default(),
+                    finished: true,
+                    canceled: false,
+                }
+            }
+
+            /// Exit the `prev` timer and enter `timer` using a single timestamp read
+            pub fn chain(prev: Option<Self>, timer: impl Into<&'static str>) -> Self {
+                let timer = timer.into();
+                let timestamp = $crate::_timestamp();
                 let thread_id = thread_id();
 
-                // Get the parent timer for this new timer
-                let parent = unsafe {
-                    let parent = PROFILER_PARENT[thread_id];
-                    PROFILER_PARENT[thread_id] = Some(timer);
-                    parent
-                };
+                if let Some(mut prev) = prev {
+                    if !prev.finished {
+                        if prev.canceled {
+                            TIMELOOP_PROFILER.cancel_timer(thread_id, &prev.open);
+                        } else {
+                            TIMELOOP_PROFILER.exit_timer(thread_id, &prev.open, timestamp);
+                        }
+                    }
+                    prev.finished = true;
+                }
+
+                if !$crate::is_enabled()
+                    || TIMELOOP_PAUSED.load(std::sync::atomic::Ordering::Relaxed)
+                {
+                    return _ScopedTimer::skipped();
+                }
 
-                let old_inclusive_time = unsafe {
-                    crate::TIMELOOP_PROFILER
-                        .get_timer(thread_id, timer)
-                        .inclusive_time
-                };
+                let open = TIMELOOP_PROFILER.enter_timer_at(thread_id, timer, 0, timestamp);
 
                 _ScopedTimer {
-                    timer,
-                    start_time: unsafe { std::arch::x86_64::_rdtsc() },
-                    parent,
-                    old_inclusive_time,
-                    bytes_processed,
+                    open,
+                    finished: false,
+                    canceled: false,
                 }
             }
         }
 
-        impl Drop for _ScopedTimer {
-            fn drop(&mut self) {
-                let thread_id = thread_id();
+        std::thread_local! {
+            /// The slot of the forking thread and the profiler it holds locked during a
+            /// `fork()`
+            static _FORK_GUARD: std::cell::RefCell<
+                Option<(
+                    usize,
+                    std::sync::RwLockWriteGuard<
+                        'static,
+                        $crate::Profiler<NUM_THREADS, NUM_TIMERS>,
+                    >,
+                )>,
+            > = const { std::cell::RefCell::new(None) };
+        }
 
-                unsafe {
-                    // Reset the current parent node
-                    PROFILER_PARENT[thread_id] = self.parent;
+        /// Lock the profiler before a `fork()`, so no other thread holds it while the
+        /// process is copied
+        extern "C" fn _prepare_fork() {
+            let thread_id = $crate::_thread_slot();
+            let profiler = TIMELOOP_PROFILER.write();
+            _FORK_GUARD.with(|guard| *guard.borrow_mut() = Some((thread_id, profiler)));
+        }
+
+        /// Unlock the profiler in the parent process after a `fork()`
+        extern "C" fn _after_fork_parent() {
+            _FORK_GUARD.with(|guard| guard.borrow_mut().take());
+        }
 
-                    // Calculate the elapsed time for this timer
-                    let stop_time = unsafe { std::arch::x86_64::_rdtsc() };
-                    let elapsed = stop_time - self.start_time;
+        /// Unlock the profiler in the child process after a `fork()`, marking it to be
+        /// reset on its next use as nothing may be allocated until `fork()` returns
+        extern "C" fn _after_fork_child() {
+            _FORK_GUARD.with(|guard| {
+                if let Some((thread_id, profiler)) = guard.borrow_mut().take() {
+                    TIMELOOP_PROFILER.mark_forked(thread_id);
+                    drop(profiler);
+                }
+            });
+        }
 
-                    // If there is a parent timer, remove this elapsed time from the parent
-                    if let Some(parent) = self.parent {
-                        let mut parent_timer =
-                            &mut crate::TIMELOOP_PROFILER.get_timer_mut(thread_id, parent);
+        /// Register the `fork()` handlers resetting the profiler in every forked child
+        /// process
+        #[doc(hidden)]
+        pub fn _register_atfork() {
+            static REGISTER: std::sync::Once = std::sync::Once::new();
 
-                        parent_timer.exclusive_time =
-                            parent_timer.exclusive_time.wrapping_sub(elapsed);
-                    }
+            extern "C" {
+                fn pthread_atfork(
+                    prepare: Option<extern "C" fn()>,
+                    parent: Option<extern "C" fn()>,
+                    child: Option<extern "C" fn()>,
+                ) -> i32;
+            }
+
+            REGISTER.call_once(|| unsafe {
+                pthread_atfork(
+                    Some(_prepare_fork),
+                    Some(_after_fork_parent),
+                    Some(_after_fork_child),
+                );
+            });
+        }
+
+        /// A [`std::thread::JoinHandle`] which times joining the thread
+        pub struct ProfiledJoinHandle<T> {
+            /// The handle of the spawned thread
+            handle: std::thread::JoinHandle<T>,
+
+            /// The timer used when joining this thread
+            join_timer: &'static str,
+        }
+
+        impl<T> ProfiledJoinHandle<T> {
+            /// Wait for the thread to finish, timing the wait
+            pub fn join(self) -> std::thread::Result<T> {
+                let _timer = _ScopedTimer::new(self.join_timer);
+                self.handle.join()
+            }
+
+            /// Get the underlying thread
+            pub fn thread(&self) -> &std::thread::Thread {
+                self.handle.thread()
+            }
+        }
+
+        /// An iterator timing every call to `next()` of the wrapped iterator as a hit of
+        /// its timer, counting the items it yields as the `items` counter of the timer
+        pub struct TimedIterator<I> {
+            /// The timed iterator
+            iter: I,
+
+            /// The timer of every call to `next()`
+            timer: &'static str,
+        }
+
+        impl<I> TimedIterator<I> {
+            /// Time every call to `next()` of `iter` as a hit of `timer`
+            pub fn new(timer: impl Into<&'static str>, iter: I) -> Self {
+                TimedIterator {
+                    iter,
+                    timer: timer.into(),
+                }
+            }
+        }
+
+        impl<I: Iterator> Iterator for TimedIterator<I> {
+            type Item = I::Item;
+
+            fn next(&mut self) -> Option<I::Item> {
+                let timer = _ScopedTimer::new(self.timer);
+                let item = self.iter.next();
+
+                if item.is_some() && !timer.finished {
+                    TIMELOOP_PROFILER.read().count(thread_id(), "items", 1);
+                }
+
+                item
+            }
 
-                    let mut curr_timer =
-                        &mut crate::TIMELOOP_PROFILER.get_timer_mut(thread_id, self.timer);
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.iter.size_hint()
+            }
+        }
+
+        /// Spawn a thread named `name` that is profiled for its entire lifetime
+        #[doc(hidden)]
+        pub fn _spawn<F, T>(
+            name: &'static str,
+            spawn_timer: &'static str,
+            join_timer: &'static str,
+            func: F,
+        ) -> ProfiledJoinHandle<T>
+        where
+            F: FnOnce() -> T + Send + 'static,
+            T: Send + 'static,
+        {
+            let _timer = _ScopedTimer::new(spawn_timer);
+
+            let handle = std::thread::Builder::new()
+                .name(name.to_string())
+                .spawn(move || {
+                    let thread_id = thread_id();
+                    TIMELOOP_PROFILER.read().start(thread_id);
+
+                    let result = {
+                        let _timer = _ScopedTimer::new(name);
+                        func()
+                    };
+
+                    TIMELOOP_PROFILER.read().stop(thread_id);
+
+                    result
+                })
+                .expect("Failed to spawn thread");
 
-                    // Update this timer's elapsed time
-                    curr_timer.exclusive_time = curr_timer.exclusive_time.wrapping_add(elapsed);
+            ProfiledJoinHandle { handle, join_timer }
+        }
+
+        impl Drop for _ScopedTimer {
+            fn drop(&mut self) {
+                // Immediately stop the timer before any other bookkeeping
+                let stop_time = $crate::_timestamp();
 
-                    // Specifically overwritting this timer to always
-                    curr_timer.inclusive_time = self.old_inclusive_time + elapsed;
+                // Chained timers were already exited by the next timer in the chain and
+                // skipped timers were never entered
+                if self.finished {
+                    return;
+                }
 
-                    // Add this the number of bytes processed by this timer
-                    curr_timer.bytes_processed += self.bytes_processed;
+                let thread_id = thread_id();
 
-                    // Increment the hit count
-                    curr_timer.hits += 1;
+                if self.canceled {
+                    TIMELOOP_PROFILER.cancel_timer(thread_id, &self.open);
+                } else {
+                    TIMELOOP_PROFILER.exit_timer(thread_id, &self.open, stop_time);
                 }
             }
         }
@@ -175,49 +524,124 @@ macro_rules! create_profiler {
 #[macro_export]
 #[cfg(feature = "enable")]
 macro_rules! time_work {
-    ($timer:expr, $work:expr) => {{
+    (in $($root:ident)::+, $timer:expr, category = $category:expr, $work:expr) => {{
+        {
+            $crate::scoped_timer!(in $($root)::+, $timer, category = $category);
+
+            let result = $work;
+            result
+        }
+    }};
+    (in $($root:ident)::+, $timer:expr, $work:expr) => {{
         {
-            timeloop::scoped_timer!($timer);
+            $crate::scoped_timer!(in $($root)::+, $timer);
 
             let result = $work;
             result
         }
     }};
+    ($timer:expr, category = $category:expr, $work:expr) => {
+        $crate::time_work!(in crate, $timer, category = $category, $work)
+    };
+    ($timer:expr, $work:expr) => {
+        $crate::time_work!(in crate, $timer, $work)
+    };
 }
 
 #[macro_export]
 #[cfg(feature = "enable")]
 macro_rules! time_work_with_bandwidth {
-    ($timer:expr, $bytes:expr, $work:expr) => {{
+    (in $($root:ident)::+, $timer:expr, $bytes:expr, $work:expr) => {{
         {
-            timeloop::scoped_bandwidth_timer!($timer, $bytes);
+            $crate::scoped_bandwidth_timer!(in $($root)::+, $timer, $bytes);
 
             let result = $work;
             result
         }
     }};
+    ($timer:expr, $bytes:expr, $work:expr) => {
+        $crate::time_work_with_bandwidth!(in crate, $timer, $bytes, $work)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! time_iter {
+    (in $($root:ident)::+, $timer:expr, $iter:expr) => {
+        $($root)::+::TimedIterator::new($timer, $iter)
+    };
+    ($timer:expr, $iter:expr) => {
+        $crate::time_iter!(in crate, $timer, $iter)
+    };
+}
+
+/// Time calling the closure `$func`, returning its result
+///
+/// Unlike the block given to `time_work!`, a `return` or `?` in the closure only leaves
+/// the closure, so it can't skip past the code following the timed work. The result is
+/// returned identically whether or not the profiler is enabled.
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! time_work_fn {
+    (in $($root:ident)::+, $timer:expr, $func:expr) => {{
+        let _timer = $($root)::+::_ScopedTimer::new($timer);
+        ($func)()
+    }};
+    ($timer:expr, $func:expr) => {
+        $crate::time_work_fn!(in crate, $timer, $func)
+    };
 }
 
 #[macro_export]
 #[cfg(feature = "enable")]
 #[allow(clippy::crate_in_macro_def)]
 macro_rules! raw_timer {
-    ($timer:expr) => {{
-        crate::_ScopedTimer::new($timer)
+    (in $($root:ident)::+, $timer:expr) => {{
+        $($root)::+::_ScopedTimer::new($timer)
+    }};
+    ($timer:expr) => {
+        $crate::raw_timer!(in crate, $timer)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! pause_profiler {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PAUSED.store(true, std::sync::atomic::Ordering::Relaxed);
+        $($root)::+::TIMELOOP_PROFILER.write().pause();
+    }};
+    () => {
+        $crate::pause_profiler!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! resume_profiler {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().resume();
+        $($root)::+::TIMELOOP_PAUSED.store(false, std::sync::atomic::Ordering::Relaxed);
     }};
+    () => {
+        $crate::resume_profiler!(in crate)
+    };
 }
 
 #[macro_export]
 #[cfg(feature = "enable")]
 #[allow(clippy::crate_in_macro_def)]
 macro_rules! start_thread {
+    (in $($root:ident)::+) => {{
+        let thread_id = $($root)::+::thread_id();
+        $($root)::+::TIMELOOP_PROFILER.read().start(thread_id);
+    }};
     () => {
-        unsafe {
-            {
-                let thread_id = crate::thread_id();
-                crate::TIMELOOP_PROFILER.start(thread_id);
-            }
-        }
+        $crate::start_thread!(in crate)
     };
 }
 
@@ -225,13 +649,16 @@ macro_rules! start_thread {
 #[cfg(feature = "enable")]
 #[allow(clippy::crate_in_macro_def)]
 macro_rules! start_profiler {
+    (in $($root:ident)::+) => {{
+        let thread_id = $($root)::+::thread_id();
+        $($root)::+::TIMELOOP_PROFILER.read().start(thread_id);
+
+        $($root)::+::TIMELOOP_PROFILER.write().load_short_timer_policy_from_env();
+        $($root)::+::_register_atfork();
+        $($root)::+::_on_start();
+    }};
     () => {
-        unsafe {
-            {
-                let thread_id = crate::thread_id();
-                crate::TIMELOOP_PROFILER.start(thread_id);
-            }
-        }
+        $crate::start_profiler!(in crate)
     };
 }
 
@@ -239,13 +666,12 @@ macro_rules! start_profiler {
 #[cfg(feature = "enable")]
 #[allow(clippy::crate_in_macro_def)]
 macro_rules! stop_thread {
+    (in $($root:ident)::+) => {{
+        let thread_id = $($root)::+::thread_id();
+        $($root)::+::TIMELOOP_PROFILER.read().stop(thread_id);
+    }};
     () => {
-        unsafe {
-            {
-                let thread_id = crate::thread_id();
-                crate::TIMELOOP_PROFILER.stop(thread_id);
-            }
-        }
+        $crate::stop_thread!(in crate)
     };
 }
 
@@ -253,104 +679,1634 @@ macro_rules! stop_thread {
 #[cfg(feature = "enable")]
 #[allow(clippy::crate_in_macro_def)]
 macro_rules! print {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().print();
+    }};
     () => {
-        unsafe {
-            crate::TIMELOOP_PROFILER.print();
-        }
+        $crate::print!(in crate)
     };
 }
 
 #[macro_export]
 #[cfg(feature = "enable")]
 #[allow(clippy::crate_in_macro_def)]
-macro_rules! print_with_iterations {
-    ($iters:expr) => {
-        unsafe {
-            crate::TIMELOOP_PROFILER.print_with_iterations($iters);
-        }
+macro_rules! print_with_format {
+    (in $($root:ident)::+, $format:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().print_with_format($format);
+    }};
+    ($format:expr) => {
+        $crate::print_with_format!(in crate, $format)
     };
 }
 
 #[macro_export]
 #[cfg(feature = "enable")]
 #[allow(clippy::crate_in_macro_def)]
-macro_rules! scoped_timer {
-    ($timer:expr) => {
-        let _timer = crate::_ScopedTimer::new($timer);
+macro_rules! print_with_renderer {
+    (in $($root:ident)::+, $renderer:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().print_with_renderer(&$renderer);
+    }};
+    ($renderer:expr) => {
+        $crate::print_with_renderer!(in crate, $renderer)
     };
 }
 
 #[macro_export]
 #[cfg(feature = "enable")]
 #[allow(clippy::crate_in_macro_def)]
-macro_rules! scoped_bandwidth_timer {
-    ($timer:expr, $bytes:expr) => {
-        let _timer = crate::_ScopedTimer::new_with_bandwidth($timer, $bytes);
+macro_rules! register_timers {
+    (in $($root:ident)::+, $keys:ty) => {
+        $($root)::+::TIMELOOP_PROFILER.write().register_keys::<$keys>()
+    };
+    ($keys:ty) => {
+        $crate::register_timers!(in crate, $keys)
     };
 }
 
-// Disable feature macros
 #[macro_export]
-#[cfg(not(feature = "enable"))]
-macro_rules! print {
-    () => {};
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! capture_cpu_info {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().capture_cpu_info();
+    }};
+    () => {
+        $crate::capture_cpu_info!(in crate)
+    };
 }
 
 #[macro_export]
-#[cfg(not(feature = "enable"))]
-macro_rules! time_work {
-    ($timer:expr, $work:expr) => {{
-        {
-            let result = $work;
-            result
-        }
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! capture_git_info {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().capture_git_info();
     }};
+    () => {
+        $crate::capture_git_info!(in crate)
+    };
 }
 
 #[macro_export]
-#[cfg(not(feature = "enable"))]
-macro_rules! raw_timer {
-    ($timer:expr) => {};
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! set_color {
+    (in $($root:ident)::+, $mode:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().color = $mode;
+    }};
+    ($mode:expr) => {
+        $crate::set_color!(in crate, $mode)
+    };
 }
 
 #[macro_export]
-#[cfg(not(feature = "enable"))]
-macro_rules! start_profiler {
-    () => {};
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! set_time_unit {
+    (in $($root:ident)::+, $unit:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().time_unit = $unit;
+    }};
+    ($unit:expr) => {
+        $crate::set_time_unit!(in crate, $unit)
+    };
 }
 
 #[macro_export]
-#[cfg(not(feature = "enable"))]
-macro_rules! start_thread {
-    () => {};
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! calibrate_overhead {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().calibrate_overhead();
+    }};
+    () => {
+        $crate::calibrate_overhead!(in crate)
+    };
 }
 
 #[macro_export]
-#[cfg(not(feature = "enable"))]
-macro_rules! stop_thread {
-    () => {};
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! set_min_percent {
+    (in $($root:ident)::+, $percent:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().min_percent = $percent;
+    }};
+    ($percent:expr) => {
+        $crate::set_min_percent!(in crate, $percent)
+    };
 }
 
 #[macro_export]
-#[cfg(not(feature = "enable"))]
-macro_rules! create_profiler {
-    () => {};
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_to {
+    (in $($root:ident)::+, $out:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.write().print_to($out)
+    };
+    ($out:expr) => {
+        $crate::print_to!(in crate, $out)
+    };
 }
 
 #[macro_export]
-#[cfg(not(feature = "enable"))]
-macro_rules! scoped_timer {
-    ($timer:expr) => {};
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! reset {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().reset();
+    }};
+    () => {
+        $crate::reset!(in crate)
+    };
 }
 
 #[macro_export]
-#[cfg(not(feature = "enable"))]
-macro_rules! print_with_iterations {
-    ($iters:expr) => {};
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! report {
+    (in $($root:ident)::+) => {
+        $($root)::+::TIMELOOP_PROFILER.write().report()
+    };
+    () => {
+        $crate::report!(in crate)
+    };
 }
 
 #[macro_export]
-#[cfg(not(feature = "enable"))]
-macro_rules! scoped_bandwidth_timer {
-    ($timer:expr, $bytes:expr) => {};
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! report_string {
+    (in $($root:ident)::+) => {
+        $($root)::+::TIMELOOP_PROFILER.write().report_string()
+    };
+    () => {
+        $crate::report_string!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! record_stacks {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().record_stacks = true;
+    }};
+    () => {
+        $crate::record_stacks!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_tree {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().print_tree();
+    }};
+    () => {
+        $crate::print_tree!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_filtered {
+    (in $($root:ident)::+, $pattern:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.write().print_filtered($pattern)
+    };
+    ($pattern:expr) => {
+        $crate::print_filtered!(in crate, $pattern)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_top {
+    (in $($root:ident)::+, $n:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().print_top($n);
+    }};
+    ($n:expr) => {
+        $crate::print_top!(in crate, $n)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! set_window {
+    (in $($root:ident)::+, $window:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().set_window($window);
+    }};
+    ($window:expr) => {
+        $crate::set_window!(in crate, $window)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! tick_window {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.read().tick_window();
+    }};
+    () => {
+        $crate::tick_window!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_window {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().print_window();
+    }};
+    () => {
+        $crate::print_window!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_per_thread {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().print_per_thread();
+    }};
+    () => {
+        $crate::print_per_thread!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_per_item {
+    (in $($root:ident)::+, $items:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().print_per_item($items);
+    }};
+    ($items:expr) => {
+        $crate::print_per_item!(in crate, $items)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_with_iterations {
+    (in $($root:ident)::+, $iters:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().print_per_item($iters);
+    }};
+    ($iters:expr) => {
+        $crate::print_with_iterations!(in crate, $iters)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_coverage {
+    (in $($root:ident)::+, $functions:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().print_coverage($functions);
+    }};
+    ($functions:expr) => {
+        $crate::print_coverage!(in crate, $functions)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! dry_run {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().dry_run = true;
+    }};
+    () => {
+        $crate::dry_run!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_call_tree {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.read().print_call_tree();
+    }};
+    () => {
+        $crate::print_call_tree!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! record_events {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().record_events = true;
+    }};
+    () => {
+        $crate::record_events!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! mark {
+    (in $($root:ident)::+, $name:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.read().mark($($root)::+::thread_id(), $name)
+    }};
+    ($name:expr) => {
+        $crate::mark!(in crate, $name)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! coalesce_events {
+    (in $($root:ident)::+, $duration:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().set_min_event_duration($duration);
+    }};
+    ($duration:expr) => {
+        $crate::coalesce_events!(in crate, $duration)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! record_page_faults {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().record_page_faults = true;
+    }};
+    () => {
+        $crate::record_page_faults!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! record_context_switches {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().record_context_switches = true;
+    }};
+    () => {
+        $crate::record_context_switches!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! record_log2_histograms {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().record_log2_histograms = true;
+    }};
+    () => {
+        $crate::record_log2_histograms!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_log2_histograms {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.read().print_log2_histograms();
+    }};
+    () => {
+        $crate::print_log2_histograms!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! record_percentiles {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().record_percentiles = true;
+    }};
+    () => {
+        $crate::record_percentiles!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! record_callers {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().record_callers = true;
+    }};
+    () => {
+        $crate::record_callers!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! define_chain {
+    (in $($root:ident)::+, $name:expr, [$($stage:expr),* $(,)?]) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().define_chain($name, &[$($stage),*]);
+    }};
+    ($name:expr, [$($stage:expr),* $(,)?]) => {
+        $crate::define_chain!(in crate, $name, [$($stage),*])
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! finish_token {
+    (in $($root:ident)::+, $token:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.read().finish_token($($root)::+::thread_id(), $token);
+    }};
+    ($token:expr) => {
+        $crate::finish_token!(in crate, $token)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_chains {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.read().print_chains();
+    }};
+    () => {
+        $crate::print_chains!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! record_exemplars {
+    (in $($root:ident)::+) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().record_exemplars = true;
+    }};
+    () => {
+        $crate::record_exemplars!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! write_chrome_trace {
+    (in $($root:ident)::+, $path:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.read().write_chrome_trace($path)
+    };
+    ($path:expr) => {
+        $crate::write_chrome_trace!(in crate, $path)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! record_series {
+    (in $($root:ident)::+, $window:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.write().record_series($window);
+    }};
+    ($window:expr) => {
+        $crate::record_series!(in crate, $window)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! write_series_csv {
+    (in $($root:ident)::+, $out:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.read().write_series_csv($out)
+    };
+    ($out:expr) => {
+        $crate::write_series_csv!(in crate, $out)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! write_folded_stacks {
+    (in $($root:ident)::+, $out:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.read().write_folded_stacks($out)
+    };
+    ($out:expr) => {
+        $crate::write_folded_stacks!(in crate, $out)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! write_flamechart {
+    (in $($root:ident)::+, $path:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.read().write_flamechart($path)
+    };
+    ($path:expr) => {
+        $crate::write_flamechart!(in crate, $path)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! write_speedscope {
+    (in $($root:ident)::+, $path:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.read().write_speedscope($path)
+    };
+    ($path:expr) => {
+        $crate::write_speedscope!(in crate, $path)
+    };
+}
+
+#[macro_export]
+#[cfg(all(feature = "enable", feature = "otel"))]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! export_otlp {
+    (in $($root:ident)::+, $meter:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.write().export_otlp($meter)
+    };
+    ($meter:expr) => {
+        $crate::export_otlp!(in crate, $meter)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! spawn {
+    (in $($root:ident)::+, $name:literal, $func:expr) => {
+        $($root)::+::_spawn(
+            $name,
+            concat!($name, " (spawn)"),
+            concat!($name, " (join)"),
+            $func,
+        )
+    };
+    ($name:literal, $func:expr) => {
+        $crate::spawn!(in crate, $name, $func)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! write_html_report {
+    (in $($root:ident)::+, $path:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.write().write_html_report($path)
+    };
+    ($path:expr) => {
+        $crate::write_html_report!(in crate, $path)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! snapshot {
+    (in $($root:ident)::+) => {
+        $($root)::+::TIMELOOP_PROFILER.read().snapshot()
+    };
+    () => {
+        $crate::snapshot!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! since {
+    (in $($root:ident)::+, $snapshot:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.read().since($snapshot)
+    };
+    ($snapshot:expr) => {
+        $crate::since!(in crate, $snapshot)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! save {
+    (in $($root:ident)::+, $path:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.read().save($path)
+    };
+    ($path:expr) => {
+        $crate::save!(in crate, $path)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_every {
+    (in $($root:ident)::+, $interval:expr, path = $path:expr) => {
+        $crate::spawn_print_every($interval, Some($path.into()), &$($root)::+::TIMELOOP_PROFILER)
+    };
+    (in $($root:ident)::+, $interval:expr) => {
+        $crate::spawn_print_every($interval, None, &$($root)::+::TIMELOOP_PROFILER)
+    };
+    ($interval:expr, path = $path:expr) => {
+        $crate::print_every!(in crate, $interval, path = $path)
+    };
+    ($interval:expr) => {
+        $crate::print_every!(in crate, $interval)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! install_panic_dump {
+    (in $($root:ident)::+) => {
+        $crate::install_panic_dump(&$($root)::+::TIMELOOP_PROFILER)
+    };
+    () => {
+        $crate::install_panic_dump!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! install_signal_dump {
+    (in $($root:ident)::+, path = $path:expr) => {
+        $crate::install_signal_dump(Some($path.into()), &$($root)::+::TIMELOOP_PROFILER)
+    };
+    (in $($root:ident)::+) => {
+        $crate::install_signal_dump(None, &$($root)::+::TIMELOOP_PROFILER)
+    };
+    (path = $path:expr) => {
+        $crate::install_signal_dump!(in crate, path = $path)
+    };
+    () => {
+        $crate::install_signal_dump!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! persist {
+    (in $($root:ident)::+, $path:expr, $interval:expr) => {
+        $crate::spawn_persist($path, $interval, &$($root)::+::TIMELOOP_PROFILER)
+    };
+    ($path:expr, $interval:expr) => {
+        $crate::persist!(in crate, $path, $interval)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_vs_baseline {
+    (in $($root:ident)::+, $path:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.write().print_vs_baseline($path)
+    };
+    ($path:expr) => {
+        $crate::print_vs_baseline!(in crate, $path)
+    };
+}
+
+#[macro_export]
+#[cfg(all(feature = "enable", feature = "hist"))]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! write_hgrm {
+    (in $($root:ident)::+, $timer:expr, $path:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.read().write_hgrm($timer, $path)
+    };
+    ($timer:expr, $path:expr) => {
+        $crate::write_hgrm!(in crate, $timer, $path)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! prometheus_text {
+    (in $($root:ident)::+) => {
+        $($root)::+::TIMELOOP_PROFILER.read().prometheus_text()
+    };
+    () => {
+        $crate::prometheus_text!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(all(feature = "enable", feature = "prometheus-http"))]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! serve_prometheus {
+    (in $($root:ident)::+, $addr:expr) => {
+        $crate::serve_prometheus($addr, || {
+            $($root)::+::TIMELOOP_PROFILER.snapshot().prometheus_text()
+        })
+    };
+    ($addr:expr) => {
+        $crate::serve_prometheus!(in crate, $addr)
+    };
+}
+
+/// Time `$work_a` or `$work_b`, depending on `$condition`, as separate variants of the
+/// `$timer` timer to be compared with `print_comparisons!`
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! compare_paths {
+    (in $($root:ident)::+, $timer:literal, $condition:expr, $a:literal => $work_a:expr, $b:literal => $work_b:expr $(,)?) => {{
+        const TIMERS: [&str; 2] = [
+            concat!($timer, " [", $a, "]"),
+            concat!($timer, " [", $b, "]"),
+        ];
+
+        static REGISTER: std::sync::Once = std::sync::Once::new();
+        REGISTER.call_once(|| {
+            $($root)::+::TIMELOOP_PROFILER.write().register_comparison($timer, [$a, $b], TIMERS);
+        });
+
+        if $condition {
+            $crate::time_work!(in $($root)::+, TIMERS[0], $work_a)
+        } else {
+            $crate::time_work!(in $($root)::+, TIMERS[1], $work_b)
+        }
+    }};
+    ($timer:literal, $condition:expr, $a:literal => $work_a:expr, $b:literal => $work_b:expr $(,)?) => {
+        $crate::compare_paths!(in crate, $timer, $condition, $a => $work_a, $b => $work_b)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_comparisons {
+    (in $($root:ident)::+) => {
+        $($root)::+::TIMELOOP_PROFILER.write().print_comparisons()
+    };
+    () => {
+        $crate::print_comparisons!(in crate)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! pin_timer {
+    (in $($root:ident)::+, $timer:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.pin_timer($timer)
+    };
+    ($timer:expr) => {
+        $crate::pin_timer!(in crate, $timer)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! sketch_timer {
+    (in $($root:ident)::+, $timer:expr) => {
+        $($root)::+::TIMELOOP_PROFILER.write().enable_sketch($timer)
+    };
+    ($timer:expr) => {
+        $crate::sketch_timer!(in crate, $timer)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_quantiles {
+    (in $($root:ident)::+) => {
+        $($root)::+::TIMELOOP_PROFILER.write().print_quantiles()
+    };
+    () => {
+        $crate::print_quantiles!(in crate)
+    };
+}
+
+/// Time a sequence of adjacent stages, where each stage's timer is stopped with the
+/// same timestamp that starts the next one
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! chain_timers {
+    (in $($root:ident)::+, $($timer:expr => $stage:stmt;)+) => {
+        let next_timer = $($root)::+::_ScopedTimer::chain;
+        let mut chain = None;
+        $(
+            chain = Some(next_timer(chain.take(), $timer));
+            $stage;
+        )+
+        drop(chain);
+    };
+    ($($timer:expr => $stage:stmt;)+) => {
+        $crate::chain_timers!(in crate, $($timer => $stage;)+);
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! scoped_timer {
+    (in $($root:ident)::+, $timer:expr, category = $category:expr) => {
+        let _timer = $($root)::+::_ScopedTimer::new_in_category($timer, $category);
+    };
+    (in $($root:ident)::+, $timer:expr, pinned) => {
+        let _timer = $($root)::+::_ScopedTimer::new_pinned($timer);
+    };
+    (in $($root:ident)::+, $timer:expr) => {
+        let _timer = $($root)::+::_ScopedTimer::new($timer);
+    };
+    ($timer:expr, category = $category:expr) => {
+        $crate::scoped_timer!(in crate, $timer, category = $category);
+    };
+    ($timer:expr, pinned) => {
+        $crate::scoped_timer!(in crate, $timer, pinned);
+    };
+    ($timer:expr) => {
+        $crate::scoped_timer!(in crate, $timer);
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! begin_span {
+    (in $($root:ident)::+, $timer:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.begin($($root)::+::thread_id(), $timer)
+    }};
+    ($timer:expr) => {
+        $crate::begin_span!(in crate, $timer)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! end_span {
+    (in $($root:ident)::+, $span:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.read().end($($root)::+::thread_id(), $span)
+    }};
+    ($span:expr) => {
+        $crate::end_span!(in crate, $span)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! count {
+    (in $($root:ident)::+, $counter:expr, $value:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.read().count(
+            $($root)::+::thread_id(),
+            $counter,
+            $value,
+        )
+    }};
+    ($counter:expr, $value:expr) => {
+        $crate::count!(in crate, $counter, $value)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! counter {
+    (in $($root:ident)::+, $name:expr, $value:expr) => {{
+        $($root)::+::TIMELOOP_PROFILER.read().add_counter(
+            $($root)::+::thread_id(),
+            $name,
+            $value,
+        )
+    }};
+    (in $($root:ident)::+, $name:expr) => {
+        $crate::counter!(in $($root)::+, $name, 1)
+    };
+    ($name:expr, $value:expr) => {
+        $crate::counter!(in crate, $name, $value)
+    };
+    ($name:expr) => {
+        $crate::counter!(in crate, $name, 1)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! gauge {
+    (in $($root:ident)::+, $name:expr, $value:expr) => {{
+        #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+        let value = $value as f64;
+
+        $($root)::+::TIMELOOP_PROFILER.read().set_gauge($($root)::+::thread_id(), $name, value)
+    }};
+    ($name:expr, $value:expr) => {
+        $crate::gauge!(in crate, $name, $value)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! timer_guard {
+    (in $($root:ident)::+, $timer:expr) => {
+        $($root)::+::TimerGuard::new($timer)
+    };
+    ($timer:expr) => {
+        $crate::timer_guard!(in crate, $timer)
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! scoped_dynamic_timer {
+    (in $($root:ident)::+, $name:expr) => {
+        let _timer = $($root)::+::_ScopedTimer::new($crate::intern(&$name));
+    };
+    ($name:expr) => {
+        $crate::scoped_dynamic_timer!(in crate, $name);
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! scoped_bandwidth_timer {
+    (in $($root:ident)::+, $timer:expr, $bytes:expr) => {
+        let _timer = $($root)::+::_ScopedTimer::new_with_bandwidth($timer, $bytes);
+    };
+    ($timer:expr, $bytes:expr) => {
+        $crate::scoped_bandwidth_timer!(in crate, $timer, $bytes);
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! scoped_timer_if {
+    (in $($root:ident)::+, $cond:expr, $timer:expr) => {
+        let _timer = if $cond {
+            Some($($root)::+::_ScopedTimer::new($timer))
+        } else {
+            None
+        };
+    };
+    ($cond:expr, $timer:expr) => {
+        $crate::scoped_timer_if!(in crate, $cond, $timer);
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! scoped_timer_sampled {
+    (in $($root:ident)::+, $timer:expr, $rate:expr) => {
+        let _timer = {
+            std::thread_local! {
+                static CALLS: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+            }
+
+            $($root)::+::_ScopedTimer::new_sampled($timer, $rate, &CALLS)
+        };
+    };
+    ($timer:expr, $rate:expr) => {
+        $crate::scoped_timer_sampled!(in crate, $timer, $rate);
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "enable")]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! scoped_timer_here {
+    (in $($root:ident)::+) => {
+        let _timer = $($root)::+::_ScopedTimer::new(concat!(file!(), ":", line!()));
+    };
+    () => {
+        $crate::scoped_timer_here!(in crate);
+    };
+}
+
+// Disable feature macros
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! time_work {
+    ($(in $($root:ident)::+,)? $timer:expr, category = $category:expr, $work:expr) => {{
+        {
+            let result = $work;
+            result
+        }
+    }};
+    ($(in $($root:ident)::+,)? $timer:expr, $work:expr) => {{
+        {
+            let result = $work;
+            result
+        }
+    }};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! time_work_with_bandwidth {
+    ($(in $($root:ident)::+,)? $timer:expr, $bytes:expr, $work:expr) => {{
+        {
+            let result = $work;
+            result
+        }
+    }};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! time_iter {
+    ($(in $($root:ident)::+,)? $timer:expr, $iter:expr) => {
+        $iter
+    };
+}
+
+/// Call the closure `$func`, returning its result
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! time_work_fn {
+    ($(in $($root:ident)::+,)? $timer:expr, $func:expr) => {{
+        ($func)()
+    }};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! raw_timer {
+    ($(in $($root:ident)::+,)? $timer:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! start_profiler {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! pause_profiler {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! resume_profiler {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! start_thread {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! stop_thread {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! create_profiler {
+    ($($options:tt)*) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! scoped_timer {
+    ($(in $($root:ident)::+,)? $timer:expr $(, category = $category:expr)? $(, pinned)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_coverage {
+    ($(in $($root:ident)::+,)? $functions:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! record_events {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! mark {
+    ($(in $($root:ident)::+,)? $name:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! coalesce_events {
+    ($(in $($root:ident)::+,)? $duration:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! record_exemplars {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! write_chrome_trace {
+    ($(in $($root:ident)::+,)? $path:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! record_series {
+    ($(in $($root:ident)::+,)? $window:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! write_series_csv {
+    ($(in $($root:ident)::+,)? $out:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! write_folded_stacks {
+    ($(in $($root:ident)::+,)? $out:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! write_flamechart {
+    ($(in $($root:ident)::+,)? $path:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! write_speedscope {
+    ($(in $($root:ident)::+,)? $path:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! spawn {
+    ($(in $($root:ident)::+,)? $name:literal, $func:expr) => {
+        std::thread::spawn($func)
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_with_format {
+    ($(in $($root:ident)::+,)? $format:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_with_renderer {
+    ($(in $($root:ident)::+,)? $renderer:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! register_timers {
+    ($(in $($root:ident)::+,)? $keys:ty) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! capture_cpu_info {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! capture_git_info {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! set_color {
+    ($(in $($root:ident)::+,)? $mode:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! set_time_unit {
+    ($(in $($root:ident)::+,)? $unit:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! calibrate_overhead {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! set_min_percent {
+    ($(in $($root:ident)::+,)? $percent:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_to {
+    ($(in $($root:ident)::+,)? $out:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! reset {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! record_page_faults {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! record_context_switches {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! record_log2_histograms {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_log2_histograms {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! record_percentiles {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! record_callers {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! record_stacks {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_tree {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! define_chain {
+    ($(in $($root:ident)::+,)? $name:expr, [$($stage:expr),* $(,)?]) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! finish_token {
+    ($(in $($root:ident)::+,)? $token:expr) => {
+        let _ = $token;
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_chains {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! dry_run {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_call_tree {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! report {
+    ($(in $($root:ident)::+)?) => {
+        Vec::<$crate::TimerReport>::new()
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! report_string {
+    ($(in $($root:ident)::+)?) => {
+        String::new()
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! write_html_report {
+    ($(in $($root:ident)::+,)? $path:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_per_item {
+    ($(in $($root:ident)::+,)? $items:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_filtered {
+    ($(in $($root:ident)::+,)? $pattern:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_top {
+    ($(in $($root:ident)::+,)? $n:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! set_window {
+    ($(in $($root:ident)::+,)? $window:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! tick_window {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_window {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_per_thread {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! snapshot {
+    ($(in $($root:ident)::+)?) => {
+        $crate::ProfilerSnapshot::default()
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! since {
+    ($(in $($root:ident)::+,)? $snapshot:expr) => {
+        $crate::Report::default()
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! save {
+    ($(in $($root:ident)::+,)? $path:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_every {
+    ($(in $($root:ident)::+,)? $interval:expr $(, path = $path:expr)?) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! install_panic_dump {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! install_signal_dump {
+    ($(in $($root:ident)::+)? $(,)? $(path = $path:expr)?) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! persist {
+    ($(in $($root:ident)::+,)? $path:expr, $interval:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_vs_baseline {
+    ($(in $($root:ident)::+,)? $path:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! write_hgrm {
+    ($(in $($root:ident)::+,)? $timer:expr, $path:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! prometheus_text {
+    ($(in $($root:ident)::+)?) => {
+        String::new()
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! serve_prometheus {
+    ($(in $($root:ident)::+,)? $addr:expr) => {
+        std::io::Result::Ok(())
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! pin_timer {
+    ($(in $($root:ident)::+,)? $timer:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! sketch_timer {
+    ($(in $($root:ident)::+,)? $timer:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_quantiles {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! compare_paths {
+    ($(in $($root:ident)::+,)? $timer:literal, $condition:expr, $a:literal => $work_a:expr, $b:literal => $work_b:expr $(,)?) => {
+        if $condition {
+            $work_a
+        } else {
+            $work_b
+        }
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_comparisons {
+    ($(in $($root:ident)::+)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! chain_timers {
+    ($(in $($root:ident)::+,)? $($timer:expr => $stage:stmt;)+) => {
+        $($stage;)+
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! export_otlp {
+    ($(in $($root:ident)::+,)? $meter:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! print_with_iterations {
+    ($(in $($root:ident)::+,)? $iters:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! begin_span {
+    ($(in $($root:ident)::+,)? $timer:expr) => {
+        $crate::SpanId::default()
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! end_span {
+    ($(in $($root:ident)::+,)? $span:expr) => {{
+        let _ = $span;
+        true
+    }};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! count {
+    ($(in $($root:ident)::+,)? $counter:expr, $value:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! counter {
+    ($(in $($root:ident)::+,)? $name:expr $(, $value:expr)?) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! gauge {
+    ($(in $($root:ident)::+,)? $name:expr, $value:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! timer_guard {
+    ($(in $($root:ident)::+,)? $timer:expr) => {
+        $crate::DisabledTimerGuard
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! scoped_dynamic_timer {
+    ($(in $($root:ident)::+,)? $name:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! scoped_bandwidth_timer {
+    ($(in $($root:ident)::+,)? $timer:expr, $bytes:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! scoped_timer_if {
+    // The condition is still evaluated for its side effects, as in the enabled build
+    ($(in $($root:ident)::+,)? $cond:expr, $timer:expr) => {
+        let _ = $cond;
+    };
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! scoped_timer_sampled {
+    ($(in $($root:ident)::+,)? $timer:expr, $rate:expr) => {};
+}
+
+#[macro_export]
+#[cfg(not(feature = "enable"))]
+macro_rules! scoped_timer_here {
+    ($(in $($root:ident)::+)?) => {};
 }
@@ -0,0 +1,99 @@
+//! Implements combining the timers of two profilers into one
+use std::collections::BTreeMap;
+
+use crate::{Profiler, ThreadProfile};
+
+/// Add every value of `from` into the entry of the same key in `into` using `add`
+fn merge_map<K: Ord + Clone, V: Clone + Default>(
+    into: &mut BTreeMap<K, V>,
+    from: &BTreeMap<K, V>,
+    add: impl Fn(&mut V, &V),
+) {
+    for (key, value) in from {
+        add(into.entry(key.clone()).or_default(), value);
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Add the timers of `other` into this profiler, matching the timers by name so that
+    /// differing index assignments don't matter, such as to combine the profiles of
+    /// several processes or profiler instances into one report
+    ///
+    /// Each thread of `other` is added to the thread at the same index, with the threads
+    /// that don't fit added to the last thread. Besides the timers and the time of the
+    /// threads, the categories, caller edges, timer stacks, user counters, page faults,
+    /// context switches, allocations and power-of-two histograms are merged. Sampled
+    /// data such as events, exemplars and percentiles is not.
+    pub fn merge<const OTHER_THREADS: usize, const OTHER_TIMERS: usize>(
+        &mut self,
+        other: &Profiler<OTHER_THREADS, OTHER_TIMERS>,
+    ) {
+        // Resolve the indexes of the timers of `other` in this profiler
+        let indexes: Vec<usize> = other.timer_names[..other.next_index as usize]
+            .iter()
+            .map(|name| self.get_timer_index(name))
+            .collect();
+
+        for (thread_id, other_thread) in other.thread_profiles().enumerate() {
+            if !other_thread.is_used() {
+                continue;
+            }
+
+            let thread = self.thread_mut(thread_id.min(THREADS - 1));
+
+            // Add the time of the other thread, up to now if it is still running
+            thread.time = thread.time.wrapping_add(other_thread.elapsed());
+
+            if thread.name.is_none() {
+                thread.name = other_thread.name;
+            }
+
+            for (index, timer) in indexes.iter().zip(&other_thread.timers) {
+                thread.timers[*index].merge(timer);
+            }
+
+            thread.merge_maps(&other_thread);
+        }
+
+        for (timer, category) in &other.categories {
+            self.categories.entry(timer).or_insert(category);
+        }
+    }
+}
+
+impl<const TIMERS: usize> ThreadProfile<TIMERS> {
+    /// Merge the per-timer data of the `other` thread into this thread
+    fn merge_maps<const OTHER_TIMERS: usize>(&mut self, other: &ThreadProfile<OTHER_TIMERS>) {
+        merge_map(
+            &mut self.caller_edges,
+            &other.caller_edges,
+            |edge, other| {
+                edge.caller = other.caller;
+                edge.hits += other.hits;
+                edge.cycles = edge.cycles.wrapping_add(other.cycles);
+            },
+        );
+
+        let add = |total: &mut u64, value: &u64| *total = total.wrapping_add(*value);
+        merge_map(&mut self.stack_times, &other.stack_times, add);
+        merge_map(&mut self.call_counts, &other.call_counts, add);
+        merge_map(&mut self.user_counters, &other.user_counters, add);
+        merge_map(&mut self.page_faults, &other.page_faults, add);
+
+        merge_map(
+            &mut self.context_switches,
+            &other.context_switches,
+            |switches, other| switches.add(*other),
+        );
+        merge_map(
+            &mut self.allocations,
+            &other.allocations,
+            |allocations, other| allocations.add(*other),
+        );
+        merge_map(
+            &mut self.log2_histograms,
+            &other.log2_histograms,
+            crate::Log2Histogram::merge,
+        );
+    }
+}
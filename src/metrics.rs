@@ -0,0 +1,154 @@
+//! Implements the counters and gauges published alongside the timers
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::{is_enabled, rdtsc, Profiler};
+
+/// The values a gauge was set to
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Gauge {
+    /// The value the gauge was last set to
+    pub last: f64,
+
+    /// The smallest value the gauge was set to
+    pub min: f64,
+
+    /// The largest value the gauge was set to
+    pub max: f64,
+
+    /// The number of times the gauge was set
+    pub updates: u64,
+
+    /// The timestamp of the last update, to find the last value across threads
+    updated_at: u64,
+}
+
+impl Gauge {
+    /// Set the gauge to `value` at `timestamp`
+    fn set(&mut self, value: f64, timestamp: u64) {
+        if self.updates == 0 {
+            self.min = value;
+            self.max = value;
+        }
+
+        self.last = value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.updates += 1;
+        self.updated_at = timestamp;
+    }
+
+    /// Fold the values of `other` into this gauge, keeping the latest value of the two
+    fn merge(&mut self, other: &Gauge) {
+        if other.updates == 0 {
+            return;
+        }
+
+        if self.updates == 0 {
+            *self = *other;
+            return;
+        }
+
+        if other.updated_at > self.updated_at {
+            self.last = other.last;
+            self.updated_at = other.updated_at;
+        }
+
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.updates += other.updates;
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Add `value` to the counter `name` on the given thread, such as the number of
+    /// events handled
+    ///
+    /// Unlike [`Profiler::count`], the counter is not attached to any timer.
+    pub fn add_counter(&self, thread_id: usize, name: &'static str, value: u64) {
+        if !is_enabled() {
+            return;
+        }
+
+        let mut thread = self.thread(thread_id);
+        let counter = thread.metric_counters.entry(name).or_default();
+        *counter = counter.wrapping_add(value);
+    }
+
+    /// Set the gauge `name` to `value` on the given thread, such as the depth of a queue
+    pub fn set_gauge(&self, thread_id: usize, name: &'static str, value: f64) {
+        if !is_enabled() {
+            return;
+        }
+
+        self.thread(thread_id)
+            .gauges
+            .entry(name)
+            .or_default()
+            .set(value, rdtsc());
+    }
+
+    /// Get the total of the counter `name` over every thread
+    #[must_use]
+    pub fn counter_total(&self, name: &str) -> u64 {
+        self.thread_profiles()
+            .filter_map(|thread| thread.metric_counters.get(name).copied())
+            .fold(0, u64::wrapping_add)
+    }
+
+    /// Get the values of the gauge `name` over every thread, if it was ever set
+    #[must_use]
+    pub fn gauge(&self, name: &str) -> Option<Gauge> {
+        self.thread_profiles()
+            .filter_map(|thread| thread.gauges.get(name).copied())
+            .fold(None, |total: Option<Gauge>, gauge| {
+                let mut total = total.unwrap_or_default();
+                total.merge(&gauge);
+                Some(total)
+            })
+    }
+
+    /// Write the counters and the gauges, sorted by name. Nothing is written if none were
+    /// used.
+    pub(crate) fn write_metrics(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let mut counters: BTreeMap<&'static str, u64> = BTreeMap::new();
+        let mut gauges: BTreeMap<&'static str, Gauge> = BTreeMap::new();
+        for thread in self.thread_profiles() {
+            for (name, value) in &thread.metric_counters {
+                let total = counters.entry(name).or_default();
+                *total = total.wrapping_add(*value);
+            }
+
+            for (name, gauge) in &thread.gauges {
+                gauges.entry(name).or_default().merge(gauge);
+            }
+        }
+
+        let name_width = counters
+            .keys()
+            .chain(gauges.keys())
+            .map(|name| name.len())
+            .max()
+            .unwrap_or(0);
+
+        if !counters.is_empty() {
+            writeln!(out, "Counters:")?;
+            for (name, value) in &counters {
+                writeln!(out, "  {name:name_width$} | {value}")?;
+            }
+        }
+
+        if !gauges.is_empty() {
+            writeln!(out, "Gauges:")?;
+            for (name, gauge) in &gauges {
+                writeln!(
+                    out,
+                    "  {name:name_width$} | last {} | min {} | max {} | {} updates",
+                    gauge.last, gauge.min, gauge.max, gauge.updates
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
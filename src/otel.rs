@@ -0,0 +1,88 @@
+//! Implements the bridge of the profiler state into OpenTelemetry metrics
+use opentelemetry::metrics::Meter;
+use opentelemetry::KeyValue;
+
+use crate::{calibration, timer_hash, Profiler};
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Record the change of every timer since the last export into instruments of `meter`
+    ///
+    /// Each timer is exported with a `timer` attribute as:
+    ///
+    /// * `timeloop.timer.hits`: counter of the times the timer was hit
+    /// * `timeloop.timer.exclusive_cycles`: counter of cycles spent without child timers
+    /// * `timeloop.timer.bytes`: counter of bytes processed
+    /// * `timeloop.timer.duration`: histogram of the average time per hit (with child
+    ///   timers) in seconds since the last export
+    ///
+    /// The calibrated frequency of the clock is recorded in the `timeloop.clock.frequency`
+    /// gauge.
+    ///
+    /// Running threads are not stopped, so this can be called periodically from
+    /// long-running services.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn export_otlp(&mut self, meter: &Meter) {
+        let calibration = calibration();
+        let os_timer_freq = calibration.frequency;
+
+        meter
+            .f64_gauge("timeloop.clock.frequency")
+            .with_description("Calibrated frequency of the clock")
+            .with_unit("Hz")
+            .build()
+            .record(
+                os_timer_freq,
+                &[KeyValue::new("source", calibration.source.name())],
+            );
+
+        let hits_counter = meter
+            .u64_counter("timeloop.timer.hits")
+            .with_description("Number of times the timer was hit")
+            .build();
+        let cycles_counter = meter
+            .u64_counter("timeloop.timer.exclusive_cycles")
+            .with_description("Cycles spent in the timer without child timers")
+            .build();
+        let bytes_counter = meter
+            .u64_counter("timeloop.timer.bytes")
+            .with_description("Bytes processed by the timer")
+            .with_unit("By")
+            .build();
+        let duration_histogram = meter
+            .f64_histogram("timeloop.timer.duration")
+            .with_description("Average time per hit of the timer including child timers")
+            .with_unit("s")
+            .build();
+
+        let (acc, _) = self.accumulate_timers();
+
+        for (index, timer) in acc.iter().enumerate().take(self.next_index as usize) {
+            let previous = self.otel_exported[index];
+            self.otel_exported[index] = *timer;
+
+            let hits = timer.hits.wrapping_sub(previous.hits);
+            if hits == 0 {
+                continue;
+            }
+
+            let exclusive_time = timer.exclusive_time.wrapping_sub(previous.exclusive_time);
+            let inclusive_time = timer.inclusive_time.wrapping_sub(previous.inclusive_time);
+            let bytes_processed = timer.bytes_processed.wrapping_sub(previous.bytes_processed);
+
+            let timer_name = self.timer_names[index];
+            let attributes = [
+                KeyValue::new("timer", timer_name),
+                KeyValue::new("timer.hash", format!("{:016x}", timer_hash(timer_name))),
+            ];
+
+            hits_counter.add(hits, &attributes);
+            cycles_counter.add(exclusive_time, &attributes);
+            if bytes_processed > 0 {
+                bytes_counter.add(bytes_processed, &attributes);
+            }
+
+            let secs_per_hit = inclusive_time as f64 / os_timer_freq / hits as f64;
+            duration_histogram.record(secs_per_hit, &attributes);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Implements measuring the overhead of the timers on the current machine
+use std::hint::black_box;
+
+use crate::{calculate_os_frequency, rdtsc, Profiler, Report, TimerReport};
+
+/// Number of measured iterations for each timer kind
+const ITERATIONS: u64 = 100_000;
+
+/// Label of the report row of the time subtracted from the timers as profiler overhead
+pub(crate) const OVERHEAD_LABEL: &str = "Profiler overhead";
+
+/// The measured cost (in cycles) of a single hit of each kind of timer
+#[derive(Debug, Copy, Clone, Default)]
+pub struct OverheadReport {
+    /// The OS frequency used to convert cycles into time
+    pub os_timer_freq: f64,
+
+    /// Reading a single timestamp
+    pub timestamp: f64,
+
+    /// Entering and exiting a `scoped_timer!`
+    pub scoped_timer: f64,
+
+    /// Entering and exiting a `scoped_bandwidth_timer!`
+    pub bandwidth_timer: f64,
+}
+
+impl OverheadReport {
+    /// Print the overhead of each timer kind
+    pub fn print(&self) {
+        let ns_per_cycle = 1_000_000_000.0 / self.os_timer_freq;
+
+        eprintln!("Timer overhead per hit ({ITERATIONS} iterations)");
+        for (name, cycles) in [
+            ("timestamp", self.timestamp),
+            ("scoped_timer", self.scoped_timer),
+            ("bandwidth_timer", self.bandwidth_timer),
+        ] {
+            eprintln!(
+                "{name:<16} | {cycles:10.2} cycles | {:8.2} ns",
+                cycles * ns_per_cycle
+            );
+        }
+    }
+}
+
+/// Measure the overhead of each kind of timer on the current machine, to help choose how
+/// densely to instrument hot code
+#[must_use]
+pub fn overhead_report() -> OverheadReport {
+    // A private profiler so the measurement doesn't pollute the global profile
+    let mut profiler = Box::new(Profiler::<1>::new());
+
+    let timestamp = measure(|| {
+        black_box(rdtsc());
+    });
+
+    let scoped_timer = measure(|| {
+        let open = profiler.enter_timer(0, "overhead", 0);
+        profiler.exit_timer(0, &open, rdtsc());
+    });
+
+    let bandwidth_timer = measure(|| {
+        let open = profiler.enter_timer(0, "overhead_bandwidth", black_box(64));
+        profiler.exit_timer(0, &open, rdtsc());
+    });
+
+    OverheadReport {
+        os_timer_freq: calculate_os_frequency(),
+        timestamp,
+        scoped_timer,
+        bandwidth_timer,
+    }
+}
+
+/// Get the average number of cycles of a single call of `func`
+#[allow(clippy::cast_precision_loss)]
+fn measure(mut func: impl FnMut()) -> f64 {
+    // Warm up the caches and branch predictors
+    for _ in 0..ITERATIONS / 10 {
+        func();
+    }
+
+    let start = rdtsc();
+    for _ in 0..ITERATIONS {
+        func();
+    }
+    let elapsed = rdtsc() - start;
+
+    elapsed as f64 / ITERATIONS as f64
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Measure the cost of an empty `scoped_timer!` on the current machine and subtract
+    /// it from every hit of the timers in the following reports, see
+    /// [`Profiler::overhead_cycles`]
+    pub fn calibrate_overhead(&mut self) {
+        self.overhead_cycles = overhead_report().scoped_timer;
+    }
+}
+
+impl Report {
+    /// Subtract `cycles_per_hit` of profiler overhead from the exclusive time of every hit
+    /// of the timers, moving the subtracted time into a separate row
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub(crate) fn subtract_overhead(&mut self, cycles_per_hit: f64) {
+        if cycles_per_hit <= 0. {
+            return;
+        }
+
+        let total_time = self.total_time.max(1) as f64;
+        let mut overhead = TimerReport {
+            name: OVERHEAD_LABEL.to_string(),
+            ..TimerReport::default()
+        };
+
+        for timer in &mut self.timers {
+            // Never subtract more than the measured time of the timer
+            let subtracted =
+                ((timer.hits as f64 * cycles_per_hit) as u64).min(timer.exclusive_time);
+
+            timer.exclusive_time -= subtracted;
+            timer.percent = timer.exclusive_time as f64 / total_time * 100.;
+            timer.seconds = timer.exclusive_time as f64 / self.os_timer_freq;
+
+            overhead.exclusive_time += subtracted;
+            overhead.hits += timer.hits;
+        }
+
+        if overhead.exclusive_time == 0 {
+            return;
+        }
+
+        overhead.percent = overhead.exclusive_time as f64 / total_time * 100.;
+        overhead.seconds = overhead.exclusive_time as f64 / self.os_timer_freq;
+
+        self.timers
+            .sort_by_key(|timer| std::cmp::Reverse(timer.exclusive_time));
+        self.timers.push(overhead);
+    }
+}
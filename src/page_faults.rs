@@ -0,0 +1,46 @@
+//! Implements attributing the page faults of the process to the timers
+use std::sync::MutexGuard;
+
+use crate::{get_page_faults, OpenTimer, Profiler, ThreadProfile};
+
+/// Header of the page fault column of the report
+pub(crate) const PAGE_FAULTS_HEADER: &str = "faults";
+
+impl<const TIMERS: usize> ThreadProfile<TIMERS> {
+    /// Add the page faults during the hit of the exited `open` timer on this thread, if
+    /// its page faults were read when it was entered
+    pub(crate) fn record_page_fault_delta(&mut self, open: &OpenTimer) {
+        let Some(start_page_faults) = open.start_page_faults else {
+            return;
+        };
+
+        let faults = get_page_faults().saturating_sub(start_page_faults);
+        let total = self.page_faults.entry(open.timer).or_default();
+        *total = total.wrapping_add(faults);
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Get the page faults during the hits of `timer` summed over every thread, if page
+    /// faults were recorded for it
+    ///
+    /// The faults are those of the whole process, so faults caused by other threads
+    /// during a hit are attributed to it as well.
+    #[must_use]
+    pub fn timer_page_faults(&self, timer: &str) -> Option<u64> {
+        let threads: Vec<_> = self.thread_profiles().collect();
+        page_faults_of(&threads, timer)
+    }
+}
+
+/// Get the page faults during the hits of `timer` summed over the already locked
+/// `threads`, if page faults were recorded for it
+pub(crate) fn page_faults_of<const TIMERS: usize>(
+    threads: &[MutexGuard<ThreadProfile<TIMERS>>],
+    timer: &str,
+) -> Option<u64> {
+    threads
+        .iter()
+        .filter_map(|thread| thread.page_faults.get(timer).copied())
+        .reduce(u64::wrapping_add)
+}
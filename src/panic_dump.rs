@@ -0,0 +1,73 @@
+//! Implements dumping the report when the process panics
+use std::io::Write;
+
+use crate::periodic::print_text_report;
+use crate::{Profiler, ProfilerCell, ProfilerSnapshot};
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Write the timers currently open on each thread, outermost first. Nothing is
+    /// written if no timer is open.
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    pub fn write_open_timers(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let stacks: Vec<(usize, Option<&'static str>, Vec<&'static str>)> = self
+            .thread_profiles()
+            .enumerate()
+            .filter(|(_, thread)| !thread.stack.is_empty())
+            .map(|(thread_id, thread)| (thread_id, thread.thread_name(), thread.stack.clone()))
+            .collect();
+
+        if stacks.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(out, "Open timers:")?;
+        for (thread_id, name, stack) in stacks {
+            match name {
+                Some(name) => write!(out, "  {name} ({thread_id}): ")?,
+                None => write!(out, "  Thread {thread_id}: ")?,
+            }
+
+            writeln!(out, "{}", stack.join(" > "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Print the report of `profiler` and the timers open on each thread to stderr whenever
+/// a thread panics, so a crash still shows the profile gathered until then
+///
+/// The previous panic hook runs first. Running threads are not stopped, and the timers
+/// open on the panicking thread are not in the report as they never exited. Nothing but
+/// a note is printed if the panic happened while the profiler or the slot of a thread
+/// was being changed, as waiting on their locks could never return.
+pub fn install_panic_dump<const THREADS: usize, const TIMERS: usize>(
+    profiler: &'static ProfilerCell<Profiler<THREADS, TIMERS>>,
+) {
+    let previous_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        previous_hook(info);
+
+        // Gather everything under the lock and only write once it is released. A slot
+        // locked now may be held by this very thread, which would never release it.
+        let locked = profiler
+            .try_read()
+            .filter(|locked| !locked.any_thread_locked());
+        let Some(locked) = locked else {
+            eprintln!("Profiler is locked by the panicking thread, skipping its report");
+            return;
+        };
+
+        let report = locked.since(&ProfilerSnapshot::default());
+        let mut open_timers = Vec::new();
+        let _ = locked.write_open_timers(&mut open_timers);
+        drop(locked);
+
+        let _ = std::io::stderr().write_all(&open_timers);
+        print_text_report(profiler, &report);
+    }));
+}
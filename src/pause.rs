@@ -0,0 +1,112 @@
+//! Implements pausing the profiler during phases excluded from the report
+use crate::{rdtsc, OpenTimer, Profiler, ThreadTimerStatus};
+
+/// The timer guard returned by `timer_guard!` when the profiler is disabled
+#[derive(Debug, Default, Copy, Clone)]
+pub struct DisabledTimerGuard;
+
+impl DisabledTimerGuard {
+    /// Does nothing while the profiler is disabled
+    pub fn pause(&mut self) {}
+
+    /// Does nothing while the profiler is disabled
+    pub fn resume(&mut self) {}
+
+    /// Does nothing while the profiler is disabled
+    pub fn cancel(&mut self) {}
+
+    /// Does nothing while the profiler is disabled
+    pub fn set_bytes(&mut self, _bytes: u64) {}
+
+    /// Does nothing while the profiler is disabled
+    pub fn add_bytes(&mut self, _bytes: u64) {}
+}
+
+impl OpenTimer {
+    /// Stop the clock of only this timer until [`OpenTimer::resume`], such as while
+    /// waiting on a channel inside the timed scope
+    ///
+    /// The paused time is excluded from this timer and counted as time of its parent.
+    pub fn pause(&mut self) {
+        if self.own_paused_at.is_none() {
+            self.own_paused_at = Some(rdtsc());
+        }
+    }
+
+    /// Restart the clock of this timer stopped by [`OpenTimer::pause`]
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.own_paused_at.take() {
+            self.own_paused_cycles = self
+                .own_paused_cycles
+                .wrapping_add(rdtsc().saturating_sub(paused_at));
+        }
+    }
+
+    /// Get the time (in cycles) this timer itself was paused, up to `stop_time`
+    pub(crate) fn paused_until(&self, stop_time: u64) -> u64 {
+        let current = self
+            .own_paused_at
+            .map_or(0, |paused_at| stop_time.saturating_sub(paused_at));
+
+        self.own_paused_cycles.wrapping_add(current)
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Suspend the clock of every running thread until [`Profiler::resume`]
+    ///
+    /// Timers are not entered while paused and the paused time is removed from the
+    /// timers that are open across the pause.
+    pub fn pause(&mut self) {
+        if self.paused_at.is_some() {
+            return;
+        }
+
+        let now = rdtsc();
+
+        for thread in self.thread_profiles_mut() {
+            if thread.status == ThreadTimerStatus::Running {
+                thread.time = thread.time.wrapping_add(now);
+                thread.status = ThreadTimerStatus::Paused;
+            }
+        }
+
+        self.paused_at = Some(now);
+    }
+
+    /// Restart the clock of every thread suspended by [`Profiler::pause`]
+    pub fn resume(&mut self) {
+        let Some(paused_at) = self.paused_at.take() else {
+            return;
+        };
+
+        let now = rdtsc();
+        self.paused_cycles = self.paused_cycles.wrapping_add(now.wrapping_sub(paused_at));
+
+        for thread in self.thread_profiles_mut() {
+            if thread.status == ThreadTimerStatus::Paused {
+                thread.time = thread.time.wrapping_sub(now);
+                thread.status = ThreadTimerStatus::Running;
+            }
+        }
+    }
+
+    /// Check if the profiler is paused
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Get the time (in cycles) the profiler was paused while `open` was open, up to
+    /// `stop_time`
+    pub(crate) fn paused_during(&self, open: &OpenTimer, stop_time: u64) -> u64 {
+        let mut paused = self.paused_cycles.wrapping_sub(open.paused_cycles);
+
+        // Include the current pause if the timer is exited while paused
+        if let Some(paused_at) = self.paused_at {
+            paused = paused.wrapping_add(stop_time.saturating_sub(paused_at.max(open.start_time)));
+        }
+
+        paused
+    }
+}
@@ -0,0 +1,83 @@
+//! Implements the per-thread breakdown of every timer
+use crate::{rdtsc, Profiler};
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Print every timer broken down by the threads that hit it, with the hits, cycles
+    /// and percent of each thread's time, along with how imbalanced the threads are
+    ///
+    /// The imbalance of a timer is how much longer its slowest thread spent in it than
+    /// the average thread (`max / mean - 1`), 0% when every thread spent the same time.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn print_per_thread(&mut self) {
+        // Immediately stop the profiler's timer at the beginning of this function
+        self.stop_running_threads(rdtsc());
+
+        let (acc, _) = self.accumulate_timers();
+
+        let mut indexes: Vec<usize> = (0..self.next_index as usize)
+            .filter(|index| acc[*index].hits > 0)
+            .collect();
+        indexes.sort_by_key(|index| std::cmp::Reverse(acc[*index].exclusive_time));
+
+        let name_width = indexes
+            .iter()
+            .map(|index| self.timer_label(*index).len())
+            .max()
+            .unwrap_or(0)
+            .max("TIMER".len())
+            .min(60);
+
+        eprintln!(
+            "{:<name_width$} | {:>16} | {:>10} | {:>16} | {:>7} | {:>9}",
+            "TIMER", "THREAD", "HITS", "CYCLES", "THREAD%", "IMBALANCE"
+        );
+
+        for index in indexes {
+            let label = self.timer_label(index);
+            let name = &label[..label.len().min(name_width)];
+
+            let threads: Vec<(usize, u64, u64)> = self
+                .thread_profiles_mut()
+                .enumerate()
+                .filter(|(_, thread)| thread.timers[index].hits > 0)
+                .map(|(thread_id, thread)| {
+                    let timer = &thread.timers[index];
+                    (thread_id, timer.hits, timer.exclusive_time)
+                })
+                .collect();
+
+            let max = threads
+                .iter()
+                .map(|(_, _, cycles)| *cycles)
+                .max()
+                .unwrap_or(0);
+            let mean = acc[index].exclusive_time as f64 / threads.len().max(1) as f64;
+            let imbalance = if mean > 0. {
+                (max as f64 / mean - 1.) * 100.
+            } else {
+                0.
+            };
+
+            eprintln!(
+                "{name:<name_width$} | {:>16} | {:>10} | {:>16} | {:>7} | {imbalance:>8.2}%",
+                format!("{} thr", threads.len()),
+                acc[index].hits,
+                acc[index].exclusive_time,
+                ""
+            );
+
+            for (thread_id, hits, cycles) in threads {
+                let thread_time = self.thread_mut(thread_id).time;
+                let percent = cycles as f64 / thread_time.max(1) as f64 * 100.;
+
+                eprintln!(
+                    "{:<name_width$} | {:>16} | {hits:>10} | {cycles:>16} | {percent:>6.2}% | {:>9}",
+                    "",
+                    self.thread_name(thread_id)
+                        .map_or_else(|| format!("#{thread_id}"), str::to_string),
+                    ""
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Implements the bounded reservoir of hit durations used for the percentile columns
+use std::sync::MutexGuard;
+
+use crate::exemplar::xorshift;
+use crate::{Profiler, ThreadProfile};
+
+/// Number of hit durations kept for each timer on each thread
+const SAMPLES_PER_TIMER: usize = 1024;
+
+/// A uniform random sample of the hit durations of a timer on a single thread
+#[derive(Debug, Clone, Default)]
+pub struct SampleReservoir {
+    /// The number of hits seen by this reservoir
+    seen: u64,
+
+    /// The sampled hit durations (in cycles)
+    samples: Vec<u64>,
+
+    /// State of the xorshift random number generator
+    rng: u64,
+}
+
+impl SampleReservoir {
+    /// Sample a hit of `cycles` into this reservoir (Algorithm R)
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn record(&mut self, cycles: u64) {
+        self.seen += 1;
+
+        if self.samples.len() < SAMPLES_PER_TIMER {
+            self.samples.push(cycles);
+            return;
+        }
+
+        let index = (xorshift(&mut self.rng) % self.seen) as usize;
+        if let Some(sample) = self.samples.get_mut(index) {
+            *sample = cycles;
+        }
+    }
+}
+
+/// The hit duration percentiles of a timer (in cycles, with child timers)
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Percentiles {
+    /// The median hit
+    pub p50: u64,
+
+    /// The 90th percentile hit
+    pub p90: u64,
+
+    /// The 99th percentile hit
+    pub p99: u64,
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Sample a hit of `elapsed` cycles of `timer` on the locked `thread` if percentile
+    /// recording is enabled
+    pub(crate) fn record_percentile_sample(
+        &self,
+        thread: &mut ThreadProfile<TIMERS>,
+        timer: &'static str,
+        elapsed: u64,
+    ) {
+        if !self.record_percentiles {
+            return;
+        }
+
+        thread
+            .latency_samples
+            .entry(timer)
+            .or_default()
+            .record(elapsed);
+    }
+
+    /// Estimate the hit duration percentiles of `timer` across all threads from the
+    /// sampled hits, or `None` if the timer wasn't sampled
+    #[must_use]
+    pub fn percentiles(&self, timer: &str) -> Option<Percentiles> {
+        let threads: Vec<_> = self.thread_profiles().collect();
+        percentiles_of(&threads, timer)
+    }
+}
+
+/// Estimate the hit duration percentiles of `timer` across the already locked
+/// `threads` from the sampled hits, or `None` if the timer wasn't sampled
+pub(crate) fn percentiles_of<const TIMERS: usize>(
+    threads: &[MutexGuard<ThreadProfile<TIMERS>>],
+    timer: &str,
+) -> Option<Percentiles> {
+    weighted_percentiles(
+        threads
+            .iter()
+            .filter_map(|thread| thread.latency_samples.get(timer)),
+    )
+}
+
+/// Estimate the percentiles of the samples of all of the `reservoirs`, or `None` if
+/// nothing was sampled
+///
+/// Each reservoir's samples are weighted by the number of values they represent.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn weighted_percentiles<'a>(
+    reservoirs: impl Iterator<Item = &'a SampleReservoir>,
+) -> Option<Percentiles> {
+    let mut weighted = Vec::new();
+    for reservoir in reservoirs {
+        let weight = reservoir.seen as f64 / reservoir.samples.len() as f64;
+        weighted.extend(reservoir.samples.iter().map(|sample| (*sample, weight)));
+    }
+
+    if weighted.is_empty() {
+        return None;
+    }
+
+    weighted.sort_unstable_by_key(|(sample, _)| *sample);
+    let total: f64 = weighted.iter().map(|(_, weight)| weight).sum();
+
+    // Find the first sample where the cumulative weight reaches the percentile
+    let percentile = |percent: f64| {
+        let target = total * percent / 100.;
+        let mut cumulative = 0.;
+        for (sample, weight) in &weighted {
+            cumulative += weight;
+            if cumulative >= target {
+                return *sample;
+            }
+        }
+
+        weighted.last().map_or(0, |(sample, _)| *sample)
+    };
+
+    Some(Percentiles {
+        p50: percentile(50.),
+        p90: percentile(90.),
+        p99: percentile(99.),
+    })
+}
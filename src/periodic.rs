@@ -0,0 +1,63 @@
+//! Implements printing the report periodically from a background thread
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::{JsonRenderer, Profiler, ProfilerCell, Report, ReportRenderer, TextRenderer};
+
+/// Spawn a thread printing the report of `profiler` to stderr on every `interval`, or
+/// writing it as JSON to `path` if given, such as for the continuous output of a daemon
+///
+/// Running threads are not stopped, so each report covers the whole lifetime of the
+/// profiler up to that point. The JSON file is replaced as a whole on every write, so
+/// readers never see a partially written report.
+///
+/// # Errors
+///
+/// * Failed to spawn the thread
+pub fn spawn_print_every<const THREADS: usize, const TIMERS: usize>(
+    interval: Duration,
+    path: Option<PathBuf>,
+    profiler: &'static ProfilerCell<Profiler<THREADS, TIMERS>>,
+) -> std::io::Result<()> {
+    std::thread::Builder::new()
+        .name("timeloop-print".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(interval);
+
+            let report = profiler.report();
+
+            match &path {
+                Some(path) => write_json_report(&report, path),
+                None => print_text_report(profiler, &report),
+            }
+        })?;
+
+    Ok(())
+}
+
+/// Print `report` of `profiler` as text to stderr, without holding the lock of
+/// `profiler` while writing
+pub(crate) fn print_text_report<const THREADS: usize, const TIMERS: usize>(
+    profiler: &ProfilerCell<Profiler<THREADS, TIMERS>>,
+    report: &Report,
+) {
+    let renderer = TextRenderer {
+        color: profiler.read().color.enabled(),
+    };
+    let _ = renderer.render(report, &mut std::io::stderr().lock());
+}
+
+/// Replace `path` with `report` as JSON, printing any error to stderr
+pub(crate) fn write_json_report(report: &Report, path: &Path) {
+    // Write next to the destination and rename over it to replace it at once
+    let temp_path = path.with_extension("tmp");
+    let written = std::fs::File::create(&temp_path).and_then(|mut file| {
+        JsonRenderer.render(report, &mut file)?;
+        file.flush()
+    });
+
+    if let Err(error) = written.and_then(|()| std::fs::rename(&temp_path, path)) {
+        eprintln!("Failed to write the report to {}: {error}", path.display());
+    }
+}
@@ -0,0 +1,179 @@
+//! Implements a memory-mapped region holding the latest snapshot, surviving a crash
+use std::fs::OpenOptions;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::snapshot::invalid_data;
+use crate::{Profiler, ProfilerCell, ProfilerSnapshot};
+
+/// Magic bytes at the start of a persisted region
+const PERSIST_MAGIC: &[u8; 8] = b"TLPERSIS";
+
+/// Size of each of the two snapshot slots in the region
+const SLOT_SIZE: usize = 512 * 1024;
+
+/// Size of the header: the magic followed by the index of the last completed slot
+const HEADER_SIZE: usize = 16;
+
+/// Total size of the memory-mapped region
+const REGION_SIZE: usize = HEADER_SIZE + 2 * SLOT_SIZE;
+
+/// `PROT_READ | PROT_WRITE`
+const PROT_READ_WRITE: i32 = 0x3;
+
+/// `MAP_SHARED`
+const MAP_SHARED: i32 = 0x1;
+
+extern "C" {
+    fn mmap(addr: *mut u8, len: usize, prot: i32, flags: i32, fd: i32, offset: i64) -> *mut u8;
+}
+
+/// The base of the memory-mapped region of [`REGION_SIZE`] bytes
+///
+/// Every access goes through this raw pointer, so the header word read by the atomic
+/// never aliases a slice of the region.
+struct Region(*mut u8);
+
+// The mapping is never unmapped and only written by the persist thread
+unsafe impl Send for Region {}
+
+impl Region {
+    /// Copy `bytes` into the region at `offset`
+    ///
+    /// # Panics
+    ///
+    /// * `bytes` don't fit in the region at `offset`
+    fn write(&self, offset: usize, bytes: &[u8]) {
+        assert!(offset + bytes.len() <= REGION_SIZE);
+        unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), self.0.add(offset), bytes.len()) };
+    }
+
+    /// The index of the last completed slot, in the header after the magic
+    fn current_slot(&self) -> &AtomicU64 {
+        // The mapping is page aligned, so the word after the magic is aligned
+        unsafe { AtomicU64::from_ptr(self.0.add(PERSIST_MAGIC.len()).cast()) }
+    }
+}
+
+/// Periodically write a snapshot of `profiler` into a memory-mapped `path`
+///
+/// The snapshot is written into the page cache on every `interval`, so the last
+/// snapshot survives the process being killed or crashing. Each snapshot alternates
+/// between two slots, keeping the previous one intact if a write is interrupted. Read
+/// it back with [`ProfilerSnapshot::load_persisted`]. A snapshot too large for its slot
+/// is skipped, keeping the previous one, and reported once.
+///
+/// # Errors
+///
+/// * Failed to create or resize `path`
+/// * Failed to memory map `path`
+pub fn spawn_persist<const THREADS: usize, const TIMERS: usize>(
+    path: impl AsRef<Path>,
+    interval: Duration,
+    profiler: &'static ProfilerCell<Profiler<THREADS, TIMERS>>,
+) -> std::io::Result<()> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    file.set_len(REGION_SIZE as u64)?;
+
+    let region = unsafe {
+        mmap(
+            std::ptr::null_mut(),
+            REGION_SIZE,
+            PROT_READ_WRITE,
+            MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+
+    if region as isize == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // The mapping stays valid after the file is closed and is never unmapped
+    let region = Region(region);
+    region.write(0, PERSIST_MAGIC);
+
+    std::thread::Builder::new()
+        .name("timeloop-persist".to_string())
+        .spawn(move || {
+            let mut bytes = Vec::new();
+            let mut reported_overflow = false;
+
+            loop {
+                bytes.clear();
+                if profiler.snapshot().write_to(&mut bytes).is_err() {
+                    std::thread::sleep(interval);
+                    continue;
+                }
+
+                if bytes.len() + 8 > SLOT_SIZE {
+                    if !reported_overflow {
+                        eprintln!(
+                            "Warning: the snapshot of {} bytes is too large for the persisted slot of {SLOT_SIZE} bytes. Keeping the last snapshot that fit.",
+                            bytes.len()
+                        );
+                        reported_overflow = true;
+                    }
+
+                    std::thread::sleep(interval);
+                    continue;
+                }
+
+                // Write the snapshot into the slot not referenced by the header, keeping the
+                // published one intact until the write is complete
+                let current = region.current_slot();
+                let slot_index = usize::from(current.load(Ordering::Acquire) == 0);
+                let slot = HEADER_SIZE + slot_index * SLOT_SIZE;
+                region.write(slot, &(bytes.len() as u64).to_le_bytes());
+                region.write(slot + 8, &bytes);
+
+                // Only publish the slot once it is completely written
+                current.store(slot_index as u64, Ordering::Release);
+
+                std::thread::sleep(interval);
+            }
+        })?;
+
+    Ok(())
+}
+
+impl ProfilerSnapshot {
+    /// Load the last snapshot written into `path` by [`spawn_persist`]
+    ///
+    /// # Errors
+    ///
+    /// * Failed to read `path`
+    /// * `path` is not a persisted region or holds no snapshot yet
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn load_persisted(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let region = std::fs::read(path)?;
+
+        if region.len() != REGION_SIZE || &region[..PERSIST_MAGIC.len()] != PERSIST_MAGIC {
+            return Err(invalid_data("Not a timeloop persisted region"));
+        }
+
+        let read_u64 = |bytes: &[u8]| {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(&bytes[..8]);
+            u64::from_le_bytes(word) as usize
+        };
+
+        let slot_index = read_u64(&region[8..]);
+        let slot = &region[HEADER_SIZE + slot_index.min(1) * SLOT_SIZE..][..SLOT_SIZE];
+
+        let len = read_u64(slot);
+        if len == 0 || len + 8 > SLOT_SIZE {
+            return Err(invalid_data("No snapshot was persisted"));
+        }
+
+        Self::read_from(&mut &slot[8..8 + len])
+    }
+}
@@ -0,0 +1,29 @@
+//! The stable public API of timeloop
+//!
+//! ```ignore
+//! use timeloop::prelude::*;
+//! ```
+//!
+//! Everything exported here is kept source compatible across releases. Items hidden
+//! from the documentation (the per-thread storage of [`Profiler`], the types used by the
+//! expanded macros and the items generated by `create_profiler!`) are unstable and may
+//! change in any release.
+//!
+//! `timeloop::print!` is not exported to avoid shadowing `std::print!`.
+
+pub use crate::{
+    begin_span, counter, create_profiler, end_span, gauge, pause_profiler, print_with_format,
+    report, reset, resume_profiler, save, scoped_bandwidth_timer, scoped_dynamic_timer,
+    scoped_timer, scoped_timer_here, scoped_timer_if, scoped_timer_sampled, snapshot, spawn,
+    start_profiler, start_thread, stop_thread, time_iter, time_work, time_work_fn,
+    time_work_with_bandwidth, timer_guard,
+};
+
+pub use crate::{
+    intern, is_enabled, set_enabled, timer_hash, ColorMode, JsonRenderer, LatencyToken,
+    MarkdownRenderer, Percentiles, Profiler, ProfilerHandle, ProfilerSnapshot, Report,
+    ReportFormat, ReportRenderer, SpanId, TextRenderer, TimeUnit, Timer, TimerKey, TimerReport,
+};
+
+#[cfg(feature = "statsd")]
+pub use crate::StatsdConfig;
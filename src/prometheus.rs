@@ -0,0 +1,145 @@
+//! Implements the Prometheus text exposition format of the profiler state
+use std::fmt::Write;
+
+use crate::{calibration, timer_hash, Profiler, ProfilerSnapshot, Timer};
+
+/// A counter metric: its name, help text and how to read it from a timer
+type Metric = (&'static str, &'static str, fn(&Timer) -> u64);
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Render the current state of every timer in the Prometheus text exposition format
+    ///
+    /// Running threads are not stopped, so this can be scraped from long-running
+    /// services.
+    #[must_use]
+    pub fn prometheus_text(&self) -> String {
+        self.snapshot().prometheus_text()
+    }
+}
+
+impl ProfilerSnapshot {
+    /// Render the timers of this snapshot in the Prometheus text exposition format, see
+    /// [`Profiler::prometheus_text`]
+    #[must_use]
+    pub fn prometheus_text(&self) -> String {
+        let total_time_cycles = self.total_time;
+
+        let mut out = String::new();
+
+        let metrics: [Metric; 4] = [
+            (
+                "timeloop_timer_hits_total",
+                "Number of times the timer was hit",
+                |timer| timer.hits,
+            ),
+            (
+                "timeloop_timer_exclusive_cycles_total",
+                "Cycles spent in the timer without child timers",
+                |timer| timer.exclusive_time,
+            ),
+            (
+                "timeloop_timer_inclusive_cycles_total",
+                "Cycles spent in the timer including child timers",
+                |timer| timer.inclusive_time,
+            ),
+            (
+                "timeloop_timer_bytes_total",
+                "Bytes processed by the timer",
+                |timer| timer.bytes_processed,
+            ),
+        ];
+
+        for (name, help, value) in metrics {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+
+            for (timer_name, timer) in &self.timers {
+                let label = escape_label(timer_name);
+                let hash = timer_hash(timer_name);
+                let _ = writeln!(
+                    out,
+                    "{name}{{timer=\"{label}\",hash=\"{hash:016x}\"}} {}",
+                    value(timer)
+                );
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP timeloop_total_cycles Cycles profiled across all threads"
+        );
+        let _ = writeln!(out, "# TYPE timeloop_total_cycles counter");
+        let _ = writeln!(out, "timeloop_total_cycles {total_time_cycles}");
+
+        let calibration = calibration();
+        let source = calibration.source;
+        let _ = writeln!(
+            out,
+            "# HELP timeloop_clock_frequency_hertz Calibrated frequency of the clock"
+        );
+        let _ = writeln!(out, "# TYPE timeloop_clock_frequency_hertz gauge");
+        let _ = writeln!(
+            out,
+            "timeloop_clock_frequency_hertz{{source=\"{source}\"}} {}",
+            calibration.frequency
+        );
+        let _ = writeln!(
+            out,
+            "# HELP timeloop_clock_frequency_error_hertz Estimated error of the calibrated frequency"
+        );
+        let _ = writeln!(out, "# TYPE timeloop_clock_frequency_error_hertz gauge");
+        let _ = writeln!(
+            out,
+            "timeloop_clock_frequency_error_hertz{{source=\"{source}\"}} {}",
+            calibration.error
+        );
+
+        out
+    }
+}
+
+/// Escape the given string to be used as a Prometheus label value
+fn escape_label(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serve the output of `render` to every HTTP request on `addr` from a background thread
+///
+/// # Errors
+///
+/// * Failed to bind to `addr`
+#[cfg(feature = "prometheus-http")]
+pub fn serve_prometheus(
+    addr: impl std::net::ToSocketAddrs,
+    render: impl Fn() -> String + Send + 'static,
+) -> std::io::Result<()> {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind(addr)?;
+
+    std::thread::Builder::new()
+        .name("timeloop-prometheus".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+
+                // Every path returns the metrics, so the request itself is ignored
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request);
+
+                let body = render();
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        })?;
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+//! Implements tracking how deeply each timer is nested in itself, so recursive timers
+//! only count their outermost hit towards their inclusive time
+use crate::{Profiler, ThreadProfile};
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Note that `timer` was entered on the locked `thread`, one level deeper in itself
+    pub(crate) fn enter_recursion(&self, thread: &mut ThreadProfile<TIMERS>, timer: &'static str) {
+        thread.recursion_depths[self.timer_index(timer)] += 1;
+    }
+
+    /// Note that `timer` was exited on the locked `thread`, returning `true` if this was
+    /// its outermost hit, which isn't nested in another hit of the same timer
+    pub(crate) fn exit_recursion(
+        &self,
+        thread: &mut ThreadProfile<TIMERS>,
+        timer: &'static str,
+    ) -> bool {
+        let depth = &mut thread.recursion_depths[self.timer_index(timer)];
+        *depth = depth.saturating_sub(1);
+        *depth == 0
+    }
+}
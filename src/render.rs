@@ -0,0 +1,652 @@
+//! Implements the renderers of the structured timer report
+use std::fmt::Write as _;
+use std::io::Write;
+
+use crate::report::{Report, TimerReport};
+use crate::trace::json_escape;
+use crate::user_counter::counter_cells;
+use crate::REMAINING_TIME_LABEL;
+use crate::{AllocationStats, ContextSwitches, Percentiles};
+
+/// Renders a structured [`Report`] into an output
+pub trait ReportRenderer {
+    /// Render `report` into `out`
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    fn render(&self, report: &Report, out: &mut dyn Write) -> std::io::Result<()>;
+}
+
+/// Renders the report as aligned plain text columns
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TextRenderer {
+    /// Highlight the hotspots, the insignificant timers and the throughput with ANSI
+    /// colors
+    pub color: bool,
+}
+
+/// Whether the printed report is colorized
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Colorize when stderr is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+
+    /// Always colorize
+    Always,
+
+    /// Never colorize
+    Never,
+}
+
+impl ColorMode {
+    /// Check if output to stderr should be colorized in this mode
+    #[must_use]
+    pub fn enabled(self) -> bool {
+        use std::io::IsTerminal;
+
+        match self {
+            ColorMode::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal()
+            }
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+/// Number of the most expensive timers highlighted as hotspots
+const HOTSPOT_COUNT: usize = 3;
+
+/// Timers below this percent of the total time are dimmed
+const DIM_PERCENT: f64 = 1.0;
+
+/// ANSI escape sequence for the hotspots
+const RED: &str = "\x1b[1;31m";
+
+/// ANSI escape sequence for the insignificant timers
+const DIM: &str = "\x1b[2m";
+
+/// ANSI escape sequence for the throughput
+const CYAN: &str = "\x1b[36m";
+
+/// ANSI escape sequence resetting the color
+const RESET: &str = "\x1b[0m";
+
+/// Header of the percentile column
+const PERCENTILES_HEADER: &str = "P50 / P90 / P99";
+
+/// Format `cell` as the optional percentile column of the given `width` (including the
+/// separator), or nothing if the column is hidden
+fn percentile_column(cell: &str, width: usize) -> String {
+    if width == 0 {
+        return String::new();
+    }
+
+    format!(" | {cell:>cell_width$}", cell_width = width - 3)
+}
+
+/// Renders the report as a markdown table that can be pasted into GitHub issues and PRs
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MarkdownRenderer;
+
+/// Renders the report as a JSON object for other tools to consume
+#[derive(Debug, Default, Copy, Clone)]
+pub struct JsonRenderer;
+
+impl Report {
+    /// Percent of the total time not spent in any timer
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn remainder_percent(&self) -> f64 {
+        self.remainder as f64 / self.total_time as f64 * 100.
+    }
+
+    /// Check if any timers were left out of the report, hidden for being below the
+    /// minimum percent or ignored for being too short
+    pub(crate) fn has_omitted(&self) -> bool {
+        self.hidden_timers > 0 || self.ignored_timers > 0
+    }
+
+    /// Write the summaries of the timers hidden for being below the minimum percent and
+    /// of the short timers that were ignored, if there were any
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn write_omitted(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let percent = |cycles: u64| cycles as f64 / self.total_time.max(1) as f64 * 100.;
+
+        if self.hidden_timers > 0 {
+            writeln!(
+                out,
+                "Hidden: {} timers below {}% ({}{} {:.2}%)",
+                self.hidden_timers,
+                self.min_percent,
+                self.format_cycles(self.hidden_time),
+                self.time_unit.cycles_label(),
+                percent(self.hidden_time)
+            )?;
+        }
+
+        if self.ignored_timers > 0 {
+            writeln!(
+                out,
+                "Ignored: {} timers after {} hits shorter than {} cycles ({}{} {:.2}%)",
+                self.ignored_timers,
+                self.short_timer_policy.streak,
+                self.short_timer_policy.threshold_cycles,
+                self.format_cycles(self.ignored_time),
+                self.time_unit.cycles_label(),
+                percent(self.ignored_time)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the calculated frequency and total time of the report
+    #[allow(clippy::cast_precision_loss)]
+    fn write_header(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "Calculated OS frequency: {} ({}, ±{:.3}%)",
+            self.os_timer_freq,
+            self.calibration.source,
+            self.calibration.relative_error() * 100.
+        )?;
+
+        if let Some(cpu) = &self.cpu {
+            writeln!(out, "CPU: {cpu}")?;
+        }
+
+        writeln!(
+            out,
+            "Total time: {:8.2?} ({} cycles)",
+            std::time::Duration::from_secs_f64(self.total_time as f64 / self.os_timer_freq),
+            self.total_time
+        )?;
+
+        // Explain every short timer that was ignored in place of a warning during the run
+        for decision in &self.ignore_decisions {
+            writeln!(
+                out,
+                "Ignored {} on thread {} at hit {} ({:.2?} in): {} hits in a row under {} cycles, averaging {} cycles",
+                decision.timer,
+                decision.thread_id,
+                decision.hits,
+                std::time::Duration::from_secs_f64(
+                    decision.thread_time as f64 / self.os_timer_freq
+                ),
+                decision.streak,
+                self.short_timer_policy.threshold_cycles,
+                decision.avg_cycles
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Check if any timer of the report has recorded percentiles
+    fn has_percentiles(&self) -> bool {
+        self.timers.iter().any(|timer| timer.percentiles.is_some())
+    }
+
+    /// Add the optional columns to the rows of the markdown table: the percentiles after
+    /// the coefficient of variation if they were recorded, and the user counters at the
+    /// end. The rows after the timers (the remainder) are left empty.
+    fn insert_optional_columns(&self, header: &mut Vec<&'static str>, rows: &mut [Vec<String>]) {
+        if self.has_percentiles() {
+            header.insert(7, PERCENTILES_HEADER);
+            for (i, row) in rows.iter_mut().enumerate() {
+                let timer = self.timers.get(i);
+                row.insert(
+                    7,
+                    timer
+                        .map(|timer| timer.percentiles_cell(self))
+                        .unwrap_or_default(),
+                );
+            }
+        }
+
+        for (counter, _) in self.counter_columns() {
+            header.push(counter);
+            for (i, row) in rows.iter_mut().enumerate() {
+                let value = self.timers.get(i).and_then(|timer| timer.counter(counter));
+                row.push(value.map(|value| value.to_string()).unwrap_or_default());
+            }
+        }
+    }
+
+    /// Get the maximum width of a column, given its `header` and the `cell` of each timer
+    fn column_width(&self, header: &str, cell: impl Fn(&TimerReport) -> String) -> usize {
+        self.timers
+            .iter()
+            .map(|timer| cell(timer).chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(header.len())
+    }
+}
+
+impl std::fmt::Display for Report {
+    /// Format the report as the aligned plain text columns of [`TextRenderer`]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut out = Vec::new();
+        TextRenderer::default()
+            .render(self, &mut out)
+            .map_err(|_| std::fmt::Error)?;
+
+        f.write_str(&String::from_utf8_lossy(&out))
+    }
+}
+
+impl TimerReport {
+    /// Format the number of threads that hit this timer and the share of the dominant one
+    fn thread_affinity(&self) -> String {
+        // Rows aggregating several timers don't have a dominant thread
+        if self.threads == 0 {
+            return String::new();
+        }
+
+        let thread = self
+            .dominant_thread_name
+            .map_or_else(|| format!("#{}", self.dominant_thread), str::to_string);
+
+        format!(
+            "{} ({:.0}% on {thread})",
+            self.threads, self.dominant_thread_percent
+        )
+    }
+
+    /// Format the hit duration percentiles of this timer in the display unit of
+    /// `report`, if they were recorded
+    fn percentiles_cell(&self, report: &Report) -> String {
+        self.percentiles
+            .map(|Percentiles { p50, p90, p99 }| {
+                format!(
+                    "{} / {} / {}",
+                    report.format_cycles(p50),
+                    report.format_cycles(p90),
+                    report.format_cycles(p99)
+                )
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl ReportRenderer for TextRenderer {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> std::io::Result<()> {
+        report.write_header(out)?;
+
+        // Update the variant length to be the maximum length (capped at 60 chars)
+        let variant_length = report
+            .timers
+            .iter()
+            .map(|timer| timer.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(REMAINING_TIME_LABEL.len())
+            .min(60);
+
+        // Calculate the maximum width of the numeric columns
+        let hit_width = report.column_width("HITS", |timer| timer.hits.to_string());
+        let thread_width = report.column_width("THREADS", TimerReport::thread_affinity);
+        let time = |cycles: u64| report.format_cycles(cycles);
+        let cycles_label = report.time_unit.cycles_label();
+        let min_width = report.column_width("MIN", |timer| time(timer.min_cycles));
+        let max_width = report.column_width("MAX", |timer| time(timer.max_cycles));
+        let std_dev_width =
+            report.column_width("STDDEV", |timer| report.format_time(timer.std_dev_cycles));
+
+        // The percentile column is only shown when percentiles were recorded
+        let percentile_width = if report.has_percentiles() {
+            report.column_width(PERCENTILES_HEADER, |timer| timer.percentiles_cell(report)) + 3
+        } else {
+            0
+        };
+
+        // The user counters are shown as extra columns after the percentiles
+        let counters = report.counter_columns();
+
+        writeln!(
+            out,
+            "{:<variant_length$} | {:^hit_width$} | {:^thread_width$} | {:^min_width$} | {:^max_width$} | {:^std_dev_width$} | {:^6}{}{}",
+            "TIMER", "HITS", "THREADS", "MIN", "MAX", "STDDEV", "CV",
+            percentile_column(PERCENTILES_HEADER, percentile_width),
+            counter_cells(&counters, str::to_string)
+        )?;
+
+        // Wrap `text` in the given color when colorizing
+        let paint = |color: &str, text: String| {
+            if self.color && !text.is_empty() {
+                format!("{color}{text}{RESET}")
+            } else {
+                text
+            }
+        };
+
+        for (i, timer) in report.timers.iter().enumerate() {
+            let TimerReport {
+                name,
+                hits,
+                min_cycles,
+                max_cycles,
+                std_dev_cycles,
+                coefficient_of_variation,
+                exclusive_time,
+                percent,
+                inclusive_percent,
+                throughput,
+                ..
+            } = timer;
+
+            let name = &name[..name.len().min(variant_length)];
+
+            let inclusive_time_str = inclusive_percent
+                .map(|percent| format!("({percent:5.2}% with child timers)"))
+                .unwrap_or_default();
+
+            let throughput_str = throughput
+                .map(|gbs_per_sec| format!("{gbs_per_sec:5.3} GBs/sec"))
+                .unwrap_or_default();
+
+            let cv_percent = coefficient_of_variation * 100.;
+            let threads = timer.thread_affinity();
+            let percentiles = percentile_column(&timer.percentiles_cell(report), percentile_width);
+            let counters = counter_cells(&counters, |counter| {
+                timer.counter(counter).unwrap_or(0).to_string()
+            });
+            let (min, max) = (time(*min_cycles), time(*max_cycles));
+            let std_dev = report.format_time(*std_dev_cycles);
+            let exclusive_time = time(*exclusive_time);
+
+            let stats = format!(
+                "{name:<variant_length$} | {hits:<hit_width$} | {threads:<thread_width$} | {min:>min_width$} | {max:>max_width$} | {std_dev:>std_dev_width$} | {cv_percent:5.1}%{percentiles}{counters} | {exclusive_time:>14}{cycles_label} {percent:6.2}% | {inclusive_time_str}",
+            );
+
+            // Highlight the hotspots and dim the insignificant timers
+            let stats = if i < HOTSPOT_COUNT && *percent >= DIM_PERCENT {
+                paint(RED, stats)
+            } else if *percent < DIM_PERCENT {
+                paint(DIM, stats)
+            } else {
+                stats
+            };
+
+            // Print the stats for this timer
+            writeln!(out, "{stats} {}", paint(CYAN, throughput_str))?;
+        }
+
+        // Print the remaining
+        writeln!(
+            out,
+            "{REMAINING_TIME_LABEL:<variant_length$} | {:<hit_width$} | {:thread_width$} | {:min_width$} | {:max_width$} | {:std_dev_width$} | {:6}{}{} | {:>14}{cycles_label} {:6.2}%",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            percentile_column("", percentile_width),
+            counter_cells(&counters, |_| String::new()),
+            time(report.remainder),
+            report.remainder_percent(),
+        )?;
+
+        report.write_omitted(out)
+    }
+}
+
+impl ReportRenderer for MarkdownRenderer {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> std::io::Result<()> {
+        report.write_header(out)?;
+
+        let mut header = vec![
+            "Timer",
+            "Hits",
+            "Threads",
+            "Min",
+            "Max",
+            "Std dev",
+            "CV",
+            report.time_unit.time_header(),
+            "Percent",
+            "With children",
+            "Throughput",
+        ];
+
+        let mut rows: Vec<Vec<String>> = report
+            .timers
+            .iter()
+            .map(|timer| {
+                vec![
+                    format!("`{}`", timer.name.replace('|', "\\|")),
+                    timer.hits.to_string(),
+                    timer.thread_affinity(),
+                    report.format_cycles(timer.min_cycles),
+                    report.format_cycles(timer.max_cycles),
+                    report.format_time(timer.std_dev_cycles),
+                    format!("{:.1}%", timer.coefficient_of_variation * 100.),
+                    report.format_cycles(timer.exclusive_time),
+                    format!("{:.2}%", timer.percent),
+                    timer
+                        .inclusive_percent
+                        .map(|percent| format!("{percent:.2}%"))
+                        .unwrap_or_default(),
+                    timer
+                        .throughput
+                        .map(|gbs_per_sec| format!("{gbs_per_sec:.3} GBs/sec"))
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect();
+
+        rows.push(vec![
+            REMAINING_TIME_LABEL.to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            report.format_cycles(report.remainder),
+            format!("{:.2}%", report.remainder_percent()),
+            String::new(),
+            String::new(),
+        ]);
+
+        report.insert_optional_columns(&mut header, &mut rows);
+
+        // Align every column to its widest cell
+        let mut widths: Vec<usize> = header.iter().map(|name| name.len()).collect();
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count()).max(3);
+            }
+        }
+
+        // The name column is left aligned, the numeric columns are right aligned
+        let mut line = String::from("|");
+        for (i, (name, width)) in header.iter().zip(widths.iter().copied()).enumerate() {
+            if i == 0 {
+                let _ = write!(line, " {name:<width$} |");
+            } else {
+                let _ = write!(line, " {name:>width$} |");
+            }
+        }
+        writeln!(out, "{line}")?;
+
+        let mut line = String::from("|");
+        for (i, width) in widths.iter().enumerate() {
+            if i == 0 {
+                let _ = write!(line, ":{}|", "-".repeat(width + 1));
+            } else {
+                let _ = write!(line, "{}:|", "-".repeat(width + 1));
+            }
+        }
+        writeln!(out, "{line}")?;
+
+        for row in &rows {
+            let mut line = String::from("|");
+            for (i, (cell, width)) in row.iter().zip(widths.iter().copied()).enumerate() {
+                if i == 0 {
+                    let _ = write!(line, " {cell:<width$} |");
+                } else {
+                    let _ = write!(line, " {cell:>width$} |");
+                }
+            }
+            writeln!(out, "{line}")?;
+        }
+
+        // Separate the summary of the hidden timers from the table
+        if report.has_omitted() {
+            writeln!(out)?;
+        }
+        report.write_omitted(out)
+    }
+}
+
+/// Format `value` as a JSON number, or `null` if it is infinite or NaN as JSON has no
+/// representation for them
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// Format an optional number as JSON
+fn json_option(value: Option<f64>) -> String {
+    value.map_or_else(|| "null".to_string(), json_number)
+}
+
+impl TimerReport {
+    /// Write this timer as a JSON object
+    fn write_json(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        write!(
+            out,
+            "{{\"name\":\"{}\",\"hash\":\"{:016x}\",\"hits\":{},\"threads\":{},\"dominant_thread\":{},\"dominant_thread_name\":{},\"dominant_thread_percent\":{},\"min_cycles\":{},\"max_cycles\":{},\"std_dev_cycles\":{},\"coefficient_of_variation\":{},\"category\":{},\"percentiles\":{},\"exclusive_time\":{},\"inclusive_time\":{},\"seconds\":{},\"bytes_processed\":{},\"percent\":{},\"inclusive_percent\":{},\"throughput\":{},\"counters\":{},\"page_faults\":{},\"allocations\":{},\"context_switches\":{}}}",
+            json_escape(&self.name),
+            self.hash,
+            self.hits,
+            self.threads,
+            self.dominant_thread,
+            self.dominant_thread_name.map_or_else(
+                || "null".to_string(),
+                |name| format!("\"{}\"", json_escape(name))
+            ),
+            json_number(self.dominant_thread_percent),
+            self.min_cycles,
+            self.max_cycles,
+            json_number(self.std_dev_cycles),
+            json_number(self.coefficient_of_variation),
+            self.category.map_or_else(
+                || "null".to_string(),
+                |category| format!("\"{}\"", json_escape(category))
+            ),
+            self.percentiles.map_or_else(
+                || "null".to_string(),
+                |Percentiles { p50, p90, p99 }| format!(
+                    "{{\"p50\":{p50},\"p90\":{p90},\"p99\":{p99}}}"
+                )
+            ),
+            self.exclusive_time,
+            self.inclusive_time,
+            json_number(self.seconds),
+            self.bytes_processed,
+            json_number(self.percent),
+            json_option(self.inclusive_percent),
+            json_option(self.throughput),
+            self.counters_json(),
+            self.page_faults
+                .map_or_else(|| "null".to_string(), |faults| faults.to_string()),
+            self.allocations.map_or_else(
+                || "null".to_string(),
+                |AllocationStats { count, bytes }| format!(
+                    "{{\"count\":{count},\"bytes\":{bytes}}}"
+                )
+            ),
+            self.context_switches.map_or_else(
+                || "null".to_string(),
+                |ContextSwitches {
+                     voluntary,
+                     involuntary,
+                 }| format!(
+                    "{{\"voluntary\":{voluntary},\"involuntary\":{involuntary}}}"
+                )
+            )
+        )
+    }
+}
+
+impl ReportRenderer for JsonRenderer {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> std::io::Result<()> {
+        write!(
+            out,
+            "{{\"os_timer_freq\":{},\"calibration_error\":{},\"clock_source\":\"{}\",\"total_time\":{},\"remainder\":{},\"hidden_timers\":{},\"hidden_time\":{},\"ignored_timers\":{},\"ignored_time\":{},\"timers\":[",
+            json_number(report.os_timer_freq),
+            json_number(report.calibration.error),
+            report.calibration.source,
+            report.total_time,
+            report.remainder,
+            report.hidden_timers,
+            report.hidden_time,
+            report.ignored_timers,
+            report.ignored_time
+        )?;
+
+        for (i, timer) in report.timers.iter().enumerate() {
+            if i > 0 {
+                write!(out, ",")?;
+            }
+
+            timer.write_json(out)?;
+        }
+
+        write!(out, "],\"ignore_decisions\":[")?;
+
+        for (i, decision) in report.ignore_decisions.iter().enumerate() {
+            let comma = if i > 0 { "," } else { "" };
+            write!(
+                out,
+                "{comma}{{\"timer\":\"{}\",\"thread\":{},\"hits\":{},\"thread_time\":{},\"avg_cycles\":{},\"streak\":{}}}",
+                json_escape(decision.timer),
+                decision.thread_id,
+                decision.hits,
+                decision.thread_time,
+                decision.avg_cycles,
+                decision.streak
+            )?;
+        }
+
+        write!(out, "],\"cpu\":")?;
+
+        match &report.cpu {
+            Some(cpu) => {
+                write!(
+                    out,
+                    "{{\"brand\":\"{}\",\"logical_cpus\":{},\"physical_cores\":{},\"packages\":{},\"caches\":[",
+                    json_escape(&cpu.brand),
+                    cpu.logical_cpus,
+                    cpu.physical_cores,
+                    cpu.packages
+                )?;
+
+                for (i, cache) in cpu.caches.iter().enumerate() {
+                    let comma = if i > 0 { "," } else { "" };
+                    write!(
+                        out,
+                        "{comma}{{\"level\":{},\"kind\":\"{}\",\"size\":{}}}",
+                        cache.level,
+                        json_escape(&cache.kind),
+                        cache.size
+                    )?;
+                }
+
+                write!(out, "]}}")?;
+            }
+            None => write!(out, "null")?,
+        }
+
+        writeln!(out, "}}")
+    }
+}
@@ -1,6 +1,10 @@
 //! Implements `RepitionTester`
+use std::io::Write;
+use std::path::Path;
 use std::time::{Duration, Instant};
 
+use crate::trace::json_escape;
+
 #[allow(dead_code)]
 enum TestingState {
     // Uninit,
@@ -10,6 +14,7 @@ enum TestingState {
 
 /// Statistics for an individual test case
 #[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TestCase {
     /// The time (in cycles) for this test case
     pub cycles: u64,
@@ -29,6 +34,7 @@ pub struct TestCase {
 
 /// The results for a repitition tester
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TestResults {
     /// Number of times the test was executed
     count: u64,
@@ -74,6 +80,15 @@ impl Default for TestResults {
 impl TestResults {
     /// Print the results of this test
     pub fn print(&self) {
+        let _ = self.print_to(&mut std::io::stdout().lock());
+    }
+
+    /// Write the results of this test to `out`
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    pub fn print_to(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
         let TestResults {
             count: _,
             total_time: _,
@@ -84,7 +99,11 @@ impl TestResults {
         } = self;
 
         for (title, results) in [("Min", min), ("Max", max), ("Avg", avg)] {
-            print!("{title}: {:8.2?} ({:8.2?})", results.cycles, results.time);
+            write!(
+                out,
+                "{title}: {:8.2?} ({:8.2?})",
+                results.cycles, results.time
+            )?;
 
             if let Some(bytes_per_second) = results.bytes_per_second {
                 let (num, unit) = if bytes_per_second > 1024. * 1024. * 1024. {
@@ -97,13 +116,14 @@ impl TestResults {
                     (bytes_per_second, "B")
                 };
 
-                print!(
+                write!(
+                    out,
                     " {num:8.2} {unit}/sec | PageFaults: {}",
                     results.page_faults,
-                );
+                )?;
             }
 
-            println!();
+            writeln!(out)?;
         }
 
         /*
@@ -120,6 +140,8 @@ impl TestResults {
             self.total_page_faults / self.count
         );
         */
+
+        Ok(())
     }
 }
 
@@ -147,6 +169,12 @@ pub struct RepititionTester {
 
     /// The results of this current test
     results: TestResults,
+
+    /// Keep the time (in cycles) of every test in `samples`
+    record_samples: bool,
+
+    /// The time (in cycles) of every test, if `record_samples` is set
+    samples: Vec<u64>,
 }
 
 fn rdtsc() -> u64 {
@@ -167,9 +195,23 @@ impl RepititionTester {
             stop_count: 0,
             elapsed_time: 0,
             results: TestResults::default(),
+            record_samples: false,
+            samples: Vec::new(),
         }
     }
 
+    /// Keep the time of every test to be written by [`RepititionTester::write_criterion`]
+    pub fn record_samples(&mut self) {
+        self.record_samples = true;
+    }
+
+    /// Get the time (in cycles) of every test, if [`RepititionTester::record_samples`]
+    /// was called
+    #[must_use]
+    pub fn samples(&self) -> &[u64] {
+        &self.samples
+    }
+
     pub fn reset(&mut self) {
         self.state = TestingState::Running;
         self.start_time = std::time::Instant::now();
@@ -178,6 +220,7 @@ impl RepititionTester {
         self.stop_count = 0;
         self.elapsed_time = 0;
         self.results = TestResults::default();
+        self.samples.clear();
     }
 
     /// Get the results of a test
@@ -208,6 +251,7 @@ impl RepititionTester {
 
         // Update the average time results
         self.results.avg.cycles = self.results.total_time / self.results.count;
+        self.results.avg.page_faults = self.results.total_page_faults / self.results.count;
         self.results.avg.time = Duration::from_secs_f64(
             self.results.total_time as f64 / os_freq / self.results.count as f64,
         );
@@ -254,6 +298,10 @@ impl RepititionTester {
                 self.results.total_time += self.elapsed_time;
                 self.results.total_page_faults += self.page_faults;
 
+                if self.record_samples {
+                    self.samples.push(self.elapsed_time);
+                }
+
                 if self.elapsed_time < self.results.min.cycles {
                     self.results.min.cycles = self.elapsed_time;
                     self.results.min.page_faults = self.page_faults;
@@ -290,3 +338,128 @@ impl RepititionTester {
         self.page_faults = self.page_faults.wrapping_add(crate::get_page_faults());
     }
 }
+
+impl RepititionTester {
+    /// Write the results of this test in the layout of criterion's `new` directory
+    /// (`<dir>/<id>/new/{benchmark,estimates,sample}.json`) so criterion tooling such as
+    /// `critcmp` can read them. `bytes` is the throughput of each test, if any.
+    ///
+    /// Without [`RepititionTester::record_samples`], all of the tests are written as a
+    /// single sample.
+    ///
+    /// # Errors
+    ///
+    /// * Failed to create or write to the files in `dir`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn write_criterion(
+        &self,
+        dir: impl AsRef<Path>,
+        id: &str,
+        bytes: Option<usize>,
+    ) -> std::io::Result<()> {
+        let ns_per_cycle = 1_000_000_000.0 / crate::calculate_os_frequency();
+
+        // The (iterations, nanoseconds) of every sample
+        let (iters, times): (Vec<f64>, Vec<f64>) = if self.samples.is_empty() {
+            vec![(
+                self.results.count as f64,
+                self.results.total_time as f64 * ns_per_cycle,
+            )]
+            .into_iter()
+            .unzip()
+        } else {
+            self.samples
+                .iter()
+                .map(|cycles| (1.0, *cycles as f64 * ns_per_cycle))
+                .unzip()
+        };
+
+        let directory_name: String = id
+            .chars()
+            .map(|c| match c {
+                '?' | '"' | '/' | '\\' | '*' | '<' | '>' | ':' | '|' | '^' => '_',
+                c => c,
+            })
+            .collect();
+
+        let dir = dir.as_ref().join(&directory_name).join("new");
+        std::fs::create_dir_all(&dir)?;
+
+        let id = json_escape(id);
+        let directory_name = json_escape(&directory_name);
+        let throughput = bytes.map_or_else(
+            || "null".to_string(),
+            |bytes| format!("{{\"Bytes\":{bytes}}}"),
+        );
+
+        std::fs::write(
+            dir.join("benchmark.json"),
+            format!(
+                "{{\"group_id\":\"{id}\",\"function_id\":null,\"value_str\":null,\"throughput\":{throughput},\"full_id\":\"{id}\",\"directory_name\":\"{directory_name}\",\"title\":\"{id}\"}}\n"
+            ),
+        )?;
+
+        std::fs::write(
+            dir.join("sample.json"),
+            format!("{{\"sampling_mode\":\"Flat\",\"iters\":{iters:?},\"times\":{times:?}}}\n"),
+        )?;
+
+        // Per iteration times of every sample
+        let mut per_iter: Vec<f64> = iters.iter().zip(&times).map(|(n, t)| t / n).collect();
+        per_iter.sort_by(f64::total_cmp);
+
+        let mean = per_iter.iter().sum::<f64>() / per_iter.len() as f64;
+        let std_dev = (per_iter.iter().map(|t| (t - mean).powi(2)).sum::<f64>()
+            / (per_iter.len() as f64 - 1.0).max(1.0))
+        .sqrt();
+        let median_time = median(&per_iter);
+
+        let mut deviations: Vec<f64> = per_iter.iter().map(|t| (t - median_time).abs()).collect();
+        deviations.sort_by(f64::total_cmp);
+
+        // Scaled to be consistent with the standard deviation, as criterion does
+        let median_abs_dev = median(&deviations) * 1.4826;
+
+        let mut out = std::io::BufWriter::new(std::fs::File::create(dir.join("estimates.json"))?);
+        write!(out, "{{\"mean\":")?;
+        write_estimate(&mut out, mean, std_dev, per_iter.len())?;
+        write!(out, ",\"median\":")?;
+        write_estimate(&mut out, median_time, std_dev, per_iter.len())?;
+        write!(out, ",\"median_abs_dev\":")?;
+        write_estimate(&mut out, median_abs_dev, 0.0, per_iter.len())?;
+        write!(out, ",\"slope\":null,\"std_dev\":")?;
+        write_estimate(&mut out, std_dev, 0.0, per_iter.len())?;
+        writeln!(out, "}}")?;
+        out.flush()
+    }
+}
+
+/// Get the median of the sorted `values`
+fn median(values: &[f64]) -> f64 {
+    let mid = values.len() / 2;
+
+    if values.len() % 2 == 0 {
+        f64::midpoint(values[mid - 1], values[mid])
+    } else {
+        values[mid]
+    }
+}
+
+/// Write a criterion estimate with a 95% confidence interval from the `std_dev` of the
+/// `count` samples
+#[allow(clippy::cast_precision_loss)]
+fn write_estimate(
+    out: &mut impl Write,
+    point: f64,
+    std_dev: f64,
+    count: usize,
+) -> std::io::Result<()> {
+    let standard_error = std_dev / (count as f64).sqrt();
+    let lower_bound = point - 1.96 * standard_error;
+    let upper_bound = point + 1.96 * standard_error;
+
+    write!(
+        out,
+        "{{\"confidence_interval\":{{\"confidence_level\":0.95,\"lower_bound\":{lower_bound:?},\"upper_bound\":{upper_bound:?}}},\"point_estimate\":{point:?},\"standard_error\":{standard_error:?}}}"
+    )
+}
@@ -0,0 +1,586 @@
+//! Implements the timer report printed by the profiler
+use std::io::Write;
+use std::path::Path;
+use std::sync::MutexGuard;
+
+use crate::alloc::allocations_of;
+use crate::category::write_categories;
+use crate::context_switch::context_switches_of;
+use crate::filter::TimerFilter;
+use crate::page_faults::page_faults_of;
+use crate::percentile::percentiles_of;
+use crate::render::{JsonRenderer, MarkdownRenderer, ReportRenderer, TextRenderer};
+use crate::user_counter::user_counters_of;
+use crate::{
+    calculate_os_frequency, calibration, rdtsc, timer_hash, AllocationStats, Calibration,
+    ContextSwitches, CpuInfo, IgnoreDecision, Percentiles, Profiler, ProfilerSnapshot,
+    ShortTimerPolicy, ThreadProfile, TimeUnit, Timer, REMAINING_TIME_LABEL,
+};
+
+/// The format of the printed timer report
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Aligned plain text columns
+    #[default]
+    Text,
+
+    /// A markdown table that can be pasted into GitHub issues and PRs
+    Markdown,
+
+    /// A JSON object for other tools to consume
+    Json,
+}
+
+/// The details of a timer read from every thread in the same pass as its accumulated
+/// time, so that they agree with it
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TimerDetails {
+    /// The number of distinct threads that hit the timer
+    threads: u64,
+
+    /// The thread that spent the most time in the timer
+    dominant_thread: usize,
+
+    /// The percent of the time of the timer spent on its dominant thread
+    dominant_thread_percent: f64,
+
+    /// The estimated percentiles of the hit durations, if the timer was sampled
+    percentiles: Option<Percentiles>,
+
+    /// The user counters of the timer, sorted by name
+    counters: Vec<(&'static str, u64)>,
+
+    /// The page faults during the hits of the timer, if they were recorded
+    page_faults: Option<u64>,
+
+    /// The allocations made by the timer, if any were attributed to it
+    allocations: Option<AllocationStats>,
+
+    /// The context switches during the hits of the timer, if they were recorded
+    context_switches: Option<ContextSwitches>,
+}
+
+/// The results of a single timer in the report
+#[derive(Debug, Default, Clone)]
+pub struct TimerReport {
+    /// The name of the timer
+    pub name: String,
+
+    /// The stable hash of the full timer name, see [`timer_hash`]
+    pub hash: u64,
+
+    /// Time (in cycles) spent in this timer (without child timers)
+    pub exclusive_time: u64,
+
+    /// Time (in cycles) spent in this timer (with child timers)
+    pub inclusive_time: u64,
+
+    /// Time (in seconds) spent in this timer (without child timers)
+    pub seconds: f64,
+
+    /// Number of times this timer was hit
+    pub hits: u64,
+
+    /// Number of distinct threads that hit this timer
+    pub threads: u64,
+
+    /// The thread that spent the most time (with child timers) in this timer
+    pub dominant_thread: usize,
+
+    /// The name of `dominant_thread`, if it was named
+    pub dominant_thread_name: Option<&'static str>,
+
+    /// Percent of the time (with child timers) of this timer spent on `dominant_thread`
+    pub dominant_thread_percent: f64,
+
+    /// The shortest single hit (in cycles, with child timers)
+    pub min_cycles: u64,
+
+    /// The longest single hit (in cycles, with child timers)
+    pub max_cycles: u64,
+
+    /// The standard deviation of the hits (in cycles, with child timers)
+    pub std_dev_cycles: f64,
+
+    /// The standard deviation of the hits relative to their mean
+    pub coefficient_of_variation: f64,
+
+    /// The category of the timer, if it was given one
+    pub category: Option<&'static str>,
+
+    /// The hit duration percentiles, if percentile recording is enabled
+    pub percentiles: Option<Percentiles>,
+
+    /// Number of bytes processed by this timer
+    pub bytes_processed: u64,
+
+    /// Percent of the total time spent in this timer (without child timers)
+    pub percent: f64,
+
+    /// Percent of the total time spent in this timer (with child timers), only set when
+    /// it differs from `percent`
+    pub inclusive_percent: Option<f64>,
+
+    /// Throughput of this timer in GBs/sec, if bytes were processed
+    pub throughput: Option<f64>,
+
+    /// The user counters of this timer added by `count!`, sorted by name
+    pub counters: Vec<(&'static str, u64)>,
+
+    /// The page faults of the process during the hits of this timer, if they were
+    /// recorded
+    pub page_faults: Option<u64>,
+
+    /// The allocations made by this timer (without its child timers), if the
+    /// [`crate::TrackingAllocator`] is installed
+    pub allocations: Option<AllocationStats>,
+
+    /// The context switches of the threads during the hits of this timer, if they were
+    /// recorded
+    pub context_switches: Option<ContextSwitches>,
+}
+
+/// The structured timer report given to a [`ReportRenderer`]
+#[derive(Debug, Default, Clone)]
+pub struct Report {
+    /// The calculated frequency (in Hz) of the timestamp counter
+    pub os_timer_freq: f64,
+
+    /// The calibration of the clock that produced `os_timer_freq`
+    pub calibration: Calibration,
+
+    /// The CPU that produced this report, if captured
+    pub cpu: Option<CpuInfo>,
+
+    /// Total time (in cycles) of all profiled threads
+    pub total_time: u64,
+
+    /// The results of every hit timer, sorted by most exclusive time first
+    pub timers: Vec<TimerReport>,
+
+    /// Time (in cycles) not spent in any timer
+    pub remainder: u64,
+
+    /// The percent of the total time below which timers were hidden from the report
+    pub min_percent: f64,
+
+    /// The number of timers hidden for being below `min_percent`
+    pub hidden_timers: usize,
+
+    /// Time (in cycles) spent in the hidden timers
+    pub hidden_time: u64,
+
+    /// The unit the times are displayed in by the text and markdown renderers
+    pub time_unit: TimeUnit,
+
+    /// The policy the short timers were ignored under
+    pub short_timer_policy: ShortTimerPolicy,
+
+    /// The number of timers whose later hits were ignored for being too short
+    pub ignored_timers: usize,
+
+    /// Time (in cycles) of the ignored short hits, left to their parent timers
+    pub ignored_time: u64,
+
+    /// Why and when each short timer was ignored, earliest first
+    pub ignore_decisions: Vec<IgnoreDecision>,
+}
+
+impl Report {
+    /// Hide the timers taking less than `min_percent` of the total time, keeping count
+    /// of how many were hidden and how much time they took
+    pub(crate) fn hide_below(&mut self, min_percent: f64) {
+        self.min_percent = min_percent;
+        if min_percent <= 0. {
+            return;
+        }
+
+        let (mut hidden_timers, mut hidden_time) = (0, 0u64);
+        self.timers.retain(|timer| {
+            if timer.percent >= min_percent {
+                return true;
+            }
+
+            hidden_timers += 1;
+            hidden_time = hidden_time.wrapping_add(timer.exclusive_time);
+            false
+        });
+
+        self.hidden_timers += hidden_timers;
+        self.hidden_time = self.hidden_time.wrapping_add(hidden_time);
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Print a basic percentage-based status of the timers state
+    pub fn print(&mut self) {
+        self.print_with_format(ReportFormat::Text);
+    }
+
+    /// Print the status of the timers state in the given [`ReportFormat`]
+    pub fn print_with_format(&mut self, format: ReportFormat) {
+        match format {
+            ReportFormat::Text => self.print_with_renderer(&TextRenderer {
+                color: self.color.enabled(),
+            }),
+            ReportFormat::Markdown => self.print_with_renderer(&MarkdownRenderer),
+            ReportFormat::Json => self.print_with_renderer(&JsonRenderer),
+        }
+    }
+
+    /// Print the status of the timers state using a custom [`ReportRenderer`]
+    pub fn print_with_renderer(&mut self, renderer: &impl ReportRenderer) {
+        let _ = self.print_with_renderer_to(renderer, &mut std::io::stderr().lock());
+    }
+
+    /// Write a basic percentage-based status of the timers state to `out`
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    pub fn print_to(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        self.print_with_renderer_to(&TextRenderer::default(), out)
+    }
+
+    /// Write the status of the timers state to `out` using a custom [`ReportRenderer`]
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    pub fn print_with_renderer_to(
+        &mut self,
+        renderer: &impl ReportRenderer,
+        out: &mut impl Write,
+    ) -> std::io::Result<()> {
+        // Timings are meaningless during a dry run, only show the discovered structure
+        if self.dry_run {
+            return self.write_call_tree(out);
+        }
+
+        let report = self.create_report();
+
+        renderer.render(&report, out)?;
+        write_categories(&report, out)?;
+
+        if self.record_exemplars {
+            self.write_exemplars(report.os_timer_freq, out)?;
+        }
+
+        if self.record_log2_histograms {
+            self.write_log2_histograms(report.os_timer_freq, out)?;
+        }
+
+        if self.record_callers {
+            self.write_callers(report.os_timer_freq, out)?;
+        }
+
+        self.write_chains(report.os_timer_freq, out)?;
+        self.write_metrics(out)?;
+
+        Ok(())
+    }
+
+    /// Stop the profiler and get the results of every hit timer across all threads,
+    /// sorted by most exclusive time first
+    pub fn report(&mut self) -> Vec<TimerReport> {
+        self.create_report().timers
+    }
+
+    /// Get the basic percentage-based status of the timers state as a string
+    pub fn report_string(&mut self) -> String {
+        self.create_report().to_string()
+    }
+
+    /// Stop the profiler and gather the structured [`Report`] of all threads
+    ///
+    /// Only the timers passing the `TIMELOOP_FILTER` environment variable are included
+    /// if it is set, see [`TimerFilter`].
+    pub fn create_report(&mut self) -> Report {
+        // Immediately stop the profiler's timer at the beginning of this function
+        self.stop_running_threads(rdtsc());
+
+        let (acc, total_time, details) = self.accumulate_timer_details();
+        let calibration = calibration();
+        let os_timer_freq = calibration.frequency;
+        let (mut timers, remainder) = self.timer_results(&acc, &details, total_time, os_timer_freq);
+
+        // Restrict the report to the timers of interest
+        if let Some(filter) = TimerFilter::from_env() {
+            timers.retain(|timer| filter.matches(&timer.name));
+        }
+
+        let (ignored_timers, ignored_time) = self.ignored_timers();
+        let mut report = Report {
+            os_timer_freq,
+            calibration,
+            cpu: self.captured_cpu_info(),
+            total_time,
+            timers,
+            remainder,
+            min_percent: 0.,
+            hidden_timers: 0,
+            hidden_time: 0,
+            time_unit: self.time_unit,
+            short_timer_policy: self.short_timer_policy,
+            ignored_timers,
+            ignored_time,
+            ignore_decisions: self.collect_ignore_decisions(),
+        };
+
+        // Move the measurement cost out of the timers and declutter the report of the
+        // timers too cheap to matter
+        report.subtract_overhead(self.overhead_cycles);
+        report.hide_below(self.min_percent);
+        report
+    }
+
+    /// Print the cost of every timer normalized to a single work item, given the number
+    /// of `items` processed during the profile
+    #[allow(clippy::cast_precision_loss)]
+    pub fn print_per_item(&mut self, items: u64) {
+        // Immediately stop the profiler's timer at the beginning of this function
+        self.stop_running_threads(rdtsc());
+
+        let (acc, total_time_cycles, details) = self.accumulate_timer_details();
+
+        let os_timer_freq = calculate_os_frequency();
+        let (results, other) = self.timer_results(&acc, &details, total_time_cycles, os_timer_freq);
+
+        let items_f64 = items.max(1) as f64;
+        let cycles_per_us = os_timer_freq / 1_000_000.0;
+
+        eprintln!(
+            "Items processed: {items} ({:.2} cycles/item, {:.3} us/item total)",
+            total_time_cycles as f64 / items_f64,
+            total_time_cycles as f64 / cycles_per_us / items_f64
+        );
+
+        let name_width = results
+            .iter()
+            .map(|result| result.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(REMAINING_TIME_LABEL.len())
+            .min(60);
+
+        eprintln!(
+            "{:<name_width$} | {:>10} | {:>14} | {:>12} | {:>12} | {:>7}",
+            "TIMER", "HITS/ITEM", "CYCLES/ITEM", "US/ITEM", "BYTES/ITEM", "PERCENT"
+        );
+
+        for result in &results {
+            let name = &result.name[..result.name.len().min(name_width)];
+            let cycles_per_item = result.exclusive_time as f64 / items_f64;
+
+            let bytes_per_item = if result.bytes_processed > 0 {
+                format!("{:.2}", result.bytes_processed as f64 / items_f64)
+            } else {
+                String::new()
+            };
+
+            eprintln!(
+                "{name:<name_width$} | {:>10.2} | {cycles_per_item:>14.2} | {:>12.3} | {bytes_per_item:>12} | {:>6.2}%",
+                result.hits as f64 / items_f64,
+                cycles_per_item / cycles_per_us,
+                result.percent
+            );
+        }
+
+        let cycles_per_item = other as f64 / items_f64;
+        eprintln!(
+            "{REMAINING_TIME_LABEL:<name_width$} | {:>10} | {cycles_per_item:>14.2} | {:>12.3} | {:>12} | {:>6.2}%",
+            "",
+            cycles_per_item / cycles_per_us,
+            "",
+            other as f64 / total_time_cycles as f64 * 100.
+        );
+    }
+
+    /// Print every timer along with the absolute and percent change of its exclusive time
+    /// compared to the baseline snapshot saved at `path` by [`Profiler::save`], see
+    /// [`ProfilerSnapshot::write_comparison`]
+    ///
+    /// # Errors
+    ///
+    /// * Failed to load the baseline snapshot from `path`
+    /// * Failed to write to stderr
+    pub fn print_vs_baseline(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        // Immediately stop the profiler's timer at the beginning of this function
+        self.stop_running_threads(rdtsc());
+
+        let baseline = ProfilerSnapshot::load(path)?;
+        let current = self.snapshot();
+
+        if let (Some(before), Some(after)) = (baseline.calibration(), current.calibration()) {
+            if before.differs_from(&after) {
+                eprintln!(
+                    "Warning: the baseline was calibrated at {:.0} Hz, now {:.0} Hz. Cycles may not be comparable.",
+                    before.frequency, after.frequency
+                );
+            }
+        }
+
+        baseline.write_comparison(&current, &mut std::io::stderr().lock())
+    }
+
+    /// Check if the timer at `index`, accumulated as `timer`, is part of the report
+    pub(crate) fn is_reported(&self, index: usize, timer: &Timer) -> bool {
+        // Timers that weren't hit are only reported if every registered timer is
+        timer.hits > 0 || self.exhaustive_timers.contains(self.timer_names[index])
+    }
+
+    /// Get the results of all hit timers, sorted by most exclusive time first, along with
+    /// the time not spent in any timer
+    ///
+    /// The `details` of every timer are those gathered along with the timers by
+    /// [`Profiler::accumulate_timer_details`].
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn timer_results(
+        &self,
+        acc: &[Timer; TIMERS],
+        details: &[TimerDetails],
+        total_time_cycles: u64,
+        os_timer_freq: f64,
+    ) -> (Vec<TimerReport>, u64) {
+        let mut other = total_time_cycles;
+        let mut results = Vec::new();
+
+        for (i, timer) in acc.iter().enumerate() {
+            let Timer {
+                inclusive_time,
+                exclusive_time,
+                hits,
+                bytes_processed,
+                min_cycles,
+                max_cycles,
+                ..
+            } = *timer;
+
+            if !self.is_reported(i, timer) {
+                continue;
+            }
+
+            // Timers measured across a reset or a snapshot may exceed the total time
+            other = other.saturating_sub(exclusive_time);
+            let percent = exclusive_time as f64 / total_time_cycles as f64 * 100.;
+
+            // Include the total time if it was included
+            let mut inclusive_percent = None;
+
+            if inclusive_time > 0 {
+                let total_time_percent = inclusive_time as f64 / total_time_cycles as f64 * 100.;
+
+                if (total_time_percent - percent).abs() >= 0.1 {
+                    inclusive_percent = Some(total_time_percent);
+                }
+            }
+
+            let mut throughput = None;
+            if bytes_processed > 0 {
+                const GIGABYTE: f64 = 1024.0 * 1024.0 * 1024.0;
+
+                let time = inclusive_time as f64 / os_timer_freq;
+
+                let bytes_per_sec = bytes_processed as f64 / time;
+                throughput = Some(bytes_per_sec / GIGABYTE);
+            }
+
+            let details = details[i].clone();
+
+            results.push(TimerReport {
+                name: self.timer_label(i),
+                hash: timer_hash(self.timer_names[i]),
+                exclusive_time,
+                inclusive_time,
+                seconds: exclusive_time as f64 / os_timer_freq,
+                hits,
+                threads: details.threads,
+                dominant_thread: details.dominant_thread,
+                dominant_thread_name: self.thread_name(details.dominant_thread),
+                dominant_thread_percent: details.dominant_thread_percent,
+                min_cycles,
+                max_cycles,
+                std_dev_cycles: timer.std_dev_cycles(),
+                coefficient_of_variation: timer.coefficient_of_variation(),
+                category: self.category(self.timer_names[i]),
+                percentiles: details.percentiles,
+                bytes_processed,
+                percent,
+                inclusive_percent,
+                throughput,
+                counters: details.counters,
+                page_faults: details.page_faults,
+                allocations: details.allocations,
+                context_switches: details.context_switches,
+            });
+        }
+
+        results.sort_by_key(|timer| std::cmp::Reverse(timer.exclusive_time));
+
+        (results, other)
+    }
+
+    /// Gather the details of the timer at `index` from the already locked `threads`
+    pub(crate) fn timer_details(
+        &self,
+        threads: &[MutexGuard<ThreadProfile<TIMERS>>],
+        index: usize,
+    ) -> TimerDetails {
+        let (thread_count, dominant_thread, dominant_thread_percent) =
+            thread_affinity(threads, index);
+        let name = self.timer_names[index];
+
+        TimerDetails {
+            threads: thread_count,
+            dominant_thread,
+            dominant_thread_percent,
+            percentiles: percentiles_of(threads, name),
+            counters: user_counters_of(threads, name),
+            page_faults: page_faults_of(threads, name),
+            allocations: allocations_of(threads, name),
+            context_switches: context_switches_of(threads, name),
+        }
+    }
+}
+
+/// Get the number of distinct `threads` that hit the timer at `index`, the thread that
+/// spent the most time in it, and the percent of its time spent on that thread
+#[allow(clippy::cast_precision_loss)]
+fn thread_affinity<const TIMERS: usize>(
+    threads: &[MutexGuard<ThreadProfile<TIMERS>>],
+    index: usize,
+) -> (u64, usize, f64) {
+    let mut threads_hit = 0;
+    let mut total_time = 0u64;
+    let mut total_hits = 0u64;
+    let mut dominant: Option<(usize, Timer)> = None;
+
+    for (thread_id, thread) in threads.iter().enumerate() {
+        let timer = thread.timers[index];
+        if timer.hits == 0 {
+            continue;
+        }
+
+        threads_hit += 1;
+        total_time = total_time.wrapping_add(timer.inclusive_time);
+        total_hits += timer.hits;
+
+        // Break ties of timers without measurable time by their hits
+        let key = |timer: &Timer| (timer.inclusive_time, timer.hits);
+        if dominant.is_none_or(|(_, dominant)| key(&timer) > key(&dominant)) {
+            dominant = Some((thread_id, timer));
+        }
+    }
+
+    let Some((dominant_thread, dominant)) = dominant else {
+        return (0, 0, 0.);
+    };
+
+    let percent = if total_time > 0 {
+        dominant.inclusive_time as f64 / total_time as f64 * 100.
+    } else {
+        dominant.hits as f64 / total_hits as f64 * 100.
+    };
+
+    (threads_hit, dominant_thread, percent)
+}
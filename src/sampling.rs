@@ -0,0 +1,66 @@
+//! Implements the sampled timers, only recording one of every N hits
+use std::sync::MutexGuard;
+
+use crate::{OpenTimer, Profiler, ThreadProfile, Timer};
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Mark `timer` as only recording one of every `rate` hits, scaling its hits, time
+    /// and bytes by `rate` in the accumulated timers
+    pub fn set_sample_rate(&mut self, timer: &'static str, rate: u64) {
+        self.sample_rates.insert(timer, rate.max(1));
+    }
+
+    /// Get the rate `timer` is sampled at, 1 if every hit is recorded
+    #[must_use]
+    pub fn sample_rate(&self, timer: &str) -> u64 {
+        self.sample_rates.get(timer).copied().unwrap_or(1)
+    }
+
+    /// Move the estimated time of the hits of the sampled `open` timer that weren't
+    /// recorded out of its parent, as the parent includes their time
+    pub(crate) fn record_sampled_hit(
+        &self,
+        thread: &mut ThreadProfile<TIMERS>,
+        open: &OpenTimer,
+        elapsed: u64,
+    ) {
+        let rate = self.sample_rate(open.timer);
+        let Some(parent) = open.parent.filter(|_| rate > 1) else {
+            return;
+        };
+
+        let unrecorded = thread.sampled_parent_cycles.entry(parent).or_default();
+        *unrecorded = unrecorded.wrapping_add(elapsed.wrapping_mul(rate - 1));
+    }
+
+    /// Scale the sampled timers in the accumulated `timers` by their rate, removing
+    /// their estimated unrecorded time from their parents on the already locked `threads`
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn scale_sampled_timers(
+        &self,
+        timers: &mut [Timer; TIMERS],
+        threads: &[MutexGuard<ThreadProfile<TIMERS>>],
+    ) {
+        for (timer, rate) in &self.sample_rates {
+            let Some(index) = self.timer_name_to_index.get(timer) else {
+                continue;
+            };
+
+            let timer = &mut timers[*index as usize];
+            timer.hits = timer.hits.wrapping_mul(*rate);
+            timer.exclusive_time = timer.exclusive_time.wrapping_mul(*rate);
+            timer.inclusive_time = timer.inclusive_time.wrapping_mul(*rate);
+            timer.bytes_processed = timer.bytes_processed.wrapping_mul(*rate);
+            timer.m2_cycles *= *rate as f64;
+        }
+
+        for thread in threads {
+            for (parent, unrecorded) in &thread.sampled_parent_cycles {
+                if let Some(index) = self.timer_name_to_index.get(parent) {
+                    let parent = &mut timers[*index as usize];
+                    parent.exclusive_time = parent.exclusive_time.saturating_sub(*unrecorded);
+                }
+            }
+        }
+    }
+}
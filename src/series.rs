@@ -0,0 +1,157 @@
+//! Implements the time series of the timers in windows aligned to the wall clock
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{calculate_os_frequency, rdtsc, Profiler, ThreadProfile};
+
+/// Number of windows of the series kept for each thread, dropping the oldest first
+const MAX_SERIES_WINDOWS: usize = 4096;
+
+/// The hits and time of a timer on a thread during a single window of the series
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SeriesPoint {
+    /// Number of hits that ended during the window
+    pub hits: u64,
+
+    /// Total time (in cycles) of the hits that ended during the window
+    pub cycles: u64,
+}
+
+/// Maps the timestamps of the timers to the windows of the series
+#[doc(hidden)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SeriesClock {
+    /// Length (in nanoseconds) of each window, 0 if the series isn't recorded
+    pub window_nanos: u64,
+
+    /// Length (in cycles) of each window
+    pub window_cycles: u64,
+
+    /// The timestamp (in cycles) of a window boundary
+    pub boundary_cycles: u64,
+
+    /// The UNIX time (in nanoseconds) of the window boundary at `boundary_cycles`, a
+    /// multiple of `window_nanos`
+    pub boundary_nanos: u64,
+}
+
+impl SeriesClock {
+    /// An unset clock, usable in `const` contexts
+    pub const UNSET: SeriesClock = SeriesClock {
+        window_nanos: 0,
+        window_cycles: 0,
+        boundary_cycles: 0,
+        boundary_nanos: 0,
+    };
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Record the hits and time of every timer for each thread in consecutive windows of
+    /// `window`, for [`Profiler::write_series_csv`]. A zero `window` turns it off.
+    ///
+    /// The windows start at multiples of `window` since the UNIX epoch, so the windows of
+    /// all threads, and of other processes using the same `window`, share the same
+    /// wall-clock boundaries and a spike on one thread can be lined up with a stall on
+    /// another. A hit counts towards the window it ended in.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn record_series(&mut self, window: Duration) {
+        for thread in self.thread_profiles_mut() {
+            thread.series.clear();
+        }
+
+        let window_nanos = window.as_nanos() as u64;
+        if window_nanos == 0 {
+            self.series_clock = SeriesClock::UNSET;
+            return;
+        }
+
+        let cycles_per_nano = calculate_os_frequency() / 1_000_000_000.0;
+
+        // Read both clocks together to find the last boundary on the timestamp clock
+        let now_cycles = rdtsc();
+        let now_nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let since_boundary = now_nanos % window_nanos;
+
+        self.series_clock = SeriesClock {
+            window_nanos,
+            window_cycles: ((window_nanos as f64 * cycles_per_nano) as u64).max(1),
+            boundary_cycles: now_cycles
+                .wrapping_sub((since_boundary as f64 * cycles_per_nano) as u64),
+            boundary_nanos: now_nanos - since_boundary,
+        };
+    }
+
+    /// Add the hit of `timer` taking `elapsed` cycles and ending at `stop_time` to the
+    /// series of the locked thread, if the series is recorded
+    pub(crate) fn record_series_hit(
+        &self,
+        thread: &mut ThreadProfile<TIMERS>,
+        timer: &'static str,
+        stop_time: u64,
+        elapsed: u64,
+    ) {
+        let clock = self.series_clock;
+        if clock.window_cycles == 0 {
+            return;
+        }
+
+        let window = stop_time.saturating_sub(clock.boundary_cycles) / clock.window_cycles;
+
+        let series = &mut thread.series;
+        let point = series.entry(window).or_default().entry(timer).or_default();
+        point.hits += 1;
+        point.cycles = point.cycles.wrapping_add(elapsed);
+
+        if series.len() > MAX_SERIES_WINDOWS {
+            series.pop_first();
+        }
+    }
+
+    /// Write the series recorded by [`Profiler::record_series`] as CSV with a row for
+    /// every timer hit on a thread during a window: the UNIX time (in nanoseconds) the
+    /// window started at, the thread, the timer, its hits and its time (in cycles)
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    pub fn write_series_csv(&self, mut out: impl Write) -> std::io::Result<()> {
+        let clock = self.series_clock;
+
+        // Interleave the threads by window
+        let mut rows = BTreeMap::new();
+        for (thread_id, thread) in self.thread_profiles().enumerate() {
+            for (window, points) in &thread.series {
+                for (timer, point) in points {
+                    rows.insert((*window, thread_id, *timer), *point);
+                }
+            }
+        }
+
+        writeln!(out, "window_start_ns,thread,timer,hits,cycles")?;
+
+        for ((window, thread_id, timer), point) in rows {
+            let window_start = clock
+                .boundary_nanos
+                .wrapping_add(window.wrapping_mul(clock.window_nanos));
+
+            // Quote the timer names as they may contain commas
+            writeln!(
+                out,
+                "{window_start},{thread_id},\"{}\",{},{}",
+                timer.replace('"', "\"\""),
+                point.hits,
+                point.cycles
+            )?;
+        }
+
+        out.flush()
+    }
+}
@@ -0,0 +1,346 @@
+//! Implements ignoring the timers that are repeatedly too short to measure meaningfully
+use std::collections::BTreeMap;
+
+use crate::{OpenTimer, Profiler, ThreadProfile, Timer};
+
+/// The environment variable overriding the [`ShortTimerPolicy`] when the profiler starts
+///
+/// Either `off`, or the threshold in cycles optionally followed by the streak, such as
+/// `500,10`.
+pub const SHORT_TIMERS_ENV_VAR: &str = "TIMELOOP_SHORT_TIMERS";
+
+/// An ignored timer is recorded again once a streak of its hits averages this many times
+/// the threshold
+const REINCLUDE_FACTOR: u64 = 2;
+
+/// When to stop recording a timer whose hits are consistently too short to measure
+/// meaningfully, such as a scope around a few instructions in a hot loop
+///
+/// Once `streak` consecutive hits of a timer on a thread took less than
+/// `threshold_cycles`, its later hits on that thread are ignored: their time is left to
+/// the parent timer and the report shows how much time the ignored hits took. If the
+/// hits of an ignored timer later average twice the threshold over a streak, such as for
+/// an input dependent cost, the timer is recorded again along with its ignored hits.
+/// Hits with child timers are always recorded, as their children already took their
+/// time out of them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ShortTimerPolicy {
+    /// Hits shorter than this (in cycles) count towards the streak
+    pub threshold_cycles: u64,
+
+    /// Number of consecutive short hits after which the timer is ignored
+    pub streak: u32,
+
+    /// Whether short timers are ignored at all
+    pub enabled: bool,
+}
+
+impl ShortTimerPolicy {
+    /// The default policy, which doesn't ignore any timer but uses a 500 cycle threshold
+    /// and a streak of 10 hits once enabled
+    pub const DEFAULT: ShortTimerPolicy = ShortTimerPolicy {
+        threshold_cycles: 500,
+        streak: 10,
+        enabled: false,
+    };
+
+    /// Ignore the timers after `streak` consecutive hits shorter than `threshold_cycles`
+    #[must_use]
+    pub const fn new(threshold_cycles: u64, streak: u32) -> Self {
+        ShortTimerPolicy {
+            threshold_cycles,
+            streak,
+            enabled: true,
+        }
+    }
+
+    /// Get the policy set by the `TIMELOOP_SHORT_TIMERS` environment variable, if it is
+    /// set to a valid policy
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var(SHORT_TIMERS_ENV_VAR).ok()?;
+        let value = value.trim();
+
+        if value.eq_ignore_ascii_case("off") {
+            return Some(ShortTimerPolicy::DEFAULT);
+        }
+
+        let (threshold, streak) = value.split_once(',').unwrap_or((value, ""));
+        let threshold = threshold.trim().parse().ok();
+        let streak = match streak.trim() {
+            "" => Some(ShortTimerPolicy::DEFAULT.streak),
+            streak => streak.parse().ok(),
+        };
+
+        if let (Some(threshold), Some(streak)) = (threshold, streak) {
+            Some(ShortTimerPolicy::new(threshold, streak))
+        } else {
+            eprintln!("Ignoring invalid {SHORT_TIMERS_ENV_VAR}: {value:?}");
+            None
+        }
+    }
+}
+
+/// The current streak of consecutive short hits of a timer on a thread
+#[doc(hidden)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ShortStreak {
+    /// The number of consecutive short hits
+    pub hits: u32,
+
+    /// Total time (in cycles) of the consecutive short hits
+    pub cycles: u64,
+}
+
+impl ShortStreak {
+    /// An empty streak, usable in `const` contexts
+    pub const EMPTY: ShortStreak = ShortStreak { hits: 0, cycles: 0 };
+}
+
+/// The hits of an ignored timer on a thread, kept to be merged back into the timer if it
+/// is re-included
+#[doc(hidden)]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct IgnoredHits {
+    /// All of the ignored hits
+    pub timer: Timer,
+
+    /// Time (in cycles) of the ignored hits left to each parent timer
+    pub parents: BTreeMap<&'static str, u64>,
+
+    /// The number of ignored hits since the last check for re-inclusion
+    pub recent_hits: u32,
+
+    /// Total time (in cycles) of the ignored hits since the last check for re-inclusion
+    pub recent_cycles: u64,
+}
+
+/// Why and when a timer was ignored on a thread under the [`ShortTimerPolicy`], shown in
+/// the report header
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IgnoreDecision {
+    /// The ignored timer
+    pub timer: &'static str,
+
+    /// The thread slot the timer was ignored on
+    pub thread_id: usize,
+
+    /// The number of hits of the timer on the thread when it was ignored
+    pub hits: u64,
+
+    /// Time (in cycles) the thread had been profiled when the timer was ignored
+    pub thread_time: u64,
+
+    /// The average (in cycles) of the short hits in the streak
+    pub avg_cycles: u64,
+
+    /// The number of consecutive short hits that got the timer ignored
+    pub streak: u32,
+}
+
+impl Default for ShortTimerPolicy {
+    fn default() -> Self {
+        ShortTimerPolicy::DEFAULT
+    }
+}
+
+impl<const TIMERS: usize> ThreadProfile<TIMERS> {
+    /// Record why and when `timer` at `index` got ignored on this thread slot at
+    /// `thread_id` after the current hit
+    fn record_ignore_decision(
+        &mut self,
+        thread_id: usize,
+        timer: &'static str,
+        index: usize,
+        streak: ShortStreak,
+    ) {
+        let decision = IgnoreDecision {
+            timer,
+            thread_id,
+            // The current hit is recorded once this returns
+            hits: self.timers[index].hits + 1,
+            thread_time: self.elapsed(),
+            avg_cycles: streak.cycles / u64::from(streak.hits.max(1)),
+            streak: streak.hits,
+        };
+
+        self.ignore_decisions.insert(timer, decision);
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Use the given [`ShortTimerPolicy`], such as when creating the static profiler
+    #[must_use]
+    pub const fn with_short_timer_policy(mut self, policy: ShortTimerPolicy) -> Self {
+        self.short_timer_policy = policy;
+        self
+    }
+
+    /// Override the [`ShortTimerPolicy`] with the one set by the `TIMELOOP_SHORT_TIMERS`
+    /// environment variable, if it is set
+    #[doc(hidden)]
+    pub fn load_short_timer_policy_from_env(&mut self) {
+        if let Some(policy) = ShortTimerPolicy::from_env() {
+            self.short_timer_policy = policy;
+        }
+    }
+
+    /// Always record `timer`, even if its hits are short enough for the
+    /// [`ShortTimerPolicy`] to ignore it, such as for a tiny but important fast path
+    pub fn pin_timer(&mut self, timer: &'static str) {
+        self.pinned_timers.insert(timer);
+    }
+
+    /// Check if `timer` was pinned by [`Profiler::pin_timer`]
+    #[must_use]
+    pub fn is_pinned(&self, timer: &str) -> bool {
+        self.pinned_timers.contains(timer)
+    }
+
+    /// Check if the hit of the `open` timer taking `elapsed` cycles on the given locked
+    /// thread should be ignored under the [`ShortTimerPolicy`], counting its time as
+    /// ignored if so
+    pub(crate) fn ignore_short_hit(
+        &self,
+        thread_id: usize,
+        thread: &mut ThreadProfile<TIMERS>,
+        open: &OpenTimer,
+        elapsed: u64,
+    ) -> bool {
+        let timer = open.timer;
+        let policy = self.short_timer_policy;
+        if !policy.enabled || self.pinned_timers.contains(timer) {
+            return false;
+        }
+
+        // Its children already removed their time from this hit, which can't be handed to
+        // the parent as a whole, so hits with children are always recorded
+        if thread.nested_hits != open.nested_hits {
+            return false;
+        }
+
+        let index = self.timer_index(timer);
+        let streak = &mut thread.short_streaks[index];
+
+        if streak.hits >= policy.streak {
+            self.ignore_hit(thread, open, index, elapsed);
+            return true;
+        }
+
+        if elapsed >= policy.threshold_cycles {
+            *streak = ShortStreak::EMPTY;
+            return false;
+        }
+
+        streak.hits += 1;
+        streak.cycles = streak.cycles.wrapping_add(elapsed);
+
+        // This hit completes the streak, so the following hits are ignored
+        if streak.hits == policy.streak {
+            let streak = *streak;
+            thread.record_ignore_decision(thread_id, timer, index, streak);
+        }
+
+        false
+    }
+
+    /// Keep the ignored hit of the `open` timer at `index` taking `elapsed` cycles,
+    /// re-including the timer if its recent hits are no longer short
+    fn ignore_hit(
+        &self,
+        thread: &mut ThreadProfile<TIMERS>,
+        open: &OpenTimer,
+        index: usize,
+        elapsed: u64,
+    ) {
+        thread.ignored_cycles = thread.ignored_cycles.wrapping_add(elapsed);
+
+        let policy = self.short_timer_policy;
+        let ignored = thread.ignored_hits.entry(open.timer).or_default();
+        ignored.timer.record_hit(elapsed);
+        if let Some(parent) = open.parent {
+            let cycles = ignored.parents.entry(parent).or_default();
+            *cycles = cycles.wrapping_add(elapsed);
+        }
+
+        ignored.recent_hits += 1;
+        ignored.recent_cycles = ignored.recent_cycles.wrapping_add(elapsed);
+        if ignored.recent_hits < policy.streak {
+            return;
+        }
+
+        let average = ignored.recent_cycles / u64::from(ignored.recent_hits);
+        ignored.recent_hits = 0;
+        ignored.recent_cycles = 0;
+
+        if average >= policy.threshold_cycles.saturating_mul(REINCLUDE_FACTOR) {
+            self.reinclude_timer(thread, open.timer, index);
+        }
+    }
+
+    /// Record `timer` at `index` on the locked thread again, merging its ignored hits back
+    /// into it and taking their time back from the parent timers
+    fn reinclude_timer(
+        &self,
+        thread: &mut ThreadProfile<TIMERS>,
+        timer: &'static str,
+        index: usize,
+    ) {
+        thread.short_streaks[index] = ShortStreak::EMPTY;
+        thread.ignore_decisions.remove(timer);
+
+        let Some(ignored) = thread.ignored_hits.remove(timer) else {
+            return;
+        };
+
+        thread.timers[index] = thread.timers[index] + ignored.timer;
+
+        for (parent, cycles) in ignored.parents {
+            let parent = &mut thread.timers[self.timer_index(parent)];
+            parent.exclusive_time = parent.exclusive_time.wrapping_sub(cycles);
+        }
+
+        thread.ignored_cycles = thread
+            .ignored_cycles
+            .wrapping_sub(ignored.timer.exclusive_time);
+    }
+
+    /// Get the decisions of every ignored timer across all threads, earliest first
+    pub(crate) fn collect_ignore_decisions(&self) -> Vec<IgnoreDecision> {
+        let mut decisions: Vec<IgnoreDecision> = self
+            .thread_profiles()
+            .flat_map(|thread| {
+                thread
+                    .ignore_decisions
+                    .values()
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        decisions.sort_by_key(|decision| (decision.thread_time, decision.thread_id));
+        decisions
+    }
+
+    /// Get the number of timers ignored on any thread and the total time (in cycles)
+    /// their ignored hits took
+    pub(crate) fn ignored_timers(&self) -> (usize, u64) {
+        if !self.short_timer_policy.enabled {
+            return (0, 0);
+        }
+
+        let streak = self.short_timer_policy.streak;
+        let mut ignored = [false; TIMERS];
+        let mut time = 0u64;
+        for thread in self.thread_profiles() {
+            for (ignored, streaks) in ignored.iter_mut().zip(thread.short_streaks.iter()) {
+                *ignored |= streaks.hits >= streak;
+            }
+
+            time = time.wrapping_add(thread.ignored_cycles);
+        }
+
+        let timers = ignored.iter().filter(|ignored| **ignored).count();
+        (timers, time)
+    }
+}
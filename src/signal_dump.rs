@@ -0,0 +1,78 @@
+//! Implements dumping the report on demand when the process receives a signal
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
+
+use crate::periodic::{print_text_report, write_json_report};
+use crate::{JsonRenderer, Profiler, ProfilerCell, ReportRenderer};
+
+/// `SIGUSR1` from `<signal.h>`
+const SIGUSR1: i32 = 10;
+
+/// `SIGUSR2` from `<signal.h>`
+const SIGUSR2: i32 = 12;
+
+/// `SIG_ERR` from `<signal.h>`
+const SIG_ERR: usize = usize::MAX;
+
+/// How often the dumping thread checks for a received signal
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The last dump signal received and not yet handled, 0 if none
+static PENDING_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+extern "C" {
+    fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+}
+
+/// Remember the received signal for the dumping thread. Only does what is safe to do
+/// inside of a signal handler.
+extern "C" fn on_dump_signal(signum: i32) {
+    PENDING_SIGNAL.store(signum, Ordering::Relaxed);
+}
+
+/// Dump the report of `profiler` whenever the process receives `SIGUSR1` or `SIGUSR2`,
+/// such as to inspect a stuck process without killing it
+///
+/// `SIGUSR1` prints the report as text to stderr. `SIGUSR2` writes the report as JSON
+/// to `path`, or to stderr if no path is given. The report is written by a background
+/// thread checking for the signals every 100ms, as the signal handler itself can't
+/// safely write the report. Running threads are not stopped.
+///
+/// # Errors
+///
+/// * Failed to install the signal handlers
+/// * Failed to spawn the thread
+pub fn install_signal_dump<const THREADS: usize, const TIMERS: usize>(
+    path: Option<PathBuf>,
+    profiler: &'static ProfilerCell<Profiler<THREADS, TIMERS>>,
+) -> std::io::Result<()> {
+    for signum in [SIGUSR1, SIGUSR2] {
+        if unsafe { signal(signum, on_dump_signal) } == SIG_ERR {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    std::thread::Builder::new()
+        .name("timeloop-signal-dump".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(POLL_INTERVAL);
+
+            let signum = PENDING_SIGNAL.swap(0, Ordering::Relaxed);
+            if signum == 0 {
+                continue;
+            }
+
+            let report = profiler.report();
+
+            match (signum, &path) {
+                (SIGUSR2, Some(path)) => write_json_report(&report, path),
+                (SIGUSR2, None) => {
+                    let _ = JsonRenderer.render(&report, &mut std::io::stderr().lock());
+                }
+                _ => print_text_report(profiler, &report),
+            }
+        })?;
+
+    Ok(())
+}
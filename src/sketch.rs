@@ -0,0 +1,259 @@
+//! Implements the per timer quantile sketches
+use std::time::Duration;
+
+use crate::{calculate_os_frequency, rdtsc, Profiler, ThreadProfile};
+
+/// Default relative accuracy of the quantiles returned by a [`DDSketch`]
+const RELATIVE_ACCURACY: f64 = 0.01;
+
+/// Maximum number of bins kept by a [`DDSketch`] before the lowest bins are collapsed
+const MAX_BINS: usize = 2048;
+
+/// A `DDSketch` quantile sketch with bounded memory and bounded relative error
+///
+/// Every returned quantile is within the relative accuracy of the true value, as long
+/// as the lowest bins have not been collapsed.
+#[derive(Debug, Clone)]
+pub struct DDSketch {
+    /// The natural log of the bin growth factor `(1 + a) / (1 - a)`
+    ln_gamma: f64,
+
+    /// Counts of the logarithmically sized bins starting at `offset`
+    bins: Vec<u64>,
+
+    /// The index of the first bin in `bins`
+    offset: i32,
+
+    /// Number of added values of zero, which have no bin
+    zero_count: u64,
+
+    /// Total number of added values
+    count: u64,
+
+    /// The smallest added value
+    min: u64,
+
+    /// The largest added value
+    max: u64,
+}
+
+impl Default for DDSketch {
+    fn default() -> Self {
+        Self::with_accuracy(RELATIVE_ACCURACY)
+    }
+}
+
+impl DDSketch {
+    /// Create an empty sketch with the given relative accuracy (for example `0.01`)
+    #[must_use]
+    pub fn with_accuracy(relative_accuracy: f64) -> Self {
+        let gamma = (1.0 + relative_accuracy) / (1.0 - relative_accuracy);
+
+        Self {
+            ln_gamma: gamma.ln(),
+            bins: Vec::new(),
+            offset: 0,
+            zero_count: 0,
+            count: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    /// Add a single `value` to the sketch
+    pub fn add(&mut self, value: u64) {
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+
+        if value == 0 {
+            self.zero_count += 1;
+        } else {
+            self.add_to_bin(self.bin_index(value), 1);
+        }
+    }
+
+    /// Add all of the values of `other` to this sketch
+    ///
+    /// # Panics
+    ///
+    /// * The sketches were created with a different relative accuracy
+    pub fn merge(&mut self, other: &DDSketch) {
+        assert!(
+            (self.ln_gamma - other.ln_gamma).abs() < f64::EPSILON,
+            "Cannot merge sketches of different accuracy"
+        );
+
+        self.count += other.count;
+        self.zero_count += other.zero_count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+
+        for (index, count) in (other.offset..).zip(&other.bins) {
+            if *count > 0 {
+                self.add_to_bin(index, *count);
+            }
+        }
+    }
+
+    /// The number of values added to this sketch
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Get the value at quantile `q` (between `0.0` and `1.0`)
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn quantile(&self, q: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = (q.clamp(0.0, 1.0) * (self.count - 1) as f64) as u64;
+        if rank < self.zero_count {
+            return Some(0);
+        }
+
+        let mut seen = self.zero_count;
+        for (index, count) in (self.offset..).zip(&self.bins) {
+            seen += count;
+
+            if seen > rank {
+                // The middle of the bin is within the relative accuracy of every value in it
+                let gamma = self.ln_gamma.exp();
+                let value = (f64::from(index) * self.ln_gamma).exp() * 2.0 / (gamma + 1.0);
+                return Some((value as u64).clamp(self.min, self.max));
+            }
+        }
+
+        Some(self.max)
+    }
+
+    /// Get the bin index holding `value`
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn bin_index(&self, value: u64) -> i32 {
+        ((value as f64).ln() / self.ln_gamma).ceil() as i32
+    }
+
+    /// Add `count` values to the bin at `index`, collapsing the lowest bins if the
+    /// sketch grows past [`MAX_BINS`]
+    #[allow(
+        clippy::cast_sign_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap
+    )]
+    fn add_to_bin(&mut self, index: i32, count: u64) {
+        if self.bins.is_empty() {
+            self.offset = index;
+        }
+
+        if index < self.offset {
+            let missing = (self.offset - index) as usize;
+            self.bins.splice(0..0, std::iter::repeat_n(0, missing));
+            self.offset = index;
+        }
+
+        let position = (index - self.offset) as usize;
+        if position >= self.bins.len() {
+            self.bins.resize(position + 1, 0);
+        }
+
+        self.bins[position] += count;
+
+        if self.bins.len() > MAX_BINS {
+            let excess = self.bins.len() - MAX_BINS;
+            let collapsed: u64 = self.bins.drain(..excess).sum();
+            self.bins[0] += collapsed;
+            self.offset += excess as i32;
+        }
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Track the distribution of the time spent in `timer` (with child timers) in a
+    /// [`DDSketch`] to report its quantiles
+    pub fn enable_sketch(&mut self, timer: &'static str) {
+        self.sketched_timers.insert(timer);
+    }
+
+    /// Add the `elapsed` time of a hit of `timer` to its sketch, if it is tracked
+    pub(crate) fn record_sketch(
+        &self,
+        thread: &mut ThreadProfile<TIMERS>,
+        timer: &'static str,
+        elapsed: u64,
+    ) {
+        if self.sketched_timers.is_empty() || !self.sketched_timers.contains(timer) {
+            return;
+        }
+
+        thread.sketches.entry(timer).or_default().add(elapsed);
+    }
+
+    /// Get the sketch of `timer` (in cycles) merged across all threads
+    #[must_use]
+    pub fn sketch(&self, timer: &'static str) -> Option<DDSketch> {
+        let mut result: Option<DDSketch> = None;
+
+        for thread in self.thread_profiles() {
+            let Some(sketch) = thread.sketches.get(timer) else {
+                continue;
+            };
+
+            match result.as_mut() {
+                Some(result) => result.merge(sketch),
+                None => result = Some(sketch.clone()),
+            }
+        }
+
+        result
+    }
+
+    /// Print the p50, p90, p99 and p99.9 time per hit of every sketched timer
+    #[allow(clippy::cast_precision_loss)]
+    pub fn print_quantiles(&mut self) {
+        self.stop_running_threads(rdtsc());
+
+        let os_timer_freq = calculate_os_frequency();
+
+        let name_width = self
+            .sketched_timers
+            .iter()
+            .map(|timer| timer.len())
+            .max()
+            .unwrap_or(0)
+            .max("TIMER".len());
+
+        eprintln!(
+            "{:<name_width$} | {:>10} | {:>10} | {:>10} | {:>10} | {:>10} | {:>10}",
+            "TIMER", "HITS", "P50", "P90", "P99", "P99.9", "MAX"
+        );
+
+        for timer in &self.sketched_timers {
+            let Some(sketch) = self.sketch(timer) else {
+                continue;
+            };
+
+            let columns = [0.5, 0.9, 0.99, 0.999, 1.0].map(|q| {
+                let cycles = sketch.quantile(q).unwrap_or(0);
+                let time = Duration::from_secs_f64(cycles as f64 / os_timer_freq);
+                format!("{time:.2?}")
+            });
+
+            eprintln!(
+                "{timer:<name_width$} | {:>10} | {:>10} | {:>10} | {:>10} | {:>10} | {:>10}",
+                sketch.count(),
+                columns[0],
+                columns[1],
+                columns[2],
+                columns[3],
+                columns[4]
+            );
+        }
+    }
+}
@@ -0,0 +1,425 @@
+//! Implements an owned snapshot of the profiler state and its binary format
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::{
+    calibration, Calibration, ClockSource, CpuCache, CpuInfo, GitInfo, Profiler, Report, Timer,
+};
+
+/// Magic bytes at the start of a saved snapshot
+const SNAPSHOT_MAGIC: &[u8; 8] = b"TIMELOOP";
+
+/// Version of the saved snapshot format
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// An owned copy of the timers accumulated across all threads at one point in time
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfilerSnapshot {
+    /// Total time (in cycles) of all profiled threads
+    pub total_time: u64,
+
+    /// The calibrated frequency (in Hz) of the clock, 0 if unknown
+    pub tsc_frequency: u64,
+
+    /// The estimated error (in Hz) of `tsc_frequency`
+    pub tsc_frequency_error: u64,
+
+    /// The CPU that produced this snapshot, if captured
+    pub cpu: Option<CpuInfo>,
+
+    /// The git commit of the program that produced this snapshot, if captured
+    pub git: Option<GitInfo>,
+
+    /// The accumulated timers that were hit, by timer name
+    pub timers: BTreeMap<String, Timer>,
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Take a snapshot of the current state of the timers
+    ///
+    /// Running threads are not stopped, so this can be taken while profiling.
+    #[must_use]
+    pub fn snapshot(&self) -> ProfilerSnapshot {
+        // Calibrate before reading the timers to keep a first calibration out of the
+        // time measured after this snapshot
+        let calibration = calibration();
+        let (acc, total_time) = self.accumulate_timers();
+
+        let timers = acc
+            .iter()
+            .enumerate()
+            .take(self.next_index as usize)
+            .filter(|(_, timer)| timer.hits > 0)
+            .map(|(index, timer)| (self.timer_names[index].to_string(), *timer))
+            .collect();
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        ProfilerSnapshot {
+            total_time,
+            tsc_frequency: calibration.frequency.round() as u64,
+            tsc_frequency_error: calibration.error.ceil() as u64,
+            cpu: self.captured_cpu_info(),
+            git: self.captured_git_info(),
+            timers,
+        }
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Get the report of only what happened since `snapshot` was taken, such as during a
+    /// single request, frame or phase
+    ///
+    /// Running threads are not stopped, so this can be called while profiling.
+    #[must_use]
+    pub fn since(&self, snapshot: &ProfilerSnapshot) -> Report {
+        // Calibrate before reading the timers to keep a first calibration out of the report
+        let calibration = calibration();
+        let (mut acc, total_time, details) = self.accumulate_timer_details();
+
+        for (index, timer) in acc.iter_mut().enumerate().take(self.next_index as usize) {
+            if let Some(before) = snapshot.timers.get(self.timer_names[index]) {
+                *timer = *timer - *before;
+            }
+        }
+
+        let total_time = total_time.wrapping_sub(snapshot.total_time);
+        let (timers, remainder) =
+            self.timer_results(&acc, &details, total_time, calibration.frequency);
+
+        let (ignored_timers, ignored_time) = self.ignored_timers();
+        let mut report = Report {
+            os_timer_freq: calibration.frequency,
+            calibration,
+            cpu: self.captured_cpu_info(),
+            total_time,
+            timers,
+            remainder,
+            min_percent: 0.,
+            hidden_timers: 0,
+            hidden_time: 0,
+            time_unit: self.time_unit,
+            short_timer_policy: self.short_timer_policy,
+            ignored_timers,
+            ignored_time,
+            ignore_decisions: self.collect_ignore_decisions(),
+        };
+
+        report.subtract_overhead(self.overhead_cycles);
+        report.hide_below(self.min_percent);
+        report
+    }
+
+    /// Save a snapshot of the current state of the timers to `path`
+    ///
+    /// # Errors
+    ///
+    /// * Failed to create or write to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.snapshot().save(path)
+    }
+}
+
+/// The change of a single timer between two snapshots
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimerDelta {
+    /// The name of the timer
+    pub name: String,
+
+    /// The timer in the earlier snapshot (default if it wasn't hit)
+    pub before: Timer,
+
+    /// The timer in the later snapshot (default if it wasn't hit)
+    pub after: Timer,
+}
+
+impl TimerDelta {
+    /// Change of the exclusive time (in cycles)
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn exclusive_time(&self) -> i64 {
+        self.after
+            .exclusive_time
+            .wrapping_sub(self.before.exclusive_time) as i64
+    }
+
+    /// Change of the inclusive time (in cycles)
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn inclusive_time(&self) -> i64 {
+        self.after
+            .inclusive_time
+            .wrapping_sub(self.before.inclusive_time) as i64
+    }
+
+    /// Change of the number of hits
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn hits(&self) -> i64 {
+        self.after.hits.wrapping_sub(self.before.hits) as i64
+    }
+
+    /// Change of the number of bytes processed
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn bytes_processed(&self) -> i64 {
+        self.after
+            .bytes_processed
+            .wrapping_sub(self.before.bytes_processed) as i64
+    }
+
+    /// Percent change of the exclusive time, if the timer was hit in the earlier snapshot
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn exclusive_time_percent(&self) -> Option<f64> {
+        if self.before.exclusive_time == 0 {
+            return None;
+        }
+
+        Some(self.exclusive_time() as f64 / self.before.exclusive_time as f64 * 100.)
+    }
+}
+
+impl ProfilerSnapshot {
+    /// The calibration of the clock used by this snapshot, if it was saved with one
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn calibration(&self) -> Option<Calibration> {
+        if self.tsc_frequency == 0 {
+            return None;
+        }
+
+        Some(Calibration {
+            frequency: self.tsc_frequency as f64,
+            error: self.tsc_frequency_error as f64,
+            source: ClockSource::Rdtsc,
+        })
+    }
+
+    /// Save this snapshot to `path` in a compact binary format
+    ///
+    /// # Errors
+    ///
+    /// * Failed to create or write to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut out = std::io::BufWriter::new(file);
+
+        self.write_to(&mut out)?;
+        out.flush()
+    }
+
+    /// Load a snapshot previously written by [`ProfilerSnapshot::save`] from `path`
+    ///
+    /// # Errors
+    ///
+    /// * Failed to open or read `path`
+    /// * `path` is not a saved snapshot or was saved by an unsupported version
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::read_from(&mut std::io::BufReader::new(file))
+    }
+
+    /// Write this snapshot in the binary format to `out`
+    pub(crate) fn write_to(&self, out: &mut impl Write) -> std::io::Result<()> {
+        /// Write a string prefixed by its length
+        fn write_str(out: &mut impl Write, value: &str) -> std::io::Result<()> {
+            out.write_all(&(value.len() as u64).to_le_bytes())?;
+            out.write_all(value.as_bytes())
+        }
+
+        out.write_all(SNAPSHOT_MAGIC)?;
+        out.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        out.write_all(&self.total_time.to_le_bytes())?;
+        out.write_all(&self.tsc_frequency.to_le_bytes())?;
+        out.write_all(&self.tsc_frequency_error.to_le_bytes())?;
+
+        match &self.cpu {
+            Some(cpu) => {
+                out.write_all(&[1])?;
+                write_str(out, &cpu.brand)?;
+
+                for value in [
+                    cpu.logical_cpus,
+                    cpu.physical_cores,
+                    cpu.packages,
+                    cpu.caches.len() as u64,
+                ] {
+                    out.write_all(&value.to_le_bytes())?;
+                }
+
+                for cache in &cpu.caches {
+                    out.write_all(&cache.level.to_le_bytes())?;
+                    write_str(out, &cache.kind)?;
+                    out.write_all(&cache.size.to_le_bytes())?;
+                }
+            }
+            None => out.write_all(&[0])?,
+        }
+
+        match &self.git {
+            Some(git) => {
+                out.write_all(&[1])?;
+                write_str(out, &git.commit)?;
+                out.write_all(&[u8::from(git.dirty)])?;
+            }
+            None => out.write_all(&[0])?,
+        }
+
+        out.write_all(&(self.timers.len() as u64).to_le_bytes())?;
+
+        for (name, timer) in &self.timers {
+            write_str(out, name)?;
+
+            for value in [
+                timer.exclusive_time,
+                timer.inclusive_time,
+                timer.hits,
+                timer.bytes_processed,
+                timer.min_cycles,
+                timer.max_cycles,
+                timer.mean_cycles.to_bits(),
+                timer.m2_cycles.to_bits(),
+            ] {
+                out.write_all(&value.to_le_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read a snapshot in the binary format from `input`
+    pub(crate) fn read_from(input: &mut impl Read) -> std::io::Result<Self> {
+        fn read_u64(input: &mut impl Read) -> std::io::Result<u64> {
+            let mut bytes = [0u8; 8];
+            input.read_exact(&mut bytes)?;
+            Ok(u64::from_le_bytes(bytes))
+        }
+
+        fn read_string(input: &mut impl Read) -> std::io::Result<String> {
+            let len =
+                usize::try_from(read_u64(input)?).map_err(|_| invalid_data("String too long"))?;
+
+            let mut bytes = Vec::new();
+            input.by_ref().take(len as u64).read_to_end(&mut bytes)?;
+            if bytes.len() != len {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+
+            String::from_utf8(bytes).map_err(|_| invalid_data("String is not UTF-8"))
+        }
+
+        let mut magic = [0u8; SNAPSHOT_MAGIC.len()];
+        input.read_exact(&mut magic)?;
+        if &magic != SNAPSHOT_MAGIC {
+            return Err(invalid_data("Not a timeloop snapshot"));
+        }
+
+        let mut version = [0u8; 4];
+        input.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != SNAPSHOT_VERSION {
+            return Err(invalid_data("Unsupported timeloop snapshot version"));
+        }
+
+        let total_time = read_u64(input)?;
+
+        let tsc_frequency = read_u64(input)?;
+        let tsc_frequency_error = read_u64(input)?;
+
+        let mut cpu = None;
+        let mut has_cpu = [0u8];
+        input.read_exact(&mut has_cpu)?;
+
+        if has_cpu[0] == 1 {
+            let brand = read_string(input)?;
+            let logical_cpus = read_u64(input)?;
+            let physical_cores = read_u64(input)?;
+            let packages = read_u64(input)?;
+
+            let mut caches = Vec::new();
+            for _ in 0..read_u64(input)? {
+                caches.push(CpuCache {
+                    level: read_u64(input)?,
+                    kind: read_string(input)?,
+                    size: read_u64(input)?,
+                });
+            }
+
+            cpu = Some(CpuInfo {
+                brand,
+                logical_cpus,
+                physical_cores,
+                packages,
+                caches,
+            });
+        }
+
+        let mut git = None;
+        let mut has_git = [0u8];
+        input.read_exact(&mut has_git)?;
+
+        if has_git[0] == 1 {
+            let commit = read_string(input)?;
+            let mut dirty = [0u8];
+            input.read_exact(&mut dirty)?;
+
+            git = Some(GitInfo {
+                commit,
+                dirty: dirty[0] == 1,
+            });
+        }
+
+        let count = read_u64(input)?;
+
+        let mut timers = BTreeMap::new();
+        for _ in 0..count {
+            let name = read_string(input)?;
+
+            let timer = Timer {
+                exclusive_time: read_u64(input)?,
+                inclusive_time: read_u64(input)?,
+                hits: read_u64(input)?,
+                bytes_processed: read_u64(input)?,
+                min_cycles: read_u64(input)?,
+                max_cycles: read_u64(input)?,
+                mean_cycles: f64::from_bits(read_u64(input)?),
+                m2_cycles: f64::from_bits(read_u64(input)?),
+            };
+
+            timers.insert(name, timer);
+        }
+
+        Ok(ProfilerSnapshot {
+            total_time,
+            tsc_frequency,
+            tsc_frequency_error,
+            cpu,
+            git,
+            timers,
+        })
+    }
+
+    /// Get the change of every timer from this snapshot to the later `other` snapshot
+    #[must_use]
+    pub fn diff(&self, other: &ProfilerSnapshot) -> Vec<TimerDelta> {
+        let mut names: Vec<&String> = self.timers.keys().chain(other.timers.keys()).collect();
+        names.sort_unstable();
+        names.dedup();
+
+        names
+            .into_iter()
+            .map(|name| TimerDelta {
+                name: name.clone(),
+                before: self.timers.get(name).copied().unwrap_or_default(),
+                after: other.timers.get(name).copied().unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+/// Create an [`std::io::ErrorKind::InvalidData`] error with the given `message`
+pub(crate) fn invalid_data(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
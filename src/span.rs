@@ -0,0 +1,101 @@
+//! Implements the explicit begin/end timer handles for scopes that can't use a guard
+use std::sync::atomic::Ordering;
+use std::sync::PoisonError;
+
+use crate::{counters, is_enabled, rdtsc, OpenTimer, Profiler, ThreadProfile};
+
+/// Identifies a timer entered by [`Profiler::begin`] until it is ended by
+/// [`Profiler::end`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpanId(pub u64);
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Start `timer` on the given thread until the returned span is passed to
+    /// [`Profiler::end`], for scopes that start and end in different functions or
+    /// callbacks
+    ///
+    /// Spans are recorded outside of the stack of nested timers: they can end in any
+    /// order and on any thread, have no parent and their time is not removed from the
+    /// exclusive time of the timers open around them. While the timers are switched off
+    /// by [`crate::set_enabled`] nothing is started.
+    pub fn begin(&mut self, thread_id: usize, timer: &'static str) -> SpanId {
+        self.get_timer_index(timer);
+        self.begin_registered(thread_id, timer)
+    }
+
+    /// Start the registered `timer` on the given thread until the returned span is
+    /// passed to [`Profiler::end`], see [`Profiler::begin`]
+    pub(crate) fn begin_registered(&self, thread_id: usize, timer: &'static str) -> SpanId {
+        if !is_enabled() {
+            return SpanId::default();
+        }
+
+        self.thread(thread_id).capture_name();
+
+        let span = SpanId(self.next_span_id.fetch_add(1, Ordering::Relaxed) + 1);
+
+        let open = if cfg!(feature = "counters") {
+            counters::enter_counter(timer, 0)
+        } else {
+            OpenTimer {
+                timer,
+                start_time: rdtsc(),
+                paused_cycles: self.paused_cycles,
+                ..Default::default()
+            }
+        };
+        self.open_spans
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(span, open);
+
+        span
+    }
+
+    /// Stop the timer of `span`, recording its hit on the given thread, which does not
+    /// have to be the thread that began it. Returns `false` if the span is unknown or
+    /// was already ended.
+    pub fn end(&self, thread_id: usize, span: SpanId) -> bool {
+        let stop_time = rdtsc();
+
+        let open = self
+            .open_spans
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&span);
+        let Some(open) = open else {
+            return false;
+        };
+
+        if cfg!(feature = "counters") {
+            self.exit_counter(thread_id, &open, stop_time);
+        } else if self.dry_run {
+            self.thread(thread_id).timers[self.timer_index(open.timer)].hits += 1;
+        } else {
+            self.exit_span(&mut self.thread(thread_id), &open, stop_time);
+        }
+
+        true
+    }
+
+    /// Record the hit of the span `open` on the locked `thread` as a standalone hit with
+    /// no parent and no stack
+    fn exit_span(&self, thread: &mut ThreadProfile<TIMERS>, open: &OpenTimer, stop_time: u64) {
+        // The clocks of different cores may be slightly apart for spans ending on another
+        // thread
+        let elapsed = stop_time
+            .saturating_sub(open.start_time)
+            .saturating_sub(self.paused_during(open, stop_time));
+
+        thread.timers[self.timer_index(open.timer)].record_hit(elapsed);
+
+        self.record_sketch(thread, open.timer, elapsed);
+        #[cfg(feature = "hist")]
+        thread.record_histogram(open.timer, elapsed);
+        self.record_log2_histogram(thread, open.timer, elapsed);
+        self.record_percentile_sample(thread, open.timer, elapsed);
+
+        self.record_event(thread, open.timer, open.start_time, stop_time);
+        self.record_series_hit(thread, open.timer, stop_time, elapsed);
+    }
+}
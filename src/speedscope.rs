@@ -0,0 +1,123 @@
+//! Implements the speedscope export of the recorded timer events
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use crate::trace::json_escape;
+use crate::{calibration, Event, Profiler};
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Write the recorded events in the speedscope JSON format to `path`, to be opened
+    /// at <https://www.speedscope.app>
+    ///
+    /// Each thread is written as its own evented profile.
+    ///
+    /// # Errors
+    ///
+    /// * Failed to create or write to `path`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn write_speedscope(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut out = std::io::BufWriter::new(file);
+
+        let calibration = calibration();
+        let cycles_per_ns = calibration.frequency / 1_000_000_000.0;
+
+        // Start every profile at the first recorded event
+        let threads = self.thread_events();
+        let first_timestamp = threads
+            .iter()
+            .filter_map(|(_, _, events)| events.iter().map(|event| event.start).min())
+            .min()
+            .unwrap_or(0);
+
+        let to_ns = |timestamp: u64| timestamp.wrapping_sub(first_timestamp) as f64 / cycles_per_ns;
+
+        // Each timer name is a shared frame
+        let mut frames: BTreeMap<&'static str, usize> = BTreeMap::new();
+        for event in threads.iter().flat_map(|(_, _, events)| events) {
+            let next_frame = frames.len();
+            frames.entry(event.timer).or_insert(next_frame);
+        }
+
+        let mut frame_names = vec![""; frames.len()];
+        for (name, index) in &frames {
+            frame_names[*index] = name;
+        }
+
+        write!(
+            out,
+            "{{\"$schema\":\"https://www.speedscope.app/file-format-schema.json\",\"exporter\":\"timeloop\",\"name\":\"timeloop ({} at {:.0} Hz ±{:.0})\",\"activeProfileIndex\":0,\"shared\":{{\"frames\":[",
+            calibration.source, calibration.frequency, calibration.error
+        )?;
+
+        for (i, name) in frame_names.iter().enumerate() {
+            let comma = if i > 0 { "," } else { "" };
+            write!(out, "{comma}{{\"name\":\"{}\"}}", json_escape(name))?;
+        }
+
+        write!(out, "]}},\"profiles\":[")?;
+
+        let mut first = true;
+        for (_, label, events) in &threads {
+            if !first {
+                write!(out, ",")?;
+            }
+            first = false;
+
+            // Events are recorded when a timer stops. Order them by start (outermost
+            // first) to rebuild the open/close nesting.
+            let mut spans: Vec<&Event> = events.iter().filter(|event| !event.instant).collect();
+            spans.sort_by_key(|event| (event.start, std::cmp::Reverse(event.stop)));
+
+            let start_value = spans.first().map_or(0.0, |event| to_ns(event.start));
+            let end_value = spans
+                .iter()
+                .map(|event| event.stop)
+                .max()
+                .map_or(0.0, to_ns);
+
+            write!(
+                out,
+                "\n{{\"type\":\"evented\",\"name\":\"{}\",\"unit\":\"nanoseconds\",\"startValue\":{start_value:.0},\"endValue\":{end_value:.0},\"events\":[",
+                json_escape(label)
+            )?;
+
+            let mut open: Vec<&Event> = Vec::new();
+            let mut first_event = true;
+            let mut write_event = |out: &mut std::io::BufWriter<std::fs::File>,
+                                   kind: &str,
+                                   event: &Event,
+                                   timestamp: u64|
+             -> std::io::Result<()> {
+                let comma = if first_event { "" } else { "," };
+                first_event = false;
+                write!(
+                    out,
+                    "{comma}{{\"type\":\"{kind}\",\"frame\":{},\"at\":{:.0}}}",
+                    frames[event.timer],
+                    to_ns(timestamp)
+                )
+            };
+
+            for span in spans {
+                // Close every open span that stopped before this one started
+                while let Some(last) = open.pop_if(|last| last.stop <= span.start) {
+                    write_event(&mut out, "C", last, last.stop)?;
+                }
+
+                write_event(&mut out, "O", span, span.start)?;
+                open.push(span);
+            }
+
+            while let Some(last) = open.pop() {
+                write_event(&mut out, "C", last, last.stop)?;
+            }
+
+            write!(out, "]}}")?;
+        }
+
+        writeln!(out, "\n]}}")?;
+        out.flush()
+    }
+}
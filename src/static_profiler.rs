@@ -0,0 +1,189 @@
+//! Implements the `Sync` storage of the static profiler created by `create_profiler!`
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+
+use crate::{OpenTimer, Profiler, ProfilerSnapshot, Report, SpanId};
+
+/// The storage of the static profiler created by `create_profiler!`, replacing a
+/// `static mut` so that the profiler is never referenced through a mutable static
+///
+/// Timers share the read lock and only lock the slot of their own thread in the profiler,
+/// so threads never wait on each other while timing. The state shared by every thread,
+/// such as the timer names and the settings, is only changed under the write lock: when
+/// a timer name is first seen, and by the control macros such as `reset!`.
+///
+/// The `fork()` handlers of `create_profiler!` hold the write lock across the fork, so
+/// the child never inherits the profiler locked by a thread it doesn't have. The child
+/// handler only marks the profiler as forked, as it must not allocate, and the
+/// profiler is reset on its first use in the child, see [`Profiler::reset_after_fork`].
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct ProfilerCell<P> {
+    /// The profiler
+    profiler: RwLock<P>,
+
+    /// The thread slot of the thread that forked this child process, or [`NOT_FORKED`]
+    /// once the profiler is reset in the child
+    forked_thread: AtomicUsize,
+}
+
+/// The [`ProfilerCell::forked_thread`] while no reset after a fork is pending
+const NOT_FORKED: usize = usize::MAX;
+
+impl<P> ProfilerCell<P> {
+    /// Wrap the `profiler` to store it in a `static`
+    pub const fn new(profiler: P) -> Self {
+        ProfilerCell {
+            profiler: RwLock::new(profiler),
+            forked_thread: AtomicUsize::new(NOT_FORKED),
+        }
+    }
+
+    /// Mark the profiler to be reset on its next use, in the child process of a fork from
+    /// the thread at `thread_id`. Only stores the thread, so it can be called from the
+    /// `atfork` child handler.
+    pub fn mark_forked(&self, thread_id: usize) {
+        self.forked_thread.store(thread_id, Ordering::Release);
+    }
+
+    /// Lock the profiler for changing the state shared by every thread without resetting
+    /// it after a fork, even if a thread panicked while changing it
+    fn lock_write(&self) -> RwLockWriteGuard<'_, P> {
+        self.profiler
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> ProfilerCell<Profiler<THREADS, TIMERS>> {
+    /// Reset the profiler inherited by this child process if it was forked since its last
+    /// use
+    fn reset_if_forked(&self) {
+        if self.forked_thread.load(Ordering::Acquire) == NOT_FORKED {
+            return;
+        }
+
+        let mut profiler = self.lock_write();
+        let thread_id = self.forked_thread.swap(NOT_FORKED, Ordering::AcqRel);
+        if thread_id != NOT_FORKED {
+            profiler.reset_after_fork(thread_id);
+        }
+    }
+
+    /// Lock the profiler for reading, even if a thread panicked while changing it
+    pub fn read(&self) -> RwLockReadGuard<'_, Profiler<THREADS, TIMERS>> {
+        self.reset_if_forked();
+        self.profiler.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the profiler for reading unless a thread is changing it
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, Profiler<THREADS, TIMERS>>> {
+        self.reset_if_forked();
+        match self.profiler.try_read() {
+            Ok(profiler) => Some(profiler),
+            Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    /// Lock the profiler for changing the state shared by every thread, even if a thread
+    /// panicked while changing it
+    pub fn write(&self) -> RwLockWriteGuard<'_, Profiler<THREADS, TIMERS>> {
+        self.reset_if_forked();
+        self.lock_write()
+    }
+
+    /// Lock the profiler for reading with `timer` registered, only taking the write lock
+    /// the first time `timer` is seen
+    pub fn registered(
+        &self,
+        timer: &'static str,
+    ) -> RwLockReadGuard<'_, Profiler<THREADS, TIMERS>> {
+        let profiler = self.read();
+        if profiler.is_registered(timer) {
+            return profiler;
+        }
+
+        drop(profiler);
+        self.write().get_timer_index(timer);
+        self.read()
+    }
+
+    /// Enter `timer` on the given thread, see [`Profiler::enter_timer`]
+    pub fn enter_timer(
+        &self,
+        thread_id: usize,
+        timer: &'static str,
+        bytes_processed: u64,
+    ) -> OpenTimer {
+        self.registered(timer)
+            .enter_registered_timer(thread_id, timer, bytes_processed)
+    }
+
+    /// Enter `timer` on the given thread starting at `start_time`, see
+    /// [`Profiler::enter_timer_at`]
+    pub fn enter_timer_at(
+        &self,
+        thread_id: usize,
+        timer: &'static str,
+        bytes_processed: u64,
+        start_time: u64,
+    ) -> OpenTimer {
+        let mut open = self.enter_timer(thread_id, timer, bytes_processed);
+        open.start_time = start_time;
+        open
+    }
+
+    /// Exit the `open` timer on the given thread, see [`Profiler::exit_timer`]
+    pub fn exit_timer(&self, thread_id: usize, open: &OpenTimer, stop_time: u64) {
+        self.read().exit_timer(thread_id, open, stop_time);
+    }
+
+    /// Exit the `open` timer on the given thread without recording it, see
+    /// [`Profiler::cancel_timer`]
+    pub fn cancel_timer(&self, thread_id: usize, open: &OpenTimer) {
+        self.read().cancel_timer(thread_id, open);
+    }
+
+    /// Start the span of `timer` on the given thread, see [`Profiler::begin`]
+    pub fn begin(&self, thread_id: usize, timer: &'static str) -> SpanId {
+        self.registered(timer).begin_registered(thread_id, timer)
+    }
+
+    /// Take a snapshot of the timers, see [`Profiler::snapshot`]
+    ///
+    /// This is the path every background consumer reads the profiler through: the read
+    /// lock is only held while the timers of every thread are gathered at once, and is
+    /// released before the caller formats or writes anything.
+    pub fn snapshot(&self) -> ProfilerSnapshot {
+        self.read().snapshot()
+    }
+
+    /// Gather the report of everything timed so far without stopping the running
+    /// threads, through the same locking as [`ProfilerCell::snapshot`]
+    pub fn report(&self) -> Report {
+        self.read().since(&ProfilerSnapshot::default())
+    }
+
+    /// Put `timer` in `category`, only taking the write lock if it isn't already
+    pub fn set_category(&self, timer: &'static str, category: &'static str) {
+        if self.read().category(timer) != Some(category) {
+            self.write().set_category(timer, category);
+        }
+    }
+
+    /// Sample one of every `rate` hits of `timer`, only taking the write lock if its rate
+    /// changed
+    pub fn set_sample_rate(&self, timer: &'static str, rate: u64) {
+        if self.read().sample_rate(timer) != rate.max(1) {
+            self.write().set_sample_rate(timer, rate);
+        }
+    }
+
+    /// Pin `timer`, only taking the write lock if it isn't already pinned
+    pub fn pin_timer(&self, timer: &'static str) {
+        if !self.read().is_pinned(timer) {
+            self.write().pin_timer(timer);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Implements a background emitter of the profiler state as statsd metrics
+use std::collections::BTreeMap;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+use crate::{calculate_os_frequency, timer_hash, Profiler, ProfilerCell, ProfilerSnapshot, Timer};
+
+/// Largest payload sent in a single datagram, keeping under the common 1500 byte MTU
+const MAX_PACKET_SIZE: usize = 1432;
+
+/// Configuration for the statsd emitter given to `create_profiler!(statsd = ..)`
+#[derive(Debug, Clone, Copy)]
+pub struct StatsdConfig {
+    /// Address of the statsd or dogstatsd agent
+    pub addr: &'static str,
+
+    /// Prefix of every emitted metric name
+    pub prefix: &'static str,
+
+    /// How often the metrics are flushed
+    pub interval: Duration,
+
+    /// Dogstatsd tags (`key:value`) added to every metric. Leave empty for plain statsd.
+    pub tags: &'static [&'static str],
+}
+
+impl StatsdConfig {
+    /// Create a config flushing metrics to `addr` every 10 seconds
+    #[must_use]
+    pub const fn new(addr: &'static str) -> Self {
+        Self {
+            addr,
+            prefix: "timeloop",
+            interval: Duration::from_secs(10),
+            tags: &[],
+        }
+    }
+}
+
+impl StatsdConfig {
+    /// Render the change of every timer in `snapshot` since `previous` as statsd lines,
+    /// updating `previous` to the timers of `snapshot`
+    ///
+    /// Per timer this emits the hits and exclusive cycles as counters and the average
+    /// exclusive time per hit as a timing.
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn lines(
+        &self,
+        snapshot: &ProfilerSnapshot,
+        previous: &mut BTreeMap<String, Timer>,
+        os_timer_freq: f64,
+    ) -> Vec<String> {
+        let mut tags = String::new();
+        if !self.tags.is_empty() {
+            tags = format!("|#{}", self.tags.join(","));
+        }
+
+        let mut lines = Vec::new();
+
+        // Forget the timers that were reset, so their next hits count from zero
+        previous.retain(|timer_name, _| snapshot.timers.contains_key(timer_name));
+
+        for (timer_name, timer) in &snapshot.timers {
+            let previous = previous.entry(timer_name.clone()).or_default();
+            let hits = timer.hits.wrapping_sub(previous.hits);
+            let cycles = timer.exclusive_time.wrapping_sub(previous.exclusive_time);
+
+            *previous = *timer;
+
+            if hits == 0 {
+                continue;
+            }
+
+            let prefix = self.prefix;
+            let name = sanitize_name(timer_name);
+            let millis_per_hit = cycles as f64 / os_timer_freq * 1000.0 / hits as f64;
+
+            // Identify the timer across binaries by the hash of its full name
+            let hash = timer_hash(timer_name);
+            let timer_tags = if tags.is_empty() {
+                format!("|#timer_hash:{hash:016x}")
+            } else {
+                format!("{tags},timer_hash:{hash:016x}")
+            };
+
+            lines.push(format!("{prefix}.{name}.hits:{hits}|c{timer_tags}"));
+            lines.push(format!("{prefix}.{name}.cycles:{cycles}|c{timer_tags}"));
+            lines.push(format!(
+                "{prefix}.{name}.time:{millis_per_hit:.6}|ms{timer_tags}"
+            ));
+        }
+
+        let calibration = crate::calibration();
+        lines.push(format!(
+            "{}.clock.frequency:{:.0}|g{tags}",
+            self.prefix, calibration.frequency
+        ));
+
+        lines
+    }
+}
+
+/// Replace the characters that are reserved by the statsd protocol in a metric name
+fn sanitize_name(input: &str) -> String {
+    input
+        .chars()
+        .map(|c| match c {
+            ':' | '|' | '@' | '#' | ',' | ' ' | '\n' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Flush the timers of `profiler` to the statsd agent in `config` from a background thread
+///
+/// # Errors
+///
+/// * Failed to bind the local UDP socket
+/// * Failed to resolve or connect to `config.addr`
+pub fn spawn_statsd_emitter<const THREADS: usize, const TIMERS: usize>(
+    config: StatsdConfig,
+    profiler: &'static ProfilerCell<Profiler<THREADS, TIMERS>>,
+) -> std::io::Result<()> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect(config.addr)?;
+
+    std::thread::Builder::new()
+        .name("timeloop-statsd".to_string())
+        .spawn(move || {
+            let os_timer_freq = calculate_os_frequency();
+            let mut previous = BTreeMap::new();
+
+            loop {
+                std::thread::sleep(config.interval);
+
+                let lines = config.lines(&profiler.snapshot(), &mut previous, os_timer_freq);
+
+                // Batch as many lines as fit into each datagram
+                let mut packet = String::new();
+                for line in lines {
+                    if !packet.is_empty() && packet.len() + line.len() + 1 > MAX_PACKET_SIZE {
+                        let _ = socket.send(packet.as_bytes());
+                        packet.clear();
+                    }
+
+                    if !packet.is_empty() {
+                        packet.push('\n');
+                    }
+                    packet.push_str(&line);
+                }
+
+                if !packet.is_empty() {
+                    let _ = socket.send(packet.as_bytes());
+                }
+            }
+        })?;
+
+    Ok(())
+}
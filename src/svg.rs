@@ -0,0 +1,529 @@
+//! Implements a small SVG line plot generator for benchmark results
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::json::JsonValue;
+use crate::snapshot::invalid_data;
+use crate::ProfilerSnapshot;
+
+/// Width of the generated image
+const WIDTH: f64 = 800.0;
+
+/// Height of the generated image
+const HEIGHT: f64 = 480.0;
+
+/// Space around the plot area for the title, axes labels and legend
+const MARGIN_LEFT: f64 = 80.0;
+const MARGIN_RIGHT: f64 = 180.0;
+const MARGIN_TOP: f64 = 40.0;
+const MARGIN_BOTTOM: f64 = 60.0;
+
+/// Number of ticks on each axis
+const TICKS: usize = 5;
+
+/// The line colors, repeated for more series
+const COLORS: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+/// A single named line of a [`SvgPlot`]
+#[derive(Debug, Clone, Default)]
+pub struct Series {
+    /// The name of this line in the legend
+    pub name: String,
+
+    /// The (x, y) points of this line
+    pub points: Vec<(f64, f64)>,
+}
+
+/// A line plot, such as the throughput of each variant of a size sweep
+#[derive(Debug, Clone, Default)]
+pub struct SvgPlot {
+    /// The title above the plot
+    pub title: String,
+
+    /// The label of the x axis
+    pub x_label: String,
+
+    /// The label of the y axis
+    pub y_label: String,
+
+    /// Use a log2 scale for the x axis, such as for power of two input sizes
+    pub log_x: bool,
+
+    /// The lines of this plot
+    pub series: Vec<Series>,
+}
+
+impl SvgPlot {
+    /// Create an empty plot with the given `title`
+    #[must_use]
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a line called `name` through the given (x, y) `points`
+    pub fn add_series(&mut self, name: &str, points: impl IntoIterator<Item = (f64, f64)>) {
+        self.series.push(Series {
+            name: name.to_string(),
+            points: points.into_iter().collect(),
+        });
+    }
+
+    /// Create a plot of the exclusive time (in milliseconds) of every timer over a
+    /// series of snapshots taken from the same run, using the total time as the x axis
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_snapshots(snapshots: &[ProfilerSnapshot]) -> Self {
+        let mut plot = SvgPlot::new("timeloop");
+        plot.x_label = "Total time (s)".to_string();
+        plot.y_label = "Exclusive time (ms)".to_string();
+
+        let frequency = snapshots
+            .iter()
+            .find_map(ProfilerSnapshot::calibration)
+            .map_or_else(crate::tsc_frequency, |calibration| calibration.frequency);
+
+        let mut names: Vec<&String> = snapshots
+            .iter()
+            .flat_map(|snapshot| snapshot.timers.keys())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+
+        for name in names {
+            let points = snapshots.iter().map(|snapshot| {
+                let time = snapshot
+                    .timers
+                    .get(name)
+                    .map_or(0, |timer| timer.exclusive_time);
+
+                (
+                    snapshot.total_time as f64 / frequency,
+                    time as f64 / frequency * 1000.0,
+                )
+            });
+
+            plot.add_series(name, points);
+        }
+
+        plot
+    }
+
+    /// Load a plot from the JSON results in `path`, see [`SvgPlot::from_json`]
+    ///
+    /// # Errors
+    ///
+    /// * Failed to read `path`
+    /// * `path` holds none of the supported results
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Create a plot from the JSON results in `input`, which are one of:
+    ///
+    /// * A plot: `{"title": "copy", "x_label": "Size", "y_label": "GB/s", "log_x": true,
+    ///   "series": [{"name": "std", "points": [[1024, 3.5], [4096, 7.1]]}]}`
+    /// * The rows of a size sweep, plotted as a line per variant:
+    ///   `[{"variant": "std", "size": 1024, "gb_per_sec": 3.5}, ...]`
+    /// * The messages of `cargo criterion --message-format=json`, plotted as a line per
+    ///   benchmark function over its parameter. Benchmarks with a throughput in bytes are
+    ///   plotted in GB/s, others by their typical time.
+    ///
+    /// # Errors
+    ///
+    /// * `input` holds none of the supported results
+    pub fn from_json(input: &str) -> std::io::Result<Self> {
+        // Criterion writes a document per line
+        let json = match JsonValue::parse(input) {
+            Ok(json) => json,
+            Err(err) => JsonValue::Array(
+                input
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(JsonValue::parse)
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| err)?,
+            ),
+        };
+
+        match &json {
+            JsonValue::Object(_) if json.get("series").is_some() => Self::from_plot_json(&json),
+            JsonValue::Object(_) if json.get("reason").is_some() => {
+                Self::from_criterion_messages(std::slice::from_ref(&json))
+            }
+            JsonValue::Array(rows) if rows.iter().any(|row| row.get("reason").is_some()) => {
+                Self::from_criterion_messages(rows)
+            }
+            JsonValue::Array(rows) => Self::from_sweep_rows(rows),
+            _ => Err(invalid_data("Unsupported JSON results")),
+        }
+    }
+
+    /// Create a plot from the JSON form of a plot
+    fn from_plot_json(json: &JsonValue) -> std::io::Result<Self> {
+        let text = |key: &str| json.get(key).and_then(JsonValue::as_str).unwrap_or("");
+
+        let mut plot = SvgPlot::new(text("title"));
+        plot.x_label = text("x_label").to_string();
+        plot.y_label = text("y_label").to_string();
+        plot.log_x = json.get("log_x") == Some(&JsonValue::Bool(true));
+
+        let series = json
+            .get("series")
+            .and_then(JsonValue::as_array)
+            .ok_or_else(|| invalid_data("The series of the plot are not an array"))?;
+
+        for series in series {
+            let name = series.get("name").and_then(JsonValue::as_str).unwrap_or("");
+            let points = series
+                .get("points")
+                .and_then(JsonValue::as_array)
+                .unwrap_or_default()
+                .iter()
+                .map(|point| match point.as_array() {
+                    Some([x, y]) => x.as_f64().zip(y.as_f64()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| invalid_data("A point of the plot is not an [x, y] pair"))?;
+
+            plot.add_series(name, points);
+        }
+
+        Ok(plot)
+    }
+
+    /// Create a plot of the throughput of every variant from the rows of a size sweep
+    fn from_sweep_rows(rows: &[JsonValue]) -> std::io::Result<Self> {
+        let mut variants: BTreeMap<&str, Vec<(f64, f64)>> = BTreeMap::new();
+
+        for row in rows {
+            let variant = row.get("variant").and_then(JsonValue::as_str);
+            let size = row.get("size").and_then(JsonValue::as_f64);
+            let throughput = row.get("gb_per_sec").and_then(JsonValue::as_f64);
+
+            let (Some(variant), Some(size), Some(throughput)) = (variant, size, throughput) else {
+                return Err(invalid_data(
+                    "A sweep row is missing its variant, size or gb_per_sec",
+                ));
+            };
+
+            variants
+                .entry(variant)
+                .or_default()
+                .push((size, throughput));
+        }
+
+        let mut plot = SvgPlot::new("timeloop");
+        plot.x_label = "Size (bytes)".to_string();
+        plot.y_label = "Throughput (GB/s)".to_string();
+        plot.log_x = true;
+
+        for (variant, points) in variants {
+            plot.add_series(variant, points);
+        }
+
+        Ok(plot)
+    }
+
+    /// Create a plot of every benchmark function from the completed benchmarks among the
+    /// `cargo criterion` `messages`
+    fn from_criterion_messages(messages: &[JsonValue]) -> std::io::Result<Self> {
+        /// A completed benchmark: its function, parameter, bytes per iteration and
+        /// typical time in nanoseconds
+        struct Benchmark<'a> {
+            function: &'a str,
+            parameter: Option<f64>,
+            bytes: Option<f64>,
+            nanoseconds: f64,
+        }
+
+        let mut benchmarks = Vec::new();
+        for message in messages {
+            if message.get("reason").and_then(JsonValue::as_str) != Some("benchmark-complete") {
+                continue;
+            }
+
+            let (Some(id), Some(nanoseconds)) = (
+                message.get("id").and_then(JsonValue::as_str),
+                message
+                    .get("typical")
+                    .and_then(|typical| typical.get("estimate"))
+                    .and_then(JsonValue::as_f64),
+            ) else {
+                return Err(invalid_data(
+                    "A benchmark is missing its id or typical time",
+                ));
+            };
+
+            let bytes = message
+                .get("throughput")
+                .and_then(JsonValue::as_array)
+                .unwrap_or_default()
+                .iter()
+                .find(|throughput| {
+                    throughput.get("unit").and_then(JsonValue::as_str) == Some("bytes")
+                })
+                .and_then(|throughput| throughput.get("per_iteration"))
+                .and_then(JsonValue::as_f64);
+
+            let (function, parameter) = match id.rsplit_once('/') {
+                Some((function, parameter)) => (function, parameter.parse().ok()),
+                None => (id, None),
+            };
+
+            benchmarks.push(Benchmark {
+                function,
+                parameter,
+                bytes,
+                nanoseconds,
+            });
+        }
+
+        if benchmarks.is_empty() {
+            return Err(invalid_data("No completed criterion benchmark"));
+        }
+
+        // Plot the throughput only if every benchmark has one to compare
+        let throughput = benchmarks.iter().all(|benchmark| benchmark.bytes.is_some());
+
+        let mut plot = SvgPlot::new("timeloop");
+        let mut functions: BTreeMap<&str, Vec<(f64, f64)>> = BTreeMap::new();
+        if throughput {
+            plot.x_label = "Size (bytes)".to_string();
+            plot.y_label = "Throughput (GB/s)".to_string();
+            plot.log_x = true;
+
+            for benchmark in &benchmarks {
+                let bytes = benchmark.bytes.unwrap_or_default();
+                functions
+                    .entry(benchmark.function)
+                    .or_default()
+                    .push((bytes, bytes / benchmark.nanoseconds));
+            }
+        } else {
+            plot.x_label = "Parameter".to_string();
+            plot.y_label = "Time (ns)".to_string();
+
+            for benchmark in &benchmarks {
+                let Some(parameter) = benchmark.parameter else {
+                    continue;
+                };
+
+                functions
+                    .entry(benchmark.function)
+                    .or_default()
+                    .push((parameter, benchmark.nanoseconds));
+            }
+        }
+
+        for (function, points) in functions {
+            plot.add_series(function, points);
+        }
+
+        Ok(plot)
+    }
+
+    /// Render this plot as an SVG document
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn render(&self) -> String {
+        let scale_x = |x: f64| {
+            if self.log_x {
+                x.max(f64::MIN_POSITIVE).log2()
+            } else {
+                x
+            }
+        };
+
+        // Find the range of both axes, always including 0 on the y axis
+        let points = self.series.iter().flat_map(|series| &series.points);
+        let (mut min_x, mut max_x, mut max_y) = (f64::MAX, f64::MIN, 0.0_f64);
+        for (x, y) in points {
+            min_x = min_x.min(scale_x(*x));
+            max_x = max_x.max(scale_x(*x));
+            max_y = max_y.max(*y);
+        }
+
+        if min_x > max_x {
+            (min_x, max_x) = (0.0, 1.0);
+        }
+        if (max_x - min_x).abs() < f64::EPSILON {
+            max_x = min_x + 1.0;
+        }
+        if max_y <= 0.0 {
+            max_y = 1.0;
+        }
+
+        let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+        let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+        let to_x = |x: f64| MARGIN_LEFT + (scale_x(x) - min_x) / (max_x - min_x) * plot_width;
+        let to_y = |y: f64| MARGIN_TOP + plot_height - y / max_y * plot_height;
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{HEIGHT}\" font-family=\"sans-serif\" font-size=\"12\">"
+        );
+        let _ = writeln!(
+            out,
+            "<rect width=\"{WIDTH}\" height=\"{HEIGHT}\" fill=\"white\"/>"
+        );
+        let _ = writeln!(
+            out,
+            "<text x=\"{}\" y=\"24\" text-anchor=\"middle\" font-size=\"16\">{}</text>",
+            MARGIN_LEFT + plot_width / 2.0,
+            xml_escape(&self.title)
+        );
+
+        // Axes with their ticks and grid lines
+        let bottom = MARGIN_TOP + plot_height;
+        let right = MARGIN_LEFT + plot_width;
+        let _ = writeln!(
+            out,
+            "<path d=\"M{MARGIN_LEFT},{MARGIN_TOP} V{bottom} H{right}\" stroke=\"black\" fill=\"none\"/>"
+        );
+
+        for tick in 0..=TICKS {
+            let fraction = tick as f64 / TICKS as f64;
+
+            let x = MARGIN_LEFT + fraction * plot_width;
+            let mut value = min_x + fraction * (max_x - min_x);
+            if self.log_x {
+                value = value.exp2();
+            }
+            let _ = writeln!(
+                out,
+                "<text x=\"{x:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+                bottom + 18.0,
+                format_number(value)
+            );
+
+            let y = bottom - fraction * plot_height;
+            let _ = writeln!(
+                out,
+                "<line x1=\"{MARGIN_LEFT}\" y1=\"{y:.1}\" x2=\"{right}\" y2=\"{y:.1}\" stroke=\"#ddd\"/>"
+            );
+            let _ = writeln!(
+                out,
+                "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+                MARGIN_LEFT - 6.0,
+                y + 4.0,
+                format_number(fraction * max_y)
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            MARGIN_LEFT + plot_width / 2.0,
+            HEIGHT - 16.0,
+            xml_escape(&self.x_label)
+        );
+        let _ = writeln!(
+            out,
+            "<text transform=\"translate(18,{:.1}) rotate(-90)\" text-anchor=\"middle\">{}</text>",
+            MARGIN_TOP + plot_height / 2.0,
+            xml_escape(&self.y_label)
+        );
+
+        self.write_series(&mut out, &to_x, &to_y, right);
+
+        let _ = writeln!(out, "</svg>");
+        out
+    }
+
+    /// Write the line and legend entry of every series to `out`, using `to_x` and `to_y`
+    /// to place the points. `right` is the right edge of the plot area.
+    #[allow(clippy::cast_precision_loss)]
+    fn write_series(
+        &self,
+        out: &mut String,
+        to_x: &dyn Fn(f64) -> f64,
+        to_y: &dyn Fn(f64) -> f64,
+        right: f64,
+    ) {
+        for (i, series) in self.series.iter().enumerate() {
+            let color = COLORS[i % COLORS.len()];
+
+            let mut points = series.points.clone();
+            points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let path: Vec<String> = points
+                .iter()
+                .map(|(x, y)| format!("{:.1},{:.1}", to_x(*x), to_y(*y)))
+                .collect();
+
+            let _ = writeln!(
+                out,
+                "<polyline points=\"{}\" stroke=\"{color}\" stroke-width=\"2\" fill=\"none\"/>",
+                path.join(" ")
+            );
+
+            for (x, y) in &points {
+                let _ = writeln!(
+                    out,
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{color}\"><title>{}: {}</title></circle>",
+                    to_x(*x),
+                    to_y(*y),
+                    format_number(*x),
+                    format_number(*y)
+                );
+            }
+
+            let legend_y = MARGIN_TOP + 10.0 + i as f64 * 18.0;
+            let _ = writeln!(
+                out,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"12\" height=\"12\" fill=\"{color}\"/>",
+                right + 16.0,
+                legend_y - 10.0
+            );
+            let _ = writeln!(
+                out,
+                "<text x=\"{:.1}\" y=\"{legend_y:.1}\">{}</text>",
+                right + 34.0,
+                xml_escape(&series.name)
+            );
+        }
+    }
+
+    /// Write this plot as an SVG document to `path`
+    ///
+    /// # Errors
+    ///
+    /// * Failed to create or write to `path`
+    pub fn write(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.render())
+    }
+}
+
+/// Format an axis value compactly
+fn format_number(value: f64) -> String {
+    let abs = value.abs();
+
+    if abs >= 1e9 {
+        format!("{:.1}G", value / 1e9)
+    } else if abs >= 1e6 {
+        format!("{:.1}M", value / 1e6)
+    } else if abs >= 1e4 {
+        format!("{:.1}K", value / 1e3)
+    } else if abs >= 100.0 || abs == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+    }
+}
+
+/// Escape the given string to be used as SVG text
+fn xml_escape(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,43 @@
+//! Implements labeling the threads in reports by their names
+use crate::{intern, Profiler, ThreadProfile};
+
+impl<const TIMERS: usize> ThreadProfile<TIMERS> {
+    /// Capture the name of the current thread for this thread slot, the first time the
+    /// slot is used by the thread, see [`ThreadProfile::claim`]
+    pub(crate) fn capture_name(&mut self) {
+        self.claim();
+        if self.name.is_some() {
+            return;
+        }
+
+        // Unnamed threads are captured as empty names to only look them up once
+        let name = std::thread::current().name().map_or("", intern);
+        self.name = Some(name);
+    }
+
+    /// Get the name of the thread that used this thread slot, if it was named
+    pub(crate) fn thread_name(&self) -> Option<&'static str> {
+        self.name.filter(|name| !name.is_empty())
+    }
+
+    /// Get the label of this thread slot at `thread_id` in reports: its name, or its
+    /// index if the thread wasn't named
+    pub(crate) fn label(&self, thread_id: usize) -> String {
+        match self.thread_name() {
+            Some(name) => name.to_string(),
+            None => format!("Thread {thread_id}"),
+        }
+    }
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Get the name of the thread that used the given thread slot, if it was named
+    #[must_use]
+    pub fn thread_name(&self, thread_id: usize) -> Option<&'static str> {
+        if thread_id >= THREADS {
+            return None;
+        }
+
+        self.thread(thread_id).thread_name()
+    }
+}
@@ -0,0 +1,123 @@
+//! Implements the compact thread slots indexing the per-thread state of the profilers
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use crate::{rdtsc, ThreadProfile, ThreadTimerStatus};
+
+/// The next never used thread slot. Slot 0 is never given out to a running thread.
+static NEXT_SLOT: AtomicUsize = AtomicUsize::new(1);
+
+/// The slots of the exited threads, given out again before any new slot
+static FREE_SLOTS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+/// The owner token of the thread holding each slot, or 0 while the slot is free
+static SLOT_OWNERS: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+
+/// The next owner token. Token 0 is never given out to a running thread.
+static NEXT_OWNER: AtomicU64 = AtomicU64::new(1);
+
+/// The slot of a thread, returned to [`FREE_SLOTS`] when the thread exits
+struct ThreadSlot {
+    /// The index of the slot
+    slot: usize,
+
+    /// The token of this thread, unique to every thread holding a slot
+    owner: u64,
+}
+
+impl ThreadSlot {
+    /// Take a free slot, or a new one if every slot is in use
+    fn acquire() -> Self {
+        let free = FREE_SLOTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+
+        let slot = ThreadSlot {
+            slot: free.unwrap_or_else(|| NEXT_SLOT.fetch_add(1, Ordering::Relaxed)),
+            owner: NEXT_OWNER.fetch_add(1, Ordering::Relaxed),
+        };
+        slot.set_owner(slot.owner);
+        slot
+    }
+
+    /// Record `owner` as the token of the thread holding this slot
+    fn set_owner(&self, owner: u64) {
+        let mut owners = SLOT_OWNERS.lock().unwrap_or_else(PoisonError::into_inner);
+        if owners.len() <= self.slot {
+            owners.resize(self.slot + 1, 0);
+        }
+        owners[self.slot] = owner;
+    }
+}
+
+impl Drop for ThreadSlot {
+    fn drop(&mut self) {
+        self.set_owner(0);
+        FREE_SLOTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(self.slot);
+    }
+}
+
+thread_local! {
+    /// The slot of the current thread, taken on its first use
+    static THREAD_SLOT: ThreadSlot = ThreadSlot::acquire();
+}
+
+/// Get the slot of the current thread in the per-thread state of the profilers
+///
+/// Slots are only given to the threads using the profiler and are recycled once their
+/// thread exits, so the slots stay below the highest number of threads using the
+/// profiler at once. A thread reusing a slot adds to the timers of the exited thread,
+/// but starts with none of its open timers, name or clock. Slot 0 is only used by the threads that are exiting and have already given up their
+/// slot. Used by the timers expanded from `create_profiler!`.
+#[doc(hidden)]
+#[must_use]
+pub fn _thread_slot() -> usize {
+    // The slot can't be accessed anymore while the thread is exiting
+    THREAD_SLOT.try_with(|slot| slot.slot).unwrap_or(0)
+}
+
+/// Get the token of the current thread, telling apart the threads reusing the same slot
+///
+/// Token 0 is only used by the threads that are exiting and have already given up their
+/// slot.
+pub(crate) fn thread_owner() -> u64 {
+    THREAD_SLOT.try_with(|slot| slot.owner).unwrap_or(0)
+}
+
+/// Check if the thread with the given token is still holding its slot
+pub(crate) fn owner_is_alive(owner: u64) -> bool {
+    owner != 0
+        && SLOT_OWNERS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(&owner)
+}
+
+impl<const TIMERS: usize> ThreadProfile<TIMERS> {
+    /// Take this slot over for the current thread the first time it uses the slot,
+    /// dropping the open timers, name and clock left by the exited thread that used it
+    /// before. The clock of a thread that exited while running is stopped now.
+    pub(crate) fn claim(&mut self) {
+        let owner = thread_owner();
+        if owner == 0 || owner == self.owner {
+            return;
+        }
+
+        if self.owner != 0 {
+            if self.status == ThreadTimerStatus::Running {
+                self.time = self.time.wrapping_add(rdtsc());
+            }
+
+            self.status = ThreadTimerStatus::Stopped;
+            self.name = None;
+            self.stack.clear();
+            self.recursion_depths = [0; TIMERS];
+        }
+
+        self.owner = owner;
+    }
+}
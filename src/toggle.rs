@@ -0,0 +1,21 @@
+//! Implements switching the timers on and off at runtime
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set while the timers are switched on, checked before entering every timer
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Switch the timers on or off at runtime, such as to only profile a production binary
+/// built with the `enable` feature when needed
+///
+/// While switched off, entering a timer only checks this flag and its guard does nothing
+/// when dropped. Timers already open when switching off are still recorded.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Check if the timers are switched on, see [`set_enabled`]
+#[must_use]
+#[inline]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
@@ -0,0 +1,206 @@
+//! Implements recording of timer events and the chrome trace export
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::{
+    calculate_os_frequency, calibration, is_enabled, rdtsc, timer_hash, Profiler, ThreadProfile,
+};
+
+/// A single recorded hit of a timer, or a marker recorded by [`Profiler::mark`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// The name of the timer
+    pub timer: &'static str,
+
+    /// The timestamp (in cycles) when the timer started
+    pub start: u64,
+
+    /// The timestamp (in cycles) when the timer stopped
+    pub stop: u64,
+
+    /// The number of adjacent short hits coalesced into this event
+    pub hits: u64,
+
+    /// Set for the zero-duration markers, where `timer` is the name of the marker
+    pub instant: bool,
+}
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Record a hit of `timer` on the locked `thread` if event recording is enabled
+    pub(crate) fn record_event(
+        &self,
+        thread: &mut ThreadProfile<TIMERS>,
+        timer: &'static str,
+        start: u64,
+        stop: u64,
+    ) {
+        if !self.record_events {
+            return;
+        }
+
+        let events = &mut thread.events;
+
+        // Merge a short hit into the previous event if it is a short hit of the same timer
+        if stop.wrapping_sub(start) < self.min_event_cycles {
+            if let Some(last) = events.last_mut() {
+                if last.timer == timer
+                    && !last.instant
+                    && last.stop.wrapping_sub(last.start) / last.hits < self.min_event_cycles
+                {
+                    last.stop = stop;
+                    last.hits += 1;
+                    return;
+                }
+            }
+        }
+
+        events.push(Event {
+            timer,
+            start,
+            stop,
+            hits: 1,
+            instant: false,
+        });
+    }
+
+    /// Record the zero-duration marker `name` on the given thread at the current time if
+    /// event recording is enabled, such as to see when a phase started in the trace
+    /// exports
+    pub fn mark(&self, thread_id: usize, name: &'static str) {
+        if !self.record_events || !is_enabled() {
+            return;
+        }
+
+        let now = rdtsc();
+        self.thread(thread_id).events.push(Event {
+            timer: name,
+            start: now,
+            stop: now,
+            hits: 1,
+            instant: true,
+        });
+    }
+
+    /// Copy the recorded events of every thread that recorded any along with the label
+    /// of the thread, locking each thread only while copying its events
+    pub(crate) fn thread_events(&self) -> Vec<(usize, String, Vec<Event>)> {
+        self.thread_profiles()
+            .enumerate()
+            .filter(|(_, thread)| !thread.events.is_empty())
+            .map(|(thread_id, thread)| (thread_id, thread.label(thread_id), thread.events.clone()))
+            .collect()
+    }
+
+    /// Coalesce adjacent hits of the same timer shorter than `duration` into a single
+    /// event to keep long traces small. The timers themselves are not affected.
+    pub fn set_min_event_duration(&mut self, duration: Duration) {
+        let cycles = duration.as_secs_f64() * calculate_os_frequency();
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        {
+            self.min_event_cycles = cycles as u64;
+        }
+    }
+
+    /// Write the recorded events as `chrome://tracing` compatible JSON to `path`
+    ///
+    /// Each thread is written as its own lane in the trace.
+    ///
+    /// # Errors
+    ///
+    /// * Failed to create or write to `path`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn write_chrome_trace(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut out = std::io::BufWriter::new(file);
+
+        let os_timer_freq = calculate_os_frequency();
+        let cycles_per_us = os_timer_freq / 1_000_000.0;
+        let pid = std::process::id();
+
+        // Start the trace at the earliest start. Parents are recorded after their children.
+        let threads = self.thread_events();
+        let first_timestamp = threads
+            .iter()
+            .flat_map(|(_, _, events)| events)
+            .map(|event| event.start)
+            .min()
+            .unwrap_or(0);
+
+        write!(out, "{{\"displayTimeUnit\":\"ns\",\"traceEvents\":[")?;
+
+        let mut first = true;
+        for (thread_id, label, events) in &threads {
+            if !first {
+                write!(out, ",")?;
+            }
+            first = false;
+
+            // Name the lane for this thread
+            write!(
+                out,
+                "\n{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":{pid},\"tid\":{thread_id},\"args\":{{\"name\":\"{}\"}}}}",
+                json_escape(label)
+            )?;
+
+            for event in events {
+                let ts = event.start.wrapping_sub(first_timestamp) as f64 / cycles_per_us;
+
+                if event.instant {
+                    write!(
+                        out,
+                        ",\n{{\"name\":\"{}\",\"cat\":\"timeloop\",\"ph\":\"i\",\"s\":\"t\",\"ts\":{ts:.3},\"pid\":{pid},\"tid\":{thread_id}}}",
+                        json_escape(event.timer)
+                    )?;
+                    continue;
+                }
+
+                let dur = event.stop.wrapping_sub(event.start) as f64 / cycles_per_us;
+
+                write!(
+                    out,
+                    ",\n{{\"name\":\"{}\",\"cat\":\"timeloop\",\"ph\":\"X\",\"ts\":{ts:.3},\"dur\":{dur:.3},\"pid\":{pid},\"tid\":{thread_id},\"args\":{{\"hash\":\"{:016x}\"",
+                    json_escape(event.timer),
+                    timer_hash(event.timer)
+                )?;
+
+                if event.hits > 1 {
+                    write!(out, ",\"hits\":{}", event.hits)?;
+                }
+
+                write!(out, "}}}}")?;
+            }
+        }
+
+        let calibration = calibration();
+        writeln!(
+            out,
+            "\n],\"otherData\":{{\"tsc_frequency\":{},\"calibration_error\":{},\"clock_source\":\"{}\"}}}}",
+            calibration.frequency, calibration.error, calibration.source
+        )?;
+        out.flush()
+    }
+}
+
+/// Escape the given string to be used in a JSON string
+pub(crate) fn json_escape(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+
+    for c in input.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(result, "\\u{:04x}", c as u32);
+            }
+            c => result.push(c),
+        }
+    }
+
+    result
+}
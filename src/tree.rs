@@ -0,0 +1,121 @@
+//! Implements the hierarchical report of the timers nested under their parents
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::{calibration, rdtsc, Profiler};
+
+/// Time (in cycles, with child timers) spent in each full timer stack
+type StackTimes<'a> = BTreeMap<&'a [&'static str], u64>;
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Print the timers nested under their parents with the percent of the total time
+    /// and the percent of their parent's time
+    ///
+    /// Only the hits while [`Profiler::record_stacks`] is set are included.
+    pub fn print_tree(&mut self) {
+        let _ = self.write_tree(&mut std::io::stderr().lock());
+    }
+
+    /// Write the timers nested under their parents to `out`
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    pub fn write_tree(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        self.stop_running_threads(rdtsc());
+
+        let (_, total_time) = self.accumulate_timers();
+        let os_timer_freq = calibration().frequency;
+
+        // Add the exclusive time of every stack to all of its prefixes
+        let mut inclusive: StackTimes = BTreeMap::new();
+        for thread in self.thread_profiles_mut() {
+            for (stack, time) in &thread.stack_times {
+                for len in 1..=stack.len() {
+                    let entry = inclusive.entry(&stack[..len]).or_default();
+                    *entry = entry.wrapping_add(*time);
+                }
+            }
+        }
+
+        let name_width = inclusive
+            .keys()
+            .map(|stack| (stack.len() - 1) * 2 + stack.last().map_or(0, |name| name.len()))
+            .max()
+            .unwrap_or(0)
+            .max("TIMER".len());
+
+        writeln!(
+            out,
+            "{:<name_width$} | {:^10} | {:^7} | {:^7}",
+            "TIMER", "TIME", "TOTAL", "PARENT"
+        )?;
+
+        let tree = Tree {
+            inclusive: &inclusive,
+            total_time,
+            os_timer_freq,
+            name_width,
+        };
+        tree.write_children(&[], total_time, out)
+    }
+}
+
+/// The merged timer stacks being written as a tree
+struct Tree<'a> {
+    /// Time (in cycles, with child timers) of every stack prefix
+    inclusive: &'a StackTimes<'a>,
+
+    /// Total time (in cycles) of all profiled threads
+    total_time: u64,
+
+    /// The calculated frequency (in Hz) of the timestamp counter
+    os_timer_freq: f64,
+
+    /// Width of the indented timer name column
+    name_width: usize,
+}
+
+impl Tree<'_> {
+    /// Write the children of `parent`, the most expensive first, each followed by its
+    /// own children
+    #[allow(clippy::cast_precision_loss)]
+    fn write_children(
+        &self,
+        parent: &[&'static str],
+        parent_time: u64,
+        out: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let mut children: Vec<(&[&'static str], u64)> = self
+            .inclusive
+            .iter()
+            .filter(|(stack, _)| stack.len() == parent.len() + 1 && stack.starts_with(parent))
+            .map(|(stack, time)| (*stack, *time))
+            .collect();
+        children.sort_by_key(|(_, time)| std::cmp::Reverse(*time));
+
+        for (stack, time) in children {
+            let Some(name) = stack.last() else {
+                continue;
+            };
+
+            let indent = parent.len() * 2;
+            let name = format!("{:indent$}{name}", "");
+            let duration = Duration::from_secs_f64(time as f64 / self.os_timer_freq);
+            let total_percent = time as f64 / self.total_time.max(1) as f64 * 100.;
+            let parent_percent = time as f64 / parent_time.max(1) as f64 * 100.;
+
+            writeln!(
+                out,
+                "{name:<name_width$} | {:>10} | {total_percent:6.2}% | {parent_percent:6.2}%",
+                format!("{duration:.2?}"),
+                name_width = self.name_width,
+            )?;
+
+            self.write_children(stack, time, out)?;
+        }
+
+        Ok(())
+    }
+}
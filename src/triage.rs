@@ -0,0 +1,147 @@
+//! Implements the comparison of two snapshots along with the likely causes of a regression
+use std::io::Write;
+
+use crate::git::commits_between;
+use crate::{ProfilerSnapshot, TimerDelta};
+
+/// Number of the timers that got the slowest annotated as the likely causes
+const LIKELY_CAUSES: usize = 3;
+
+/// Number of commits listed between the commits of two snapshots
+const MAX_COMMITS: usize = 20;
+
+/// Format the percent change of `delta`, or `new` if it wasn't hit before
+fn percent_change(delta: &TimerDelta) -> String {
+    delta
+        .exclusive_time_percent()
+        .map_or_else(|| "new".to_string(), |percent| format!("{percent:+.2}%"))
+}
+
+impl ProfilerSnapshot {
+    /// Write every timer along with the absolute and percent change of its exclusive time
+    /// from this snapshot to the later `other` snapshot, such as two saved dumps
+    ///
+    /// If both snapshots carry their git commit (see [`crate::Profiler::capture_git_info`])
+    /// the commit range and the commits in it are written first. The timers whose exclusive
+    /// time grew the most are annotated as the likely causes, and summarized at the end
+    /// ready to paste into a regression report.
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+    pub fn write_comparison(
+        &self,
+        other: &ProfilerSnapshot,
+        out: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let range = match (&self.git, &other.git) {
+            (Some(before), Some(after)) => Some((before, after)),
+            _ => None,
+        };
+
+        if let Some((before, after)) = range {
+            writeln!(out, "Commits: {before}..{after}")?;
+
+            if let Some(commits) = commits_between(before, after) {
+                for commit in commits.iter().take(MAX_COMMITS) {
+                    writeln!(out, "  {commit}")?;
+                }
+
+                if commits.len() > MAX_COMMITS {
+                    writeln!(out, "  ... and {} more", commits.len() - MAX_COMMITS)?;
+                }
+            }
+        }
+
+        let mut deltas = self.diff(other);
+        deltas.sort_by_key(|delta| std::cmp::Reverse(delta.after.exclusive_time));
+
+        // Rank the timers that got slower by how much time they added
+        let mut slower: Vec<&TimerDelta> = deltas
+            .iter()
+            .filter(|delta| delta.exclusive_time() > 0)
+            .collect();
+        slower.sort_by_key(|delta| std::cmp::Reverse(delta.exclusive_time()));
+        slower.truncate(LIKELY_CAUSES);
+
+        let name_width = deltas
+            .iter()
+            .map(|delta| delta.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("TIMER".len())
+            .min(60);
+
+        writeln!(
+            out,
+            "{:<name_width$} | {:>16} | {:>16} | {:>17} | {:>9} | LIKELY CAUSE",
+            "TIMER", "BASELINE CYCLES", "CURRENT CYCLES", "CHANGE", "PERCENT"
+        )?;
+
+        for delta in &deltas {
+            let name = &delta.name[..delta.name.len().min(name_width)];
+            let change = delta.exclusive_time();
+
+            let arrow = match change.signum() {
+                1 => '▲',
+                -1 => '▼',
+                _ => ' ',
+            };
+
+            let cause = slower
+                .iter()
+                .position(|cause| cause.name == delta.name)
+                .map(|rank| format!("#{}", rank + 1))
+                .unwrap_or_default();
+
+            writeln!(
+                out,
+                "{name:<name_width$} | {:>16} | {:>16} | {arrow} {change:>+15} | {:>9} | {cause}",
+                delta.before.exclusive_time,
+                delta.after.exclusive_time,
+                percent_change(delta)
+            )?;
+        }
+
+        // Summarize the regression
+        writeln!(out)?;
+        match range {
+            Some((before, after)) => writeln!(out, "Regression triage {before}..{after}:")?,
+            None => writeln!(out, "Regression triage:")?,
+        }
+
+        let total_change = other.total_time.wrapping_sub(self.total_time) as i64;
+        write!(
+            out,
+            "  Total time: {} -> {} cycles ({total_change:+}",
+            self.total_time, other.total_time
+        )?;
+        if self.total_time > 0 {
+            write!(
+                out,
+                ", {:+.2}%",
+                total_change as f64 / self.total_time as f64 * 100.
+            )?;
+        }
+        writeln!(out, ")")?;
+
+        if slower.is_empty() {
+            return writeln!(out, "  No timer got slower");
+        }
+
+        writeln!(out, "  Likely caused by:")?;
+        for (rank, delta) in slower.iter().enumerate() {
+            writeln!(
+                out,
+                "    #{} {}: {:+} cycles ({})",
+                rank + 1,
+                delta.name,
+                delta.exclusive_time(),
+                percent_change(delta)
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+//! Implements the unit the times of the report are displayed in
+use std::time::Duration;
+
+use crate::Report;
+
+/// The unit the times of the printed report are displayed in
+///
+/// Raw cycles are only comparable between machines with the same timestamp counter
+/// frequency, while the other units are converted using the calibrated frequency.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum TimeUnit {
+    /// Raw timestamp counter cycles
+    #[default]
+    Cycles,
+
+    /// Nanoseconds
+    Nanoseconds,
+
+    /// Microseconds
+    Microseconds,
+
+    /// Milliseconds
+    Milliseconds,
+
+    /// The most readable unit for each time, from nanoseconds to seconds
+    Auto,
+}
+
+impl TimeUnit {
+    /// The label following the times in this unit that don't carry their own suffix
+    pub(crate) fn cycles_label(self) -> &'static str {
+        match self {
+            TimeUnit::Cycles => " cycles",
+            _ => "",
+        }
+    }
+
+    /// The header of the column of exclusive times in this unit
+    pub(crate) fn time_header(self) -> &'static str {
+        match self {
+            TimeUnit::Cycles => "Cycles",
+            _ => "Time",
+        }
+    }
+}
+
+impl Report {
+    /// Format a whole number of `cycles` in the display unit of the report
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn format_cycles(&self, cycles: u64) -> String {
+        self.format_time(cycles as f64)
+    }
+
+    /// Format a time of `cycles` in the display unit of the report
+    pub(crate) fn format_time(&self, cycles: f64) -> String {
+        let seconds = cycles / self.os_timer_freq;
+
+        match self.time_unit {
+            TimeUnit::Cycles => format!("{cycles:.0}"),
+            TimeUnit::Nanoseconds => format!("{:.0}ns", seconds * 1e9),
+            TimeUnit::Microseconds => format!("{:.2}µs", seconds * 1e6),
+            TimeUnit::Milliseconds => format!("{:.3}ms", seconds * 1e3),
+            TimeUnit::Auto => format!(
+                "{:.2?}",
+                Duration::try_from_secs_f64(seconds).unwrap_or_default()
+            ),
+        }
+    }
+}
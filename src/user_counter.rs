@@ -0,0 +1,152 @@
+//! Implements the named user counters attached to the innermost open timer
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::MutexGuard;
+
+use crate::alloc::{ALLOCS_HEADER, ALLOC_BYTES_HEADER};
+use crate::context_switch::{INVOLUNTARY_HEADER, VOLUNTARY_HEADER};
+use crate::page_faults::PAGE_FAULTS_HEADER;
+use crate::report::TimerReport;
+use crate::trace::json_escape;
+use crate::{is_enabled, Profiler, Report, ThreadProfile};
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Add `value` to the `counter` of the innermost timer open on the given thread, such
+    /// as the retries, allocations or cache misses of that scope
+    ///
+    /// Counts made outside of any timer, or while the timers are switched off by
+    /// [`crate::set_enabled`], are ignored.
+    pub fn count(&self, thread_id: usize, counter: &'static str, value: u64) {
+        if !is_enabled() {
+            return;
+        }
+
+        let mut thread = self.thread(thread_id);
+        let Some(timer) = thread.stack.last().copied() else {
+            return;
+        };
+
+        let count = thread.user_counters.entry((timer, counter)).or_default();
+        *count = count.wrapping_add(value);
+    }
+
+    /// Get the user counters of `timer` summed over every thread, sorted by name
+    #[must_use]
+    pub fn user_counters(&self, timer: &str) -> Vec<(&'static str, u64)> {
+        let threads: Vec<_> = self.thread_profiles().collect();
+        user_counters_of(&threads, timer)
+    }
+}
+
+/// Get the user counters of `timer` summed over the already locked `threads`, sorted
+/// by name
+pub(crate) fn user_counters_of<const TIMERS: usize>(
+    threads: &[MutexGuard<ThreadProfile<TIMERS>>],
+    timer: &str,
+) -> Vec<(&'static str, u64)> {
+    let mut counters: BTreeMap<&'static str, u64> = BTreeMap::new();
+
+    for thread in threads {
+        for ((counter_timer, counter), value) in &thread.user_counters {
+            if *counter_timer == timer {
+                let total = counters.entry(counter).or_default();
+                *total = total.wrapping_add(*value);
+            }
+        }
+    }
+
+    counters.into_iter().collect()
+}
+
+/// The headers of the built-in counter columns, shown before the user counters
+const BUILTIN_COLUMNS: [&str; 5] = [
+    PAGE_FAULTS_HEADER,
+    VOLUNTARY_HEADER,
+    INVOLUNTARY_HEADER,
+    ALLOCS_HEADER,
+    ALLOC_BYTES_HEADER,
+];
+
+impl Report {
+    /// Get the name of every counter column in the report with its width: the page
+    /// faults, context switches and allocations if they were recorded, then the user
+    /// counters by name
+    pub(crate) fn counter_columns(&self) -> Vec<(&'static str, usize)> {
+        let mut columns: BTreeMap<&'static str, usize> = BTreeMap::new();
+
+        for timer in &self.timers {
+            for (counter, value) in timer.counter_values() {
+                let width = columns.entry(counter).or_insert(counter.len());
+                *width = (*width).max(value.to_string().len());
+            }
+        }
+
+        let builtin = BUILTIN_COLUMNS
+            .iter()
+            .filter_map(|header| columns.get(header).map(|width| (*header, *width)));
+        let user = columns
+            .iter()
+            .filter(|(counter, _)| !BUILTIN_COLUMNS.contains(counter))
+            .map(|(counter, width)| (*counter, *width));
+
+        builtin.chain(user).collect()
+    }
+}
+
+impl TimerReport {
+    /// Get the value of every counter column of this timer: the page faults, context
+    /// switches and allocations if they were recorded, then the user counters
+    fn counter_values(&self) -> Vec<(&'static str, u64)> {
+        let mut values = Vec::new();
+
+        if let Some(faults) = self.page_faults {
+            values.push((PAGE_FAULTS_HEADER, faults));
+        }
+
+        if let Some(switches) = self.context_switches {
+            values.push((VOLUNTARY_HEADER, switches.voluntary));
+            values.push((INVOLUNTARY_HEADER, switches.involuntary));
+        }
+
+        if let Some(allocations) = self.allocations {
+            values.push((ALLOCS_HEADER, allocations.count));
+            values.push((ALLOC_BYTES_HEADER, allocations.bytes));
+        }
+
+        values.extend(self.counters.iter().copied());
+        values
+    }
+
+    /// Get the value of the counter column `counter` of this timer, if it was counted
+    pub(crate) fn counter(&self, counter: &str) -> Option<u64> {
+        self.counter_values()
+            .into_iter()
+            .find(|(name, _)| *name == counter)
+            .map(|(_, value)| value)
+    }
+
+    /// Format the user counters of this timer as a JSON object
+    pub(crate) fn counters_json(&self) -> String {
+        let fields: Vec<String> = self
+            .counters
+            .iter()
+            .map(|(counter, value)| format!("\"{}\":{value}", json_escape(counter)))
+            .collect();
+
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+/// Format the cells of the user counter `columns` (including the separators), using
+/// `cell` to get the contents of each column
+pub(crate) fn counter_cells(
+    columns: &[(&'static str, usize)],
+    cell: impl Fn(&'static str) -> String,
+) -> String {
+    columns
+        .iter()
+        .fold(String::new(), |mut cells, (counter, width)| {
+            let _ = write!(cells, " | {:>width$}", cell(counter));
+            cells
+        })
+}
@@ -0,0 +1,125 @@
+//! Implements the report of only the most recent window of time
+use std::io::Write;
+use std::sync::atomic::Ordering;
+use std::sync::{PoisonError, TryLockError};
+use std::time::Duration;
+
+use crate::{calculate_os_frequency, rdtsc, Profiler, Report, ReportRenderer, TextRenderer};
+
+/// Number of snapshots kept across the window, bounding how far past the window the
+/// report may reach
+const WINDOW_SLICES: u64 = 10;
+
+impl<const THREADS: usize, const TIMERS: usize> Profiler<THREADS, TIMERS> {
+    /// Keep the timers of only the last `window` of time next to the lifetime totals, for
+    /// [`Profiler::window_report`] to show the recent behavior of a long-running process
+    ///
+    /// The window is made of snapshots taken as the timers exit and whenever the window
+    /// is ticked or reported, at most one every tenth of the window, so the reported
+    /// window starts at the newest snapshot taken before the window started. A zero
+    /// `window` turns it off.
+    pub fn set_window(&mut self, window: Duration) {
+        let cycles = window.as_secs_f64() * calculate_os_frequency();
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        {
+            self.window_cycles = cycles as u64;
+        }
+
+        self.window_snapshots
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.tick_window();
+    }
+
+    /// Take a snapshot for the window if the last one is older than a slice of the
+    /// window, dropping the snapshots that are no longer needed. Called by the first
+    /// timer exit after every slice, this keeps the start of the window close to the
+    /// length of the window ago. Tick it manually if no timers exit for a while.
+    ///
+    /// Only one thread ticks the window at a time, the others skip the tick.
+    pub fn tick_window(&self) {
+        if self.window_cycles == 0 {
+            self.window_next_tick.store(u64::MAX, Ordering::Relaxed);
+            return;
+        }
+
+        let mut snapshots = match self.window_snapshots.try_lock() {
+            Ok(snapshots) => snapshots,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return,
+        };
+
+        let now = rdtsc();
+        let slice = self.window_cycles / WINDOW_SLICES;
+        let due = snapshots
+            .back()
+            .is_none_or(|(taken, _)| now.wrapping_sub(*taken) >= slice);
+
+        if due {
+            snapshots.push_back((now, self.snapshot()));
+            self.window_next_tick
+                .store(now.wrapping_add(slice), Ordering::Relaxed);
+        }
+
+        // Keep the newest snapshot taken before the window started as its start
+        let window_start = now.saturating_sub(self.window_cycles);
+        while snapshots
+            .get(1)
+            .is_some_and(|(taken, _)| *taken <= window_start)
+        {
+            snapshots.pop_front();
+        }
+    }
+
+    /// Get the report of only the last window set by [`Profiler::set_window`], along
+    /// with the length (in cycles) of the reported window
+    ///
+    /// Only the hits, times and bytes are windowed: the shortest and longest hits and the
+    /// variance are those of the whole lifetime. The lifetime report is returned if no
+    /// window was set.
+    pub fn window_report(&mut self) -> (Report, u64) {
+        self.tick_window();
+
+        let start = self
+            .window_snapshots
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .front()
+            .cloned();
+        if let Some((taken, snapshot)) = start {
+            return (self.since(&snapshot), rdtsc().wrapping_sub(taken));
+        }
+
+        let report = self.create_report();
+        let total_time = report.total_time;
+        (report, total_time)
+    }
+
+    /// Print the report of only the last window set by [`Profiler::set_window`]
+    pub fn print_window(&mut self) {
+        let _ = self.print_window_to(&mut std::io::stderr().lock());
+    }
+
+    /// Write the report of only the last window set by [`Profiler::set_window`] to `out`
+    ///
+    /// # Errors
+    ///
+    /// * Failed to write to `out`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn print_window_to(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        let (mut report, window) = self.window_report();
+
+        // Timers not hit during the window have nothing to show
+        report.timers.retain(|timer| timer.hits > 0);
+
+        let window = Duration::from_secs_f64(window as f64 / report.os_timer_freq);
+        writeln!(out, "Last {window:.2?}:")?;
+
+        TextRenderer {
+            color: self.color.enabled(),
+        }
+        .render(&report, out)
+    }
+}
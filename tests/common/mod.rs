@@ -0,0 +1,49 @@
+//! Helpers shared by the integration tests
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use timeloop::Profiler;
+
+/// The profiler used by the tests, small enough to live on the stack
+pub type TestProfiler = Profiler<4, 16>;
+
+/// Record a hit of `timer` on `thread` taking exactly `cycles`
+pub fn hit(profiler: &mut TestProfiler, thread: usize, timer: &'static str, cycles: u64) {
+    let mut open = profiler.enter_timer(thread, timer, 0);
+    open.start_time = 1_000;
+    profiler.exit_timer(thread, &open, 1_000 + cycles);
+}
+
+/// Record a hit of `parent` taking `parent_cycles` on `thread` with a single hit of
+/// `child` taking `child_cycles` inside of it
+pub fn nested_hit(
+    profiler: &mut TestProfiler,
+    thread: usize,
+    (parent, parent_cycles): (&'static str, u64),
+    (child, child_cycles): (&'static str, u64),
+) {
+    let mut outer = profiler.enter_timer(thread, parent, 0);
+    outer.start_time = 1_000;
+
+    let mut inner = profiler.enter_timer(thread, child, 0);
+    inner.start_time = 1_000;
+    profiler.exit_timer(thread, &inner, 1_000 + child_cycles);
+
+    profiler.exit_timer(thread, &outer, 1_000 + parent_cycles);
+}
+
+/// Record real hits of `timer` on `thread` of a started profiler, so that the total time
+/// of the thread covers the hits
+pub fn timed_hits(profiler: &mut TestProfiler, thread: usize, timer: &'static str, hits: u32) {
+    for _ in 0..hits {
+        let open = profiler.enter_timer(thread, timer, 0);
+        std::hint::black_box((0..1_000).sum::<u64>());
+        profiler.exit_timer(thread, &open, timeloop::_timestamp());
+    }
+}
+
+/// A path in the temporary directory unique to this process and `name`
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("timeloop-test-{}-{name}", std::process::id()))
+}
@@ -0,0 +1,56 @@
+//! Tests of evicting timers from the static profiler while other threads are timing
+#![cfg(feature = "enable")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+timeloop::create_profiler!(threads = 8, timers = 8);
+
+const THREADS: usize = 4;
+const HITS: usize = 500;
+
+#[test]
+fn timers_are_evicted_while_other_threads_time_and_read() {
+    let names: Vec<Vec<&'static str>> = (0..THREADS)
+        .map(|thread| {
+            (0..20)
+                .map(|timer| timeloop::intern(&format!("thread {thread} timer {timer}")))
+                .collect()
+        })
+        .collect();
+    let done = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        // Every hit is either in its own timer or folded into the overflow timer, so a
+        // snapshot never sees more hits than were recorded
+        scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                let snapshot = TIMELOOP_PROFILER.snapshot();
+                let hits: u64 = snapshot.timers.values().map(|timer| timer.hits).sum();
+                assert!(hits <= (2 * THREADS * HITS) as u64);
+            }
+        });
+
+        let workers: Vec<_> = names
+            .iter()
+            .map(|names| {
+                scope.spawn(move || {
+                    timeloop::start_thread!();
+                    for hit in 0..HITS {
+                        timeloop::scoped_timer!("outer");
+                        timeloop::scoped_timer!(names[hit % names.len()]);
+                    }
+                    timeloop::stop_thread!();
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+    });
+
+    let snapshot = TIMELOOP_PROFILER.snapshot();
+    let hits: u64 = snapshot.timers.values().map(|timer| timer.hits).sum();
+    assert_eq!(hits, (2 * THREADS * HITS) as u64);
+    assert!(snapshot.timers.contains_key("Other"));
+}
@@ -0,0 +1,100 @@
+//! Tests of the JSON, chrome trace and folded stack exports
+mod common;
+
+use common::{nested_hit, timed_hits, TestProfiler};
+use serde_json::Value;
+use timeloop::{JsonRenderer, ReportRenderer};
+
+/// Render the report of `profiler` as JSON and parse it back
+fn json_report(profiler: &mut TestProfiler) -> Value {
+    let report = profiler.create_report();
+
+    let mut out = Vec::new();
+    JsonRenderer.render(&report, &mut out).unwrap();
+    serde_json::from_slice(&out).expect("The JSON report is not valid JSON")
+}
+
+#[test]
+fn json_report_is_valid_json() {
+    let mut profiler = TestProfiler::new();
+    profiler.start(0);
+    timed_hits(&mut profiler, 0, "with \"quotes\"", 3);
+    profiler.stop(0);
+
+    let json = json_report(&mut profiler);
+    let timers = json["timers"].as_array().unwrap();
+
+    assert_eq!(timers.len(), 1);
+    assert_eq!(timers[0]["name"], "with \"quotes\"");
+    assert_eq!(timers[0]["hits"], 3);
+    assert!(json["total_time"].as_u64().unwrap() >= timers[0]["exclusive_time"].as_u64().unwrap());
+}
+
+// The counters build records neither the events nor the stacks
+#[cfg(not(feature = "counters"))]
+#[test]
+fn chrome_trace_is_valid_json() {
+    let mut profiler = TestProfiler::new();
+    profiler.record_events = true;
+    nested_hit(&mut profiler, 1, ("parent", 100), ("child", 40));
+
+    let path = common::temp_path("trace.json");
+    profiler.write_chrome_trace(&path).unwrap();
+    let trace: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let events = trace["traceEvents"].as_array().unwrap();
+    let names: Vec<&str> = events
+        .iter()
+        .filter(|event| event["ph"] == "X")
+        .map(|event| event["name"].as_str().unwrap())
+        .collect();
+
+    assert_eq!(names, ["child", "parent"]);
+    assert!(events.iter().all(|event| event["tid"] == 1));
+}
+
+#[cfg(not(feature = "counters"))]
+#[test]
+fn folded_stacks_keep_only_the_exclusive_time() {
+    let mut profiler = TestProfiler::new();
+    profiler.record_stacks = true;
+    nested_hit(&mut profiler, 0, ("parent", 100), ("child", 40));
+    nested_hit(&mut profiler, 2, ("parent", 50), ("other", 10));
+
+    let mut folded = Vec::new();
+    profiler.write_folded_stacks(&mut folded).unwrap();
+
+    assert_eq!(
+        String::from_utf8(folded).unwrap(),
+        "parent 100\nparent;child 40\nparent;other 10\n"
+    );
+}
+
+#[test]
+fn json_report_without_total_time_is_valid_json() {
+    // The hits are recorded without ever starting the thread, so the total time is 0
+    let mut profiler = TestProfiler::new();
+    nested_hit(&mut profiler, 0, ("parent", 100), ("child", 40));
+
+    let json = json_report(&mut profiler);
+
+    assert_eq!(json["total_time"], 0);
+    assert_eq!(json["remainder"], 0);
+    assert!(json["timers"][0]["percent"].is_null());
+}
+
+#[test]
+fn folded_stacks_are_empty_unless_recorded() {
+    let mut profiler = TestProfiler::new();
+    nested_hit(&mut profiler, 0, ("parent", 100), ("child", 40));
+
+    let mut folded = Vec::new();
+    profiler.write_folded_stacks(&mut folded).unwrap();
+
+    assert!(folded.is_empty());
+
+    let mut open = Vec::new();
+    profiler.write_open_timers(&mut open).unwrap();
+    assert!(open.is_empty());
+}
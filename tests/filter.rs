@@ -0,0 +1,47 @@
+//! Tests of the glob and regex timer filters
+use timeloop::TimerFilter;
+
+/// Check if `name` passes the filter `pattern`
+fn matches(pattern: &str, name: &str) -> bool {
+    TimerFilter::new(pattern).unwrap().matches(name)
+}
+
+#[test]
+fn glob_matches_the_whole_name() {
+    assert!(matches("parse", "parse"));
+    assert!(!matches("parse", "parse_header"));
+    assert!(!matches("parse", "reparse"));
+}
+
+#[test]
+fn glob_wildcards() {
+    assert!(matches("parse*", "parse_header"));
+    assert!(matches("*header", "parse_header"));
+    assert!(matches("p*e*r", "parse_header"));
+    assert!(matches("*", ""));
+    assert!(matches("pars?", "parse"));
+    assert!(!matches("pars?", "pars"));
+    assert!(!matches("*x*", "parse_header"));
+}
+
+#[test]
+fn glob_backtracks_over_repeated_characters() {
+    assert!(matches("*aab", "aaaab"));
+    assert!(matches("a*a*a", "aaa"));
+    assert!(!matches("a*a*a", "aa"));
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regex_matches_anywhere_in_the_name() {
+    assert!(matches("re:^Fn__", "Fn__parse"));
+    assert!(matches("re:head", "parse_header"));
+    assert!(!matches("re:^head", "parse_header"));
+    assert!(TimerFilter::new("re:(").is_err());
+}
+
+#[cfg(not(feature = "regex"))]
+#[test]
+fn regex_needs_the_regex_feature() {
+    assert!(TimerFilter::new("re:^Fn__").is_err());
+}
@@ -0,0 +1,45 @@
+//! Tests of the profiler in the child processes forked from a profiled process
+#![cfg(feature = "enable")]
+timeloop::create_profiler!();
+
+extern "C" {
+    fn fork() -> i32;
+    fn waitpid(pid: i32, status: *mut i32, options: i32) -> i32;
+    fn _exit(status: i32) -> !;
+}
+
+#[test]
+fn child_only_reports_the_time_after_the_fork() {
+    timeloop::start_profiler!();
+    for _ in 0..3 {
+        timeloop::scoped_timer!("parent");
+    }
+
+    // Keep the profiler locked for reading while forking from another thread
+    let reader = std::thread::spawn(|| {
+        for _ in 0..100 {
+            let _ = TIMELOOP_PROFILER.snapshot();
+        }
+    });
+
+    let pid = unsafe { fork() };
+    assert!(pid >= 0, "fork failed");
+
+    if pid == 0 {
+        {
+            timeloop::scoped_timer!("child");
+        }
+
+        let snapshot = TIMELOOP_PROFILER.snapshot();
+        let inherited = snapshot.timers.contains_key("parent");
+        let hits = snapshot.timers.get("child").map_or(0, |timer| timer.hits);
+        unsafe { _exit(i32::from(inherited || hits != 1)) }
+    }
+
+    let mut status = 0;
+    assert_eq!(unsafe { waitpid(pid, &raw mut status, 0) }, pid);
+    assert_eq!(status, 0, "The child reported the timers of its parent");
+
+    reader.join().unwrap();
+    assert_eq!(TIMELOOP_PROFILER.snapshot().timers["parent"].hits, 3);
+}
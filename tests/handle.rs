@@ -0,0 +1,106 @@
+//! Tests of the profilers owned by a library instead of the global profiler
+use std::sync::Barrier;
+
+use timeloop::ProfilerHandle;
+
+static PROFILER: ProfilerHandle<2, 16> = ProfilerHandle::new();
+
+#[test]
+fn handle_slots_are_independent_of_the_thread_slots() {
+    const HOLDERS: usize = 4;
+    let acquired = Barrier::new(HOLDERS + 1);
+    let released = Barrier::new(HOLDERS + 1);
+
+    std::thread::scope(|scope| {
+        // Hold more thread slots at once than the profiler has slots
+        for _ in 0..HOLDERS {
+            scope.spawn(|| {
+                let _ = timeloop::_thread_slot();
+                acquired.wait();
+                released.wait();
+            });
+        }
+        acquired.wait();
+
+        let worker = scope.spawn(|| {
+            for _ in 0..3 {
+                let _timer = PROFILER.timer("work");
+            }
+            timeloop::_thread_slot()
+        });
+        assert!(worker.join().unwrap() >= HOLDERS);
+
+        for _ in 0..2 {
+            let _timer = PROFILER.timer("work");
+        }
+        released.wait();
+    });
+
+    let report = PROFILER.report();
+    let work = report
+        .timers
+        .iter()
+        .find(|timer| timer.name == "work")
+        .unwrap();
+    assert_eq!(work.hits, 5);
+}
+
+#[test]
+fn slots_of_exited_threads_are_bound_again() {
+    const ROUNDS: usize = 4;
+    let profiler = ProfilerHandle::<2, 16>::new();
+    let released = Barrier::new(ROUNDS + 1);
+
+    std::thread::scope(|scope| {
+        let released = &released;
+        for _ in 0..ROUNDS {
+            // Keep the thread slot of the previous worker held, so every worker takes a
+            // thread slot never used by the profiler
+            let (acquired, holder) = std::sync::mpsc::channel();
+            scope.spawn(move || {
+                let _ = timeloop::_thread_slot();
+                acquired.send(()).unwrap();
+                released.wait();
+            });
+            holder.recv().unwrap();
+
+            std::thread::scope(|worker| {
+                worker.spawn(|| {
+                    let _timer = profiler.timer("work");
+                });
+            });
+        }
+        released.wait();
+    });
+
+    let report = profiler.report();
+    let work = report
+        .timers
+        .iter()
+        .find(|timer| timer.name == "work")
+        .unwrap();
+    assert_eq!(work.hits, ROUNDS as u64);
+}
+
+#[test]
+fn canceled_timer_is_not_recorded() {
+    let profiler = ProfilerHandle::<2, 16>::new();
+
+    {
+        let mut timer = profiler.timer("canceled");
+        timer.cancel();
+    }
+    {
+        let mut timer = profiler.timer("read");
+        timer.set_bytes(64);
+    }
+
+    let report = profiler.report();
+    assert!(report.timers.iter().all(|timer| timer.name != "canceled"));
+    let read = report
+        .timers
+        .iter()
+        .find(|timer| timer.name == "read")
+        .unwrap();
+    assert_eq!(read.bytes_processed, 64);
+}
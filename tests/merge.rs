@@ -0,0 +1,73 @@
+//! Tests of merging the timers of two profilers
+mod common;
+
+use common::{hit, nested_hit, TestProfiler};
+use timeloop::Profiler;
+
+#[test]
+fn timers_are_matched_by_name() {
+    let mut first = TestProfiler::new();
+    hit(&mut first, 0, "a", 10);
+    hit(&mut first, 0, "b", 20);
+
+    // Register the timers in the opposite order to give them other indexes
+    let mut second = TestProfiler::new();
+    hit(&mut second, 0, "b", 200);
+    hit(&mut second, 0, "a", 100);
+    hit(&mut second, 0, "c", 5);
+
+    first.merge(&second);
+
+    let snapshot = first.snapshot();
+    assert_eq!(snapshot.timers["a"].exclusive_time, 110);
+    assert_eq!(snapshot.timers["a"].hits, 2);
+    assert_eq!(snapshot.timers["b"].exclusive_time, 220);
+    assert_eq!(snapshot.timers["b"].max_cycles, 200);
+    assert_eq!(snapshot.timers["c"].hits, 1);
+}
+
+#[test]
+fn extra_threads_are_merged_into_the_last_thread() {
+    let mut small: Profiler<2, 16> = Profiler::new();
+
+    let mut large = TestProfiler::new();
+    hit(&mut large, 1, "timer", 10);
+    hit(&mut large, 3, "timer", 30);
+
+    small.merge(&large);
+
+    let snapshot = small.snapshot();
+    assert_eq!(snapshot.timers["timer"].hits, 2);
+    assert_eq!(snapshot.timers["timer"].exclusive_time, 40);
+}
+
+#[test]
+fn categories_are_merged() {
+    let mut first = TestProfiler::new();
+    let mut second = TestProfiler::new();
+    second.set_category("child", "io");
+    nested_hit(&mut second, 0, ("parent", 100), ("child", 40));
+
+    first.merge(&second);
+
+    assert_eq!(first.category("child"), Some("io"));
+}
+
+// The counters build records no stacks
+#[cfg(not(feature = "counters"))]
+#[test]
+fn stacks_are_merged() {
+    let mut first = TestProfiler::new();
+    let mut second = TestProfiler::new();
+    second.record_stacks = true;
+    nested_hit(&mut second, 0, ("parent", 100), ("child", 40));
+
+    first.merge(&second);
+
+    let mut folded = Vec::new();
+    first.write_folded_stacks(&mut folded).unwrap();
+    assert_eq!(
+        String::from_utf8(folded).unwrap(),
+        "parent 60\nparent;child 40\n"
+    );
+}
@@ -0,0 +1,21 @@
+//! Tests of dumping the report when a thread panics
+mod common;
+
+use common::TestProfiler;
+use timeloop::{install_panic_dump, ProfilerCell};
+
+#[test]
+fn panic_while_recording_skips_the_report() {
+    let profiler: &'static ProfilerCell<TestProfiler> =
+        Box::leak(Box::new(ProfilerCell::new(TestProfiler::new())));
+    install_panic_dump(profiler);
+
+    // Panic while holding the slot of a thread, as if a timer panicked while exiting
+    let result = std::panic::catch_unwind(|| {
+        let profiler = profiler.read();
+        let _slot = profiler.threads[0].lock().unwrap();
+        panic!("panic while recording");
+    });
+
+    assert!(result.is_err());
+}
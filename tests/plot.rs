@@ -0,0 +1,79 @@
+//! Tests of plotting JSON results with `timeloop-cli plot`
+mod common;
+
+use std::process::Command;
+
+use timeloop::SvgPlot;
+
+/// Plot the JSON `results` with `timeloop-cli`, returning the SVG it wrote
+fn plot(name: &str, results: &str) -> String {
+    let path = common::temp_path(name);
+    std::fs::write(&path, results).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_timeloop-cli"))
+        .arg("plot")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn sweep_is_plotted_as_a_line_per_variant() {
+    let svg = plot(
+        "sweep.json",
+        r#"[
+            {"variant": "std", "size": 1024, "gb_per_sec": 2.5},
+            {"variant": "std", "size": 4096, "gb_per_sec": 5.0},
+            {"variant": "simd <avx2>", "size": 1024, "gb_per_sec": 4.0},
+            {"variant": "simd <avx2>", "size": 4096, "gb_per_sec": 10.0}
+        ]"#,
+    );
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<polyline").count(), 2);
+    assert_eq!(svg.matches("<circle").count(), 4);
+    assert!(svg.contains(">std</text>"));
+    assert!(svg.contains(">simd &lt;avx2&gt;</text>"));
+    assert!(svg.contains(">Throughput (GB/s)</text>"));
+    assert!(svg.contains("<title>4096: 10.00</title>"));
+}
+
+#[test]
+fn criterion_messages_are_plotted_by_throughput() {
+    let messages = [
+        r#"{"reason":"benchmark-complete","id":"copy/std/1024","typical":{"estimate":512.0,"unit":"ns"},"throughput":[{"per_iteration":1024,"unit":"bytes"}]}"#,
+        r#"{"reason":"benchmark-complete","id":"copy/std/4096","typical":{"estimate":1024.0,"unit":"ns"},"throughput":[{"per_iteration":4096,"unit":"bytes"}]}"#,
+        r#"{"reason":"group-complete","group_name":"copy","benchmarks":["copy/std/1024","copy/std/4096"]}"#,
+    ];
+
+    let plot = SvgPlot::from_json(&messages.join("\n")).unwrap();
+    assert_eq!(plot.series.len(), 1);
+    assert_eq!(plot.series[0].name, "copy/std");
+    assert_eq!(plot.series[0].points, [(1024.0, 2.0), (4096.0, 4.0)]);
+    assert!(plot.log_x);
+}
+
+#[test]
+fn invalid_results_are_an_error() {
+    let path = common::temp_path("invalid.json");
+    std::fs::write(&path, "{\"rows\": [1, 2").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_timeloop-cli"))
+        .arg("plot")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
@@ -0,0 +1,108 @@
+//! Tests of the quantile sketches and histograms of the hit durations
+mod common;
+
+use timeloop::{DDSketch, Log2Histogram};
+
+/// Check that `actual` is within `accuracy` of `expected`, relative to `expected`
+#[allow(clippy::cast_precision_loss)]
+fn assert_within(actual: u64, expected: u64, accuracy: f64) {
+    let error = (actual as f64 - expected as f64).abs() / expected as f64;
+    assert!(
+        error <= accuracy,
+        "{actual} is not within {accuracy} of {expected}"
+    );
+}
+
+#[test]
+fn quantiles_are_within_the_relative_accuracy() {
+    let mut sketch = DDSketch::with_accuracy(0.01);
+    for value in 1..=10_000 {
+        sketch.add(value);
+    }
+
+    assert_eq!(sketch.count(), 10_000);
+    assert_eq!(sketch.quantile(0.0), Some(1));
+
+    for (q, expected) in [(0.5, 5_000), (0.9, 9_000), (0.99, 9_900), (1.0, 10_000)] {
+        assert_within(sketch.quantile(q).unwrap(), expected, 0.01);
+    }
+}
+
+#[test]
+fn empty_sketch_has_no_quantiles() {
+    assert_eq!(DDSketch::default().quantile(0.5), None);
+}
+
+#[test]
+fn zeros_are_counted_without_a_bin() {
+    let mut sketch = DDSketch::default();
+    for value in [0, 0, 0, 100] {
+        sketch.add(value);
+    }
+
+    assert_eq!(sketch.quantile(0.5), Some(0));
+    assert_within(sketch.quantile(1.0).unwrap(), 100, 0.01);
+}
+
+#[test]
+fn merged_sketches_match_a_single_sketch() {
+    let (mut low, mut high, mut all) = (
+        DDSketch::default(),
+        DDSketch::default(),
+        DDSketch::default(),
+    );
+
+    for value in 1..=500 {
+        low.add(value);
+        all.add(value);
+    }
+
+    for value in 501..=1_000 {
+        high.add(value);
+        all.add(value);
+    }
+
+    low.merge(&high);
+    assert_eq!(low.count(), all.count());
+
+    for q in [0.1, 0.5, 0.75, 0.99] {
+        assert_eq!(low.quantile(q), all.quantile(q));
+    }
+}
+
+#[test]
+fn log2_buckets_count_powers_of_two() {
+    let mut histogram = Log2Histogram::default();
+    for cycles in [0, 1, 2, 3, 4, 1_023, 1_024] {
+        histogram.record(cycles);
+    }
+
+    assert_eq!(histogram.total_count(), 7);
+    assert_eq!(histogram.buckets[0], 2);
+    assert_eq!(histogram.buckets[1], 2);
+    assert_eq!(histogram.buckets[2], 1);
+    assert_eq!(histogram.buckets[9], 1);
+    assert_eq!(histogram.buckets[10], 1);
+}
+
+// The counters build records neither the sketches nor the percentiles
+#[cfg(not(feature = "counters"))]
+#[test]
+fn profiler_keeps_sketches_and_percentiles() {
+    let mut profiler = common::TestProfiler::new();
+    profiler.record_percentiles = true;
+    profiler.enable_sketch("timer");
+
+    for cycles in 1..=100 {
+        common::hit(&mut profiler, 0, "timer", cycles);
+    }
+
+    let sketch = profiler.sketch("timer").unwrap();
+    assert_eq!(sketch.count(), 100);
+    assert_within(sketch.quantile(0.5).unwrap(), 50, 0.02);
+
+    let percentiles = profiler.percentiles("timer").unwrap();
+    assert!((49..=51).contains(&percentiles.p50));
+    assert!((89..=91).contains(&percentiles.p90));
+    assert!((98..=100).contains(&percentiles.p99));
+}
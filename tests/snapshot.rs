@@ -0,0 +1,167 @@
+//! Tests of the snapshots and their binary format
+mod common;
+
+use common::{hit, temp_path, TestProfiler};
+use std::time::Duration;
+
+use timeloop::{CpuCache, CpuInfo, GitInfo, ProfilerCell, ProfilerSnapshot};
+
+#[test]
+fn saved_snapshot_loads_back_identically() {
+    let mut profiler = TestProfiler::new();
+    for cycles in [10, 25, 40] {
+        hit(&mut profiler, 0, "first", cycles);
+    }
+    hit(&mut profiler, 1, "second", 7);
+
+    let mut snapshot = profiler.snapshot();
+    snapshot.cpu = Some(CpuInfo {
+        brand: "Test \"CPU\"".to_string(),
+        logical_cpus: 8,
+        physical_cores: 4,
+        packages: 1,
+        caches: vec![CpuCache {
+            level: 1,
+            kind: "Data".to_string(),
+            size: 32 * 1024,
+        }],
+    });
+    snapshot.git = Some(GitInfo {
+        commit: "0123456789abcdef".to_string(),
+        dirty: true,
+    });
+
+    let path = temp_path("snapshot-roundtrip");
+    snapshot.save(&path).unwrap();
+    let loaded = ProfilerSnapshot::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, snapshot);
+    assert_eq!(loaded.timers["first"].hits, 3);
+    assert_eq!(loaded.timers["first"].min_cycles, 10);
+}
+
+#[test]
+fn loading_rejects_other_files() {
+    let path = temp_path("snapshot-invalid");
+    std::fs::write(&path, b"NOTATIMELOOPSNAPSHOT").unwrap();
+    let error = ProfilerSnapshot::load(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn diff_covers_the_timers_of_both_snapshots() {
+    let mut profiler = TestProfiler::new();
+    hit(&mut profiler, 0, "kept", 100);
+    hit(&mut profiler, 0, "removed", 50);
+    let before = profiler.snapshot();
+
+    let mut profiler = TestProfiler::new();
+    hit(&mut profiler, 0, "kept", 150);
+    hit(&mut profiler, 0, "added", 20);
+    let after = profiler.snapshot();
+
+    let deltas = before.diff(&after);
+    let names: Vec<&str> = deltas.iter().map(|delta| delta.name.as_str()).collect();
+    assert_eq!(names, ["added", "kept", "removed"]);
+
+    assert_eq!(deltas[0].exclusive_time(), 20);
+    assert_eq!(deltas[0].exclusive_time_percent(), None);
+    assert_eq!(deltas[1].exclusive_time(), 50);
+    assert_eq!(deltas[1].exclusive_time_percent(), Some(50.0));
+    assert_eq!(deltas[2].hits(), -1);
+}
+
+#[test]
+fn since_only_reports_the_later_hits() {
+    let mut profiler = TestProfiler::new();
+    hit(&mut profiler, 0, "timer", 100);
+    let snapshot = profiler.snapshot();
+    hit(&mut profiler, 0, "timer", 30);
+
+    let report = profiler.since(&snapshot);
+    let timer = &report.timers[0];
+    assert_eq!(timer.hits, 1);
+    assert_eq!(timer.exclusive_time, 30);
+}
+
+#[test]
+fn persisted_snapshot_loads_back() {
+    let mut profiler = TestProfiler::new();
+    hit(&mut profiler, 0, "first", 10);
+    let profiler: &'static ProfilerCell<TestProfiler> =
+        Box::leak(Box::new(ProfilerCell::new(profiler)));
+
+    let path = temp_path("persisted.bin");
+    timeloop::spawn_persist(&path, Duration::from_millis(5), profiler).unwrap();
+
+    // Let the writer alternate between the slots a few times
+    std::thread::sleep(Duration::from_millis(100));
+
+    let loaded = ProfilerSnapshot::load_persisted(&path).unwrap();
+    assert_eq!(loaded, profiler.read().snapshot());
+}
+
+#[test]
+fn persisted_snapshot_too_large_keeps_the_previous_one() {
+    let mut profiler = TestProfiler::new();
+    hit(&mut profiler, 0, "first", 10);
+    let first = profiler.snapshot();
+    let profiler: &'static ProfilerCell<TestProfiler> =
+        Box::leak(Box::new(ProfilerCell::new(profiler)));
+
+    let path = temp_path("persisted-overflow.bin");
+    timeloop::spawn_persist(&path, Duration::from_millis(5), profiler).unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+
+    // A timer name larger than a whole slot
+    let huge: &'static str = Box::leak("x".repeat(1024 * 1024).into_boxed_str());
+    hit(&mut profiler.write(), 0, huge, 10);
+    std::thread::sleep(Duration::from_millis(50));
+
+    let loaded = ProfilerSnapshot::load_persisted(&path).unwrap();
+    assert_eq!(loaded, first);
+}
+
+#[test]
+fn comparison_names_the_commit_range_and_the_likely_causes() {
+    let mut profiler = TestProfiler::new();
+    hit(&mut profiler, 0, "slow", 10);
+    hit(&mut profiler, 0, "slower", 100);
+    hit(&mut profiler, 0, "faster", 100);
+    let mut before = profiler.snapshot();
+    before.git = Some(GitInfo {
+        commit: "a".repeat(40),
+        dirty: false,
+    });
+
+    let mut profiler = TestProfiler::new();
+    hit(&mut profiler, 0, "slow", 100);
+    hit(&mut profiler, 0, "slower", 120);
+    hit(&mut profiler, 0, "faster", 50);
+    let mut after = profiler.snapshot();
+    after.git = Some(GitInfo {
+        commit: "b".repeat(40),
+        dirty: true,
+    });
+
+    let mut out = Vec::new();
+    before.write_comparison(&after, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    assert!(out.starts_with("Commits: aaaaaaaaaaaa..bbbbbbbbbbbb-dirty\n"));
+    assert!(out.contains("Regression triage aaaaaaaaaaaa..bbbbbbbbbbbb-dirty:\n"));
+    assert!(out.ends_with(
+        "  Likely caused by:\n    #1 slow: +90 cycles (+900.00%)\n    #2 slower: +20 cycles (+20.00%)\n"
+    ));
+
+    let row = |name: &str| {
+        out.lines()
+            .find(|line| line.starts_with(name) && line.contains('|'))
+            .unwrap()
+    };
+    assert!(row("slow ").ends_with("| #1"));
+    assert!(row("faster").ends_with("| "));
+}
@@ -0,0 +1,48 @@
+//! Tests of the thread slots recycled from exited threads
+#![cfg(feature = "enable")]
+timeloop::create_profiler!(threads = 4);
+
+/// Get the timers open on every thread of the static profiler
+fn open_timers() -> String {
+    let mut out = Vec::new();
+    TIMELOOP_PROFILER
+        .read()
+        .write_open_timers(&mut out)
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn recycled_slot_drops_the_state_of_the_exited_thread() {
+    // Exit the thread while it is running and with a timer still open
+    let first = std::thread::Builder::new()
+        .name("first".to_string())
+        .spawn(|| {
+            timeloop::start_thread!();
+            std::mem::forget(timeloop::timer_guard!("leaked"));
+            thread_id()
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let (second, open) = std::thread::Builder::new()
+        .name("second".to_string())
+        .spawn(|| {
+            timeloop::start_thread!();
+            let _timer = timeloop::timer_guard!("fresh");
+            let open = open_timers();
+            timeloop::stop_thread!();
+            (thread_id(), open)
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(second, first);
+    // The counters build keeps no stack of open timers
+    if !cfg!(feature = "counters") {
+        assert_eq!(open, format!("Open timers:\n  second ({second}): fresh\n"));
+    }
+    assert_eq!(TIMELOOP_PROFILER.read().thread_name(second), Some("second"));
+}
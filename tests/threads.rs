@@ -0,0 +1,64 @@
+//! Tests of timing from many threads at once through the static profiler
+#![cfg(feature = "enable")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+timeloop::create_profiler!(threads = 16);
+
+const THREADS: usize = 8;
+const HITS: usize = 2_000;
+const TIMERS: [&str; 4] = ["parse", "lookup", "encode", "flush"];
+
+#[test]
+fn threads_register_and_hit_timers_at_once() {
+    let done = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        // Read the profiler throughout through the path of the background reporters. Every
+        // thread exits its inner timer before "shared", so no snapshot may see more
+        // "shared" hits than inner ones.
+        scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                let snapshot = TIMELOOP_PROFILER.snapshot();
+                let shared = snapshot.timers.get("shared").map_or(0, |timer| timer.hits);
+                let inner: u64 = TIMERS
+                    .iter()
+                    .filter_map(|timer| snapshot.timers.get(*timer))
+                    .map(|timer| timer.hits)
+                    .sum();
+                assert!(shared <= inner);
+
+                let _ = TIMELOOP_PROFILER.report();
+                let _ = snapshot.prometheus_text();
+            }
+        });
+
+        let workers: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                scope.spawn(move || {
+                    timeloop::start_thread!();
+                    for hit in 0..HITS {
+                        timeloop::scoped_timer!("shared", category = "io");
+                        timeloop::scoped_timer!(TIMERS[(thread + hit) % TIMERS.len()]);
+                    }
+                    timeloop::stop_thread!();
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+    });
+
+    let expected = (THREADS * HITS) as u64;
+    let snapshot = timeloop::snapshot!();
+    assert_eq!(snapshot.timers["shared"].hits, expected);
+
+    let hits: u64 = TIMERS
+        .iter()
+        .map(|timer| snapshot.timers[*timer].hits)
+        .sum();
+    assert_eq!(hits, expected);
+    assert_eq!(TIMELOOP_PROFILER.read().category("shared"), Some("io"));
+}
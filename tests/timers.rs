@@ -0,0 +1,279 @@
+//! Tests of the timings accumulated by the profiler
+mod common;
+
+use common::{hit, TestProfiler};
+use timeloop::{ShortTimerPolicy, Timer};
+
+// The counters build records every hit flat, without removing the time of its children
+#[cfg(not(feature = "counters"))]
+#[test]
+fn nested_timers_split_exclusive_and_inclusive_time() {
+    let mut profiler = TestProfiler::new();
+    common::nested_hit(&mut profiler, 0, ("parent", 100), ("child", 30));
+
+    let snapshot = profiler.snapshot();
+    let parent = snapshot.timers["parent"];
+    let child = snapshot.timers["child"];
+
+    assert_eq!(parent.exclusive_time, 70);
+    assert_eq!(parent.inclusive_time, 100);
+    assert_eq!(child.exclusive_time, 30);
+    assert_eq!(child.inclusive_time, 30);
+}
+
+#[cfg(not(feature = "counters"))]
+#[test]
+fn recursive_timer_counts_inclusive_time_once() {
+    let mut profiler = TestProfiler::new();
+    common::nested_hit(&mut profiler, 0, ("recurse", 100), ("recurse", 40));
+
+    let timer = profiler.snapshot().timers["recurse"];
+    assert_eq!(timer.hits, 2);
+    assert_eq!(timer.exclusive_time, 100);
+    assert_eq!(timer.inclusive_time, 100);
+}
+
+// The counters build keeps no moments of the hit durations
+#[cfg(not(feature = "counters"))]
+#[test]
+fn welford_moments_match_the_direct_computation() {
+    let samples = [10, 20, 30, 40, 1_000];
+
+    let mut profiler = TestProfiler::new();
+    for cycles in samples {
+        hit(&mut profiler, 0, "timer", cycles);
+    }
+
+    let timer = profiler.snapshot().timers["timer"];
+
+    #[allow(clippy::cast_precision_loss)]
+    let (mean, variance) = {
+        let count = samples.len() as f64;
+        let mean = samples.iter().sum::<u64>() as f64 / count;
+        let squares: f64 = samples.iter().map(|x| (*x as f64 - mean).powi(2)).sum();
+        (mean, squares / (count - 1.0))
+    };
+
+    assert_eq!(timer.hits, 5);
+    assert_eq!(timer.min_cycles, 10);
+    assert_eq!(timer.max_cycles, 1_000);
+    assert!((timer.mean_cycles - mean).abs() < 1e-9);
+    assert!((timer.std_dev_cycles() - variance.sqrt()).abs() < 1e-9);
+    assert!((timer.coefficient_of_variation() - variance.sqrt() / mean).abs() < 1e-12);
+}
+
+#[test]
+fn moments_of_added_timers_match_a_single_timer() {
+    let mut first = TestProfiler::new();
+    let mut second = TestProfiler::new();
+    let mut both = TestProfiler::new();
+
+    for cycles in [5, 7, 9] {
+        hit(&mut first, 0, "timer", cycles);
+        hit(&mut both, 0, "timer", cycles);
+    }
+
+    for cycles in [100, 300] {
+        hit(&mut second, 0, "timer", cycles);
+        hit(&mut both, 0, "timer", cycles);
+    }
+
+    let added: Timer = first.snapshot().timers["timer"] + second.snapshot().timers["timer"];
+    let expected = both.snapshot().timers["timer"];
+
+    assert_eq!(added.hits, expected.hits);
+    assert_eq!(added.min_cycles, 5);
+    assert_eq!(added.max_cycles, 300);
+    assert!((added.mean_cycles - expected.mean_cycles).abs() < 1e-9);
+    assert!((added.m2_cycles - expected.m2_cycles).abs() < 1e-6);
+}
+
+#[test]
+fn threads_are_accumulated_into_one_timer() {
+    let mut profiler = TestProfiler::new();
+    hit(&mut profiler, 0, "timer", 10);
+    hit(&mut profiler, 3, "timer", 20);
+
+    let timer = profiler.snapshot().timers["timer"];
+    assert_eq!(timer.hits, 2);
+    assert_eq!(timer.exclusive_time, 30);
+}
+
+#[test]
+fn spans_end_out_of_order_and_on_other_threads() {
+    let mut profiler = TestProfiler::new();
+
+    let first = profiler.begin(0, "first");
+    let second = profiler.begin(0, "second");
+    assert!(profiler.end(0, first));
+    hit(&mut profiler, 0, "timer", 50);
+    assert!(profiler.end(1, second));
+    assert!(!profiler.end(1, second));
+
+    // The spans never touch the nesting of the guarded timers
+    let mut open = Vec::new();
+    profiler.write_open_timers(&mut open).unwrap();
+    assert!(open.is_empty());
+
+    let snapshot = profiler.snapshot();
+    assert_eq!(snapshot.timers["first"].hits, 1);
+    assert_eq!(snapshot.timers["second"].hits, 1);
+    assert_eq!(snapshot.timers["timer"].exclusive_time, 50);
+    assert_eq!(profiler.get_timer(1, "second").hits, 1);
+}
+
+// The counters build never ignores short hits
+#[cfg(not(feature = "counters"))]
+#[test]
+fn ignored_short_timer_is_explained_in_the_report_header() {
+    let mut profiler = TestProfiler::new().with_short_timer_policy(ShortTimerPolicy::new(500, 3));
+    for cycles in [100, 200, 300, 50] {
+        hit(&mut profiler, 0, "short", cycles);
+    }
+
+    let report = profiler.create_report();
+    assert_eq!(report.ignored_timers, 1);
+    assert_eq!(report.ignored_time, 50);
+
+    let [decision] = report.ignore_decisions.as_slice() else {
+        panic!("Expected a single decision: {:?}", report.ignore_decisions);
+    };
+    assert_eq!(decision.timer, "short");
+    assert_eq!(decision.hits, 3);
+    assert_eq!(decision.avg_cycles, 200);
+    assert_eq!(decision.streak, 3);
+
+    let text = report.to_string();
+    let header = text.lines().take_while(|line| !line.starts_with("TIMER"));
+    assert!(header
+        .into_iter()
+        .any(|line| line.starts_with("Ignored short on thread 0 at hit 3")));
+}
+
+#[cfg(not(feature = "counters"))]
+#[test]
+fn ignored_timer_is_reincluded_once_its_hits_get_long() {
+    let mut profiler = TestProfiler::new().with_short_timer_policy(ShortTimerPolicy::new(500, 3));
+
+    // Three short hits get the child ignored, the next three average twice the threshold
+    for cycles in [100, 100, 100, 50, 2_000, 2_000] {
+        common::nested_hit(&mut profiler, 0, ("parent", 5_000), ("child", cycles));
+    }
+
+    let report = profiler.create_report();
+    assert_eq!(report.ignored_timers, 0);
+    assert_eq!(report.ignored_time, 0);
+    assert!(report.ignore_decisions.is_empty());
+
+    let snapshot = profiler.snapshot();
+    let child = snapshot.timers["child"];
+    assert_eq!(child.hits, 6);
+    assert_eq!(child.exclusive_time, 4_350);
+    assert_eq!(child.min_cycles, 50);
+    assert_eq!(child.max_cycles, 2_000);
+    assert_eq!(snapshot.timers["parent"].exclusive_time, 6 * 5_000 - 4_350);
+}
+
+#[test]
+fn evicted_timer_index_starts_with_fresh_state() {
+    let mut profiler =
+        timeloop::Profiler::<1, 4>::new().with_short_timer_policy(ShortTimerPolicy::new(500, 2));
+
+    let mut hit = |timer, cycles: u64| {
+        let mut open = profiler.enter_timer(0, timer, 0);
+        open.start_time = 1_000;
+        profiler.exit_timer(0, &open, 1_000 + cycles);
+    };
+
+    // The short timer gets ignored and is the least hit timer once the limit is reached
+    for (timer, hits, cycles) in [("short", 3, 100), ("first", 5, 1_000), ("second", 5, 1_000)] {
+        for _ in 0..hits {
+            hit(timer, cycles);
+        }
+    }
+    hit("new", 100);
+
+    let report = profiler.create_report();
+    assert_eq!(report.ignored_timers, 0);
+    assert!(report.ignore_decisions.is_empty());
+    assert_eq!(profiler.snapshot().timers["new"].hits, 1);
+}
+
+// The counters build records neither the stacks nor the histograms
+#[cfg(not(feature = "counters"))]
+#[test]
+fn evicted_timer_data_is_folded_into_the_overflow_timer() {
+    let mut profiler = timeloop::Profiler::<1, 4>::new();
+    profiler.record_stacks = true;
+    profiler.record_log2_histograms = true;
+
+    let mut open = profiler.enter_timer(0, "evicted", 0);
+    open.start_time = 1_000;
+    profiler.count(0, "items", 7);
+    profiler.exit_timer(0, &open, 1_100);
+
+    let mut hit = |timer| {
+        let mut open = profiler.enter_timer(0, timer, 0);
+        open.start_time = 1_000;
+        profiler.exit_timer(0, &open, 2_000);
+    };
+
+    // The least hit timer is evicted once the limit is reached
+    for timer in ["first", "second"] {
+        for _ in 0..5 {
+            hit(timer);
+        }
+    }
+    hit("new");
+
+    assert_eq!(profiler.user_counters("Other"), [("items", 7)]);
+    assert!(profiler.user_counters("evicted").is_empty());
+    assert_eq!(profiler.log2_histogram("Other").total_count(), 1);
+
+    let mut folded = Vec::new();
+    profiler.write_folded_stacks(&mut folded).unwrap();
+    let folded = String::from_utf8(folded).unwrap();
+    assert!(folded.contains("Other 100\n"));
+    assert!(!folded.contains("evicted"));
+}
+
+// The counters build never ignores short hits
+#[cfg(not(feature = "counters"))]
+#[test]
+fn ignored_timer_with_children_is_recorded() {
+    let mut profiler = TestProfiler::new().with_short_timer_policy(ShortTimerPolicy::new(500, 2));
+
+    // The third short hit is ignored
+    for _ in 0..3 {
+        hit(&mut profiler, 0, "short", 100);
+    }
+    assert_eq!(profiler.get_timer(0, "short").hits, 2);
+
+    let mut outer = profiler.enter_timer(0, "outer", 0);
+    outer.start_time = 1_000;
+    let mut short = profiler.enter_timer(0, "short", 0);
+    short.start_time = 1_000;
+    let mut child = profiler.enter_timer(0, "child", 0);
+    child.start_time = 1_000;
+    profiler.exit_timer(0, &child, 1_050);
+    profiler.exit_timer(0, &short, 1_100);
+    profiler.exit_timer(0, &outer, 1_200);
+
+    let short = *profiler.get_timer(0, "short");
+    assert_eq!(short.hits, 3);
+    assert_eq!(short.exclusive_time, 250);
+    assert_eq!(profiler.get_timer(0, "child").exclusive_time, 50);
+    assert_eq!(profiler.get_timer(0, "outer").exclusive_time, 100);
+}
+
+#[test]
+fn timer_stopped_before_its_start_records_no_time() {
+    let mut profiler = TestProfiler::new();
+    let mut open = profiler.enter_timer(0, "early", 0);
+    open.start_time = 2_000;
+    profiler.exit_timer(0, &open, 1_000);
+
+    let timer = profiler.get_timer(0, "early");
+    assert_eq!(timer.hits, 1);
+    assert_eq!(timer.exclusive_time, 0);
+}
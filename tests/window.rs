@@ -0,0 +1,85 @@
+//! Tests of the report of the most recent window of time
+// The counters build neither rolls the window nor records the series
+#![cfg(not(feature = "counters"))]
+mod common;
+
+use std::time::Duration;
+
+use common::{timed_hits, TestProfiler};
+
+#[test]
+fn window_rolls_forward_as_timers_exit() {
+    let mut profiler = TestProfiler::new();
+    profiler.set_window(Duration::from_millis(50));
+
+    timed_hits(&mut profiler, 0, "old", 1);
+
+    // Only the timer exits roll the window, it is never ticked or reported meanwhile
+    for _ in 0..30 {
+        std::thread::sleep(Duration::from_millis(5));
+        timed_hits(&mut profiler, 0, "new", 1);
+    }
+
+    let (report, window) = profiler.window_report();
+    let hits = |name: &str| {
+        report
+            .timers
+            .iter()
+            .find(|timer| timer.name == name)
+            .map_or(0, |timer| timer.hits)
+    };
+
+    assert_eq!(hits("old"), 0);
+    assert!(hits("new") > 0);
+    assert!(window < profiler.window_cycles * 2);
+}
+
+#[test]
+fn series_windows_share_wall_clock_boundaries_across_threads() {
+    let window = Duration::from_millis(20);
+
+    let mut profiler = TestProfiler::new();
+    profiler.record_series(window);
+
+    for _ in 0..5 {
+        timed_hits(&mut profiler, 0, "first", 1);
+        timed_hits(&mut profiler, 2, "second", 1);
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    let mut csv = Vec::new();
+    profiler.write_series_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("window_start_ns,thread,timer,hits,cycles")
+    );
+
+    let rows: Vec<(u128, &str, &str)> = lines
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            (fields[0].parse().unwrap(), fields[1], fields[2])
+        })
+        .collect();
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+
+    for (start, _, _) in &rows {
+        assert_eq!(start % window.as_nanos(), 0);
+        assert!(now - start < Duration::from_secs(10).as_nanos());
+    }
+
+    // Both threads were hit during a common window
+    assert!(rows.iter().any(|(start, thread, timer)| {
+        *thread == "0"
+            && *timer == "\"first\""
+            && rows
+                .iter()
+                .any(|(other, thread, _)| other == start && *thread == "2")
+    }));
+}
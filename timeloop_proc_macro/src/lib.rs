@@ -2,18 +2,99 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::*;
 
+/// Arguments accepted by the `profile` attributes
+#[derive(Default)]
+struct ProfileArgs {
+    /// Only instrument when this `cfg` predicate holds (`cfg = "debug_assertions"`)
+    cfg: Option<Meta>,
+
+    /// Only instrument functions whose name matches this glob (`pattern = "parse_*"`)
+    pattern: Option<String>,
+
+    /// Path to the timeloop crate, for renamed dependencies (`crate = "tl"`)
+    krate: Option<Path>,
+
+    /// Path to the module where `create_profiler!` was called (`profiler = "my_profiler"`)
+    profiler: Option<Path>,
+
+    /// Fail to compile if the timer name is used by another `unique` timer anywhere
+    /// else in the crate (`unique`)
+    unique: bool,
+}
+
+impl ProfileArgs {
+    fn parse(attr: TokenStream, allow_pattern: bool) -> Result<Self> {
+        let mut args = ProfileArgs::default();
+
+        let parser = meta::parser(|meta| {
+            if meta.path.is_ident("cfg") {
+                let predicate: LitStr = meta.value()?.parse()?;
+                args.cfg = Some(predicate.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("crate") {
+                let krate: LitStr = meta.value()?.parse()?;
+                args.krate = Some(krate.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("profiler") {
+                let profiler: LitStr = meta.value()?.parse()?;
+                args.profiler = Some(profiler.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("unique") {
+                args.unique = true;
+                Ok(())
+            } else if allow_pattern && meta.path.is_ident("pattern") {
+                let pattern: LitStr = meta.value()?.parse()?;
+                args.pattern = Some(pattern.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported profile argument"))
+            }
+        });
+
+        parse::Parser::parse(parser, attr)?;
+
+        Ok(args)
+    }
+
+    /// Create the timer statement inserted at the start of the profiled function `func`
+    fn timer_stmt(&self, identifier: &str, func: &Ident) -> Stmt {
+        let krate = self.krate.clone().unwrap_or_else(|| parse_quote!(timeloop));
+
+        let profiler = self.profiler.clone().unwrap_or_else(|| parse_quote!(crate));
+
+        let identifier: Expr = if self.unique {
+            unique_timer_name(&LitStr::new(identifier, func.span()))
+        } else {
+            parse_quote!(#identifier)
+        };
+
+        match &self.cfg {
+            Some(cfg) => parse_quote! {
+                #[cfg(#cfg)]
+                #krate::scoped_timer!(in #profiler, #identifier);
+            },
+            None => parse_quote! {
+                #krate::scoped_timer!(in #profiler, #identifier);
+            },
+        }
+    }
+}
+
 #[proc_macro_attribute]
-pub fn profile(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn profile(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = match ProfileArgs::parse(attr, false) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     let mut func = parse_macro_input!(item as ItemFn);
 
     let func_name = func.sig.ident.to_string();
     let identifier = format!("Fn__{func_name}");
 
-    let scoped_timer = syn::parse_quote! {
-        timeloop::scoped_timer!(#identifier);
-    };
-
-    func.block.stmts.insert(0, scoped_timer);
+    func.block
+        .stmts
+        .insert(0, args.timer_stmt(&identifier, &func.sig.ident));
 
     let new_func = quote! {
         #func
@@ -39,7 +120,12 @@ fn get_impl_name(ty: &Type) -> Option<String> {
 }
 
 #[proc_macro_attribute]
-pub fn profile_impl(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn profile_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = match ProfileArgs::parse(attr, false) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     let mut impl_block = parse_macro_input!(item as ItemImpl);
 
     let type_name = get_impl_name(&impl_block.self_ty).unwrap_or_else(|| "UnknownType".to_string());
@@ -49,11 +135,9 @@ pub fn profile_impl(_attr: TokenStream, item: TokenStream) -> TokenStream {
             let func_name = func.sig.ident.to_string();
             let identifier = format!("{type_name}::{func_name}");
 
-            let scoped_timer = syn::parse_quote! {
-                timeloop::scoped_timer!(#identifier);
-            };
-
-            func.block.stmts.insert(0, scoped_timer);
+            func.block
+                .stmts
+                .insert(0, args.timer_stmt(&identifier, &func.sig.ident));
         }
     }
 
@@ -63,3 +147,166 @@ pub fn profile_impl(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
     TokenStream::from(new_impl)
 }
+
+/// Instrument every `pub fn` (and `pub` method of inherent impls) in an inline module
+/// whose name matches the optional `pattern` glob
+#[proc_macro_attribute]
+pub fn profile_module(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = match ProfileArgs::parse(attr, true) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let mut module = parse_macro_input!(item as ItemMod);
+
+    let pattern = args.pattern.clone().unwrap_or_else(|| "*".to_string());
+
+    let Some((_, items)) = &mut module.content else {
+        return Error::new_spanned(&module, "profile_module requires an inline module")
+            .to_compile_error()
+            .into();
+    };
+
+    instrument_items(items, &args, &pattern);
+
+    let new_module = quote! {
+        #module
+    };
+
+    TokenStream::from(new_module)
+}
+
+/// Insert a timer into every matching public function in `items`, recursing into inline
+/// modules
+fn instrument_items(items: &mut [Item], args: &ProfileArgs, pattern: &str) {
+    for item in items {
+        match item {
+            Item::Fn(func) => {
+                let func_name = func.sig.ident.to_string();
+
+                if is_public(&func.vis)
+                    && !is_profiled(&func.attrs)
+                    && glob_matches(pattern, &func_name)
+                {
+                    let identifier = format!("Fn__{func_name}");
+                    func.block
+                        .stmts
+                        .insert(0, args.timer_stmt(&identifier, &func.sig.ident));
+                }
+            }
+            Item::Impl(impl_block) => {
+                // Trait methods are not `pub` and already profiled impls are skipped
+                if impl_block.trait_.is_some() || is_profiled(&impl_block.attrs) {
+                    continue;
+                }
+
+                let type_name =
+                    get_impl_name(&impl_block.self_ty).unwrap_or_else(|| "UnknownType".to_string());
+
+                for item in &mut impl_block.items {
+                    if let ImplItem::Fn(ref mut func) = item {
+                        let func_name = func.sig.ident.to_string();
+
+                        if is_public(&func.vis)
+                            && !is_profiled(&func.attrs)
+                            && glob_matches(pattern, &func_name)
+                        {
+                            let identifier = format!("{type_name}::{func_name}");
+                            func.block
+                                .stmts
+                                .insert(0, args.timer_stmt(&identifier, &func.sig.ident));
+                        }
+                    }
+                }
+            }
+            Item::Mod(module) => {
+                if let Some((_, items)) = &mut module.content {
+                    instrument_items(items, args, pattern);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Expand to the given string literal timer name, failing to compile if the name is
+/// used by another `unique_name!` or `unique` profiled function in the crate
+#[proc_macro]
+pub fn unique_name(item: TokenStream) -> TokenStream {
+    let name = parse_macro_input!(item as LitStr);
+    let name = unique_timer_name(&name);
+
+    TokenStream::from(quote! { #name })
+}
+
+/// Expand to the timer `name` along with a hidden macro named after it
+///
+/// Exported macros all live at the root of the crate, so a second use of the name
+/// anywhere in the crate is reported by the compiler as the macro being defined multiple
+/// times, pointing at both uses. Unlike a registry kept by the proc macro, the check
+/// holds across incremental builds and long-lived expansion servers.
+fn unique_timer_name(name: &LitStr) -> Expr {
+    let marker = Ident::new(
+        &format!("__timeloop_unique_timer_{}", escape_ident(&name.value())),
+        name.span(),
+    );
+
+    parse_quote_spanned! {name.span()=>
+        {
+            #[doc(hidden)]
+            #[macro_export]
+            macro_rules! #marker {
+                () => {};
+            }
+
+            #name
+        }
+    }
+}
+
+/// Turn `name` into the characters of an identifier, giving every name its own
+/// identifier: `_` is doubled and the bytes of any other non-alphanumeric character are
+/// written as `_` followed by their hex value
+fn escape_ident(name: &str) -> String {
+    let mut escaped = String::new();
+    for byte in name.bytes() {
+        match byte {
+            b'_' => escaped.push_str("__"),
+            byte if byte.is_ascii_alphanumeric() => escaped.push(char::from(byte)),
+            byte => escaped.push_str(&format!("_{byte:02x}")),
+        }
+    }
+    escaped
+}
+
+/// Check if the visibility is any form of `pub`
+fn is_public(vis: &Visibility) -> bool {
+    !matches!(vis, Visibility::Inherited)
+}
+
+/// Check if the item already has one of the `profile` attributes
+fn is_profiled(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident.to_string().starts_with("profile"))
+    })
+}
+
+/// Match `name` against a glob `pattern` supporting `*` and `?`
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[u8], name: &[u8]) -> bool {
+        match (pattern.first(), name.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => {
+                matches(&pattern[1..], name) || (!name.is_empty() && matches(pattern, &name[1..]))
+            }
+            (Some(b'?'), Some(_)) => matches(&pattern[1..], &name[1..]),
+            (Some(p), Some(n)) if p == n => matches(&pattern[1..], &name[1..]),
+            _ => false,
+        }
+    }
+
+    matches(pattern.as_bytes(), name.as_bytes())
+}